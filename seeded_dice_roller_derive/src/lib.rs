@@ -0,0 +1,156 @@
+//! # Seeded Dice Roller Derive
+//! Derive macros for the `seeded_dice_roller` crate, re-exported by it when its `derive` feature
+//! is enabled.
+//!
+//! ## Examples
+//! ### Weighted enums
+//! ```rust
+//! use seeded_dice_roller::*;
+//!
+//! #[derive(RandomPick, Debug, PartialEq)]
+//! enum Rarity {
+//!     #[weight(60)]
+//!     Common,
+//!     #[weight(30)]
+//!     Rare,
+//!     #[weight(10)]
+//!     Epic,
+//! }
+//!
+//! let mut rng = SeededDiceRoller::new("seed", "step");
+//! assert_eq!(Rarity::table().possible_results[2].weight, 10);
+//! assert!(Rarity::pick(&mut rng).is_some());
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenTree;
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, Attribute, DeriveInput, Path};
+
+mod notation;
+mod random_pick;
+mod seeded_random;
+
+/// Implements `RandomPick` for an enum whose variants have no fields.
+///
+/// Variants accept a `#[weight(n)]` attribute, 1 by default. The enum accepts a
+/// `#[roll_method(..)]` attribute giving the method picking its values, either as a string in
+/// the notation of `RollMethod::notation`, such as `"3d6"` or `"gaussian(3)"`, or as an
+/// expression evaluating to a `RollMethod`. Notations are checked when the macro expands, an
+/// invalid one failing to compile. Values are picked with `RollMethod::SimpleRoll` by default.
+///
+/// The generated code refers to the `seeded_dice_roller` crate by that name, unless given
+/// another path with `#[seeded_dice_roller(crate = path)]`, as when the dependency is renamed.
+///
+/// ```rust
+/// use seeded_dice_roller::*;
+///
+/// #[derive(RandomPick, Debug, PartialEq)]
+/// #[roll_method("2d6")]
+/// enum Encounter {
+///     Bandits,
+///     #[weight(9)]
+///     Nothing,
+///     Wolves,
+/// }
+///
+/// let table = Encounter::table();
+/// assert_eq!(table.roll_method, RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 0)));
+/// ```
+#[proc_macro_derive(RandomPick, attributes(weight, roll_method, seeded_dice_roller))]
+pub fn derive_random_pick(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    random_pick::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expands to the `RollMethod` written in the notation of `RollMethod::notation`, without quotes,
+/// failing to compile if it isn't valid. Used by the `table!` macro of `seeded_dice_roller`, which
+/// gives the path of its crate before a `;`: `roll_method!($crate; 2d6)`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn roll_method(input: TokenStream) -> TokenStream {
+    let mut tokens = proc_macro2::TokenStream::from(input).into_iter();
+    let krate: proc_macro2::TokenStream = tokens
+        .by_ref()
+        .take_while(|token| !matches!(token, TokenTree::Punct(p) if p.as_char() == ';'))
+        .collect();
+    notation::written_method(&tokens.collect())
+        .map_or_else(syn::Error::into_compile_error, |method| {
+            notation::method_tokens(&krate, &method)
+        })
+        .into()
+}
+
+/// Returns the path of the `seeded_dice_roller` crate given by a
+/// `#[seeded_dice_roller(crate = path)]` attribute among **attrs**, or `::seeded_dice_roller`
+/// without it.
+pub(crate) fn crate_path(attrs: &[Attribute]) -> syn::Result<proc_macro2::TokenStream> {
+    let mut krate = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("seeded_dice_roller"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("crate") {
+                return Err(meta.error("expected `crate = path`"));
+            }
+            if krate.is_some() {
+                return Err(meta.error("duplicate crate path"));
+            }
+            krate = Some(meta.value()?.parse::<Path>()?);
+            Ok(())
+        })?;
+    }
+    Ok(krate.map_or_else(
+        || quote! { ::seeded_dice_roller },
+        |krate| krate.into_token_stream(),
+    ))
+}
+
+/// Implements `SeededRandom` for a struct, generating each field with a child of the roller named
+/// after the field, so that adding, removing or reordering fields doesn't change the others.
+///
+/// Fields are generated with the `SeededRandom` implementation of their type, unless given one
+/// of these attributes:
+/// - `#[roll("3d6")]` rolls the dice of the formula, converted to the integer type of the field;
+/// - `#[range(1..=10)]` draws a value in the range;
+/// - `#[pick]` picks a value of a type implementing `RandomPick`;
+/// - `#[pick(table = "names")]` picks a value in the `RollToProcess` returned by `names()`.
+///
+/// Like `RandomPick`, the struct accepts `#[seeded_dice_roller(crate = path)]` to refer to the
+/// `seeded_dice_roller` crate by another path.
+///
+/// ```rust
+/// use seeded_dice_roller::*;
+///
+/// fn names() -> RollToProcess<&'static str> {
+///     RollToProcess::new(
+///         SeededDiceRoller::to_possible_results(vec!["Ada", "Brom", "Cid"]),
+///         RollMethod::SimpleRoll,
+///     )
+/// }
+///
+/// #[derive(SeededRandom)]
+/// struct Npc {
+///     #[pick(table = "names")]
+///     name: &'static str,
+///     #[roll("3d6")]
+///     strength: u8,
+///     #[range(1..=10)]
+///     level: u32,
+/// }
+///
+/// let mut rng = SeededDiceRoller::new("seed", "npcs");
+/// let npc = Npc::generate(&mut rng);
+/// assert!((3..=18).contains(&npc.strength));
+/// assert!((1..=10).contains(&npc.level));
+/// ```
+#[proc_macro_derive(SeededRandom, attributes(roll, range, pick, seeded_dice_roller))]
+pub fn derive_seeded_random(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    seeded_random::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
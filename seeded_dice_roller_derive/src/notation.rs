@@ -0,0 +1,115 @@
+//! Parsing of roll formulas and roll methods at expansion time, with the parser the
+//! `seeded_dice_roller` crate uses in its `FromStr` implementations, so that invalid notations
+//! are reported as compile errors.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use seeded_dice_roller_notation::{
+    parse_method, parse_roll, Method, Roll, EXPECTED_METHOD, EXPECTED_ROLL,
+};
+use syn::LitStr;
+
+/// Returns the expression building the `PreparedRoll` of a **roll**, with the `seeded_dice_roller`
+/// crate found at **krate**.
+pub(crate) fn roll_tokens(krate: &TokenStream, roll: &Roll) -> TokenStream {
+    let Roll {
+        dice,
+        die_type,
+        modifier,
+    } = roll;
+    quote! { #krate::PreparedRoll::new(#dice, #die_type, #modifier) }
+}
+
+/// Returns the expression building the `RollMethod` of a **method**, with the
+/// `seeded_dice_roller` crate found at **krate**.
+pub(crate) fn method_tokens(krate: &TokenStream, method: &Method) -> TokenStream {
+    match method {
+        Method::Simple => quote! { #krate::RollMethod::SimpleRoll },
+        Method::Prepared(roll) => {
+            let roll = roll_tokens(krate, roll);
+            quote! { #krate::RollMethod::PreparedRoll(#roll) }
+        }
+        Method::GaussianRoll(dice) => {
+            quote! { #krate::RollMethod::GaussianRoll(#dice) }
+        }
+        Method::Triangular { peak } => {
+            quote! { #krate::RollMethod::Triangular { peak: #peak } }
+        }
+        Method::Exponential { decay } => {
+            quote! { #krate::RollMethod::Exponential { decay: #decay } }
+        }
+        Method::Gaussian { center, spread } => quote! {
+            #krate::RollMethod::Gaussian { center: #center, spread: #spread }
+        },
+        Method::Linear { start, end } => quote! {
+            #krate::RollMethod::Linear { start: #start, end: #end }
+        },
+    }
+}
+
+/// Returns the error reported on the **literal** when it isn't what was **expected**.
+fn invalid(literal: &LitStr, expected: &str) -> syn::Error {
+    syn::Error::new_spanned(
+        literal,
+        format!("invalid \"{}\", expected {expected}", literal.value()),
+    )
+}
+
+/// Returns the roll written in the formula **literal**.
+pub(crate) fn roll(literal: &LitStr) -> syn::Result<Roll> {
+    parse_roll(&literal.value()).ok_or_else(|| invalid(literal, EXPECTED_ROLL))
+}
+
+/// Returns the method written in the notation **literal**.
+pub(crate) fn method(literal: &LitStr) -> syn::Result<Method> {
+    parse_method(&literal.value()).ok_or_else(|| invalid(literal, EXPECTED_METHOD))
+}
+
+/// Returns the method written in the notation **tokens**, as given to `table!` without quotes.
+pub(crate) fn written_method(tokens: &TokenStream) -> syn::Result<Method> {
+    let text = tokens.to_string();
+    parse_method(&text).ok_or_else(|| {
+        syn::Error::new_spanned(
+            tokens,
+            format!("invalid \"{text}\", expected {EXPECTED_METHOD}"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_notations_are_reported_on_the_literal() {
+        let literal: LitStr = syn::parse_quote!("3x6");
+        assert_eq!(
+            method(&literal).unwrap_err().to_string(),
+            "invalid \"3x6\", expected ".to_string() + EXPECTED_METHOD
+        );
+        assert_eq!(
+            roll(&literal).unwrap_err().to_string(),
+            "invalid \"3x6\", expected ".to_string() + EXPECTED_ROLL
+        );
+        assert_eq!(
+            written_method(&quote!(sideways)).unwrap_err().to_string(),
+            "invalid \"sideways\", expected ".to_string() + EXPECTED_METHOD
+        );
+        assert_eq!(
+            written_method(&quote!(triangular(peak = 2))).unwrap(),
+            Method::Triangular { peak: 2 }
+        );
+        assert_eq!(
+            written_method(&quote!(2d6 + 1)).unwrap(),
+            method(&syn::parse_quote!("2d6+1")).unwrap()
+        );
+        assert_eq!(
+            method(&syn::parse_quote!("2d6+1")).unwrap(),
+            Method::Prepared(Roll {
+                dice: 2,
+                die_type: 6,
+                modifier: 1
+            })
+        );
+    }
+}
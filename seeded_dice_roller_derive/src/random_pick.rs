@@ -0,0 +1,132 @@
+//! Expansion of `#[derive(RandomPick)]`.
+
+use crate::{crate_path, notation};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitInt};
+
+/// Returns the implementation of `RandomPick` for the enum given as **input**.
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "RandomPick can only be derived for enums",
+        ));
+    };
+
+    let krate = crate_path(&input.attrs)?;
+    let mut entries = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "RandomPick only supports variants without fields",
+            ));
+        }
+        let ident = &variant.ident;
+        let weight = weight(&variant.attrs)?;
+        entries.push(quote! {
+            #krate::WeightedResult::new(Self::#ident, #weight)
+        });
+    }
+    let method = roll_method(&krate, &input.attrs)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::RandomPick for #name #ty_generics #where_clause {
+            fn table() -> #krate::RollToProcess<Self> {
+                #krate::RollToProcess::new(::std::vec![#(#entries),*], #method)
+            }
+        }
+    })
+}
+
+/// Returns the weight given by the `#[weight(n)]` attribute among **attrs**, or 1 without it.
+fn weight(attrs: &[Attribute]) -> syn::Result<u32> {
+    let mut weight = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("weight")) {
+        if weight.is_some() {
+            return Err(syn::Error::new_spanned(attr, "duplicate weight attribute"));
+        }
+        weight = Some(attr.parse_args::<LitInt>()?.base10_parse::<u32>()?);
+    }
+    Ok(weight.unwrap_or(1))
+}
+
+/// Returns the method given by the `#[roll_method(..)]` attribute among **attrs**, or
+/// `RollMethod::SimpleRoll` without it, with the `seeded_dice_roller` crate found at **krate**.
+fn roll_method(krate: &TokenStream, attrs: &[Attribute]) -> syn::Result<TokenStream> {
+    let mut method = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("roll_method"))
+    {
+        if method.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "duplicate roll_method attribute",
+            ));
+        }
+        method = Some(match attr.parse_args::<Expr>()? {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(notation),
+                ..
+            }) => notation::method_tokens(krate, &notation::method(&notation)?),
+            expression => quote! { #expression },
+        });
+    }
+    Ok(method.unwrap_or_else(|| quote! { #krate::RollMethod::SimpleRoll }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_roll_methods_fail_to_expand() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[roll_method("3x6")]
+            enum Encounter { Bandits, Wolves }
+        };
+        let error = expand(&input).unwrap_err().to_string();
+        assert!(
+            error.starts_with("invalid \"3x6\", expected \"simple\""),
+            "{error}"
+        );
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[roll_method("2d6")]
+            enum Encounter { Bandits, Wolves }
+        };
+        let expanded = expand(&input).unwrap().to_string();
+        assert!(
+            expanded.contains("PreparedRoll :: new (2u16 , 6u32 , 0i32)"),
+            "{expanded}"
+        );
+    }
+
+    #[test]
+    fn the_crate_can_be_renamed() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[seeded_dice_roller(crate = ::dice)]
+            #[roll_method("2d6")]
+            enum Encounter { Bandits, Wolves }
+        };
+        let expanded = expand(&input).unwrap().to_string();
+        assert!(!expanded.contains("seeded_dice_roller"), "{expanded}");
+        assert!(
+            expanded.contains(":: dice :: RollMethod :: PreparedRoll (:: dice :: PreparedRoll"),
+            "{expanded}"
+        );
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[seeded_dice_roller(krate = dice)]
+            enum Encounter { Bandits, Wolves }
+        };
+        assert_eq!(
+            expand(&input).unwrap_err().to_string(),
+            "expected `crate = path`"
+        );
+    }
+}
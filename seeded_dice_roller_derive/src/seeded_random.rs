@@ -0,0 +1,157 @@
+//! Expansion of `#[derive(SeededRandom)]`.
+
+use crate::{crate_path, notation};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{Attribute, Data, DeriveInput, Expr, Field, LitStr, Member, Meta, Path};
+
+/// How a field gets generated.
+enum Generator {
+    /// With the `SeededRandom` implementation of its type.
+    Generate,
+    /// By rolling the dice of the formula.
+    Roll(LitStr),
+    /// By drawing a value in the range.
+    Range(Expr),
+    /// By picking a value with the `RandomPick` implementation of its type.
+    Pick,
+    /// By picking a value in the table returned by the function named by the string.
+    PickIn(LitStr),
+}
+
+/// Returns the implementation of `SeededRandom` for the struct given as **input**.
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SeededRandom can only be derived for structs",
+        ));
+    };
+
+    let krate = crate_path(&input.attrs)?;
+    let mut fields = Vec::new();
+    for (index, field) in data.fields.iter().enumerate() {
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::from(ident.clone()), ident.unraw().to_string()),
+            None => (Member::from(index), index.to_string()),
+        };
+        let value = generate(&krate, field, &name)?;
+        fields.push(quote! {
+            #member: {
+                let mut child = roller.child(#name);
+                #value
+            }
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::SeededRandom for #name #ty_generics #where_clause {
+            fn generate(roller: &mut #krate::SeededDiceRoller) -> Self {
+                let generated = Self { #(#fields),* };
+                roller.gen_u64();
+                generated
+            }
+        }
+    })
+}
+
+/// Returns the expression generating the value of a **field** named **name** from a `child`
+/// roller, with the `seeded_dice_roller` crate found at **krate**.
+fn generate(krate: &TokenStream, field: &Field, name: &str) -> syn::Result<TokenStream> {
+    let ty = &field.ty;
+    Ok(match generator(&field.attrs)? {
+        Generator::Generate => quote! {
+            <#ty as #krate::SeededRandom>::generate(&mut child)
+        },
+        Generator::Roll(formula) => {
+            let roll = notation::roll_tokens(krate, &notation::roll(&formula)?);
+            let overflow = format!("roll {:?} overflows field {name}", formula.value());
+            quote! {
+                <#ty as ::std::convert::TryFrom<i64>>::try_from(child.roll_prepared(&#roll))
+                    .expect(#overflow)
+            }
+        }
+        Generator::Range(range) => quote! { child.gen_range(#range) },
+        Generator::Pick => {
+            let empty = format!("nothing to pick for field {name}");
+            quote! {
+                <#ty as #krate::RandomPick>::pick(&mut child).expect(#empty)
+            }
+        }
+        Generator::PickIn(function) => {
+            let table: Path = function.parse()?;
+            let empty = format!("nothing to pick in {}() for field {name}", function.value());
+            quote! {{
+                let mut table: #krate::RollToProcess<#ty> = #table();
+                child.take_result(&mut table).expect(#empty)
+            }}
+        }
+    })
+}
+
+/// Returns how to generate a field given its **attrs**: `#[roll("3d6")]`, `#[range(1..=10)]`,
+/// `#[pick]`, `#[pick(table = "names")]`, or with its `SeededRandom` implementation without any.
+fn generator(attrs: &[Attribute]) -> syn::Result<Generator> {
+    let mut generator = None;
+    for attr in attrs {
+        let parsed = if attr.path().is_ident("roll") {
+            Generator::Roll(attr.parse_args()?)
+        } else if attr.path().is_ident("range") {
+            Generator::Range(attr.parse_args()?)
+        } else if attr.path().is_ident("pick") {
+            pick(attr)?
+        } else {
+            continue;
+        };
+        if generator.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "a field accepts only one of roll, range and pick",
+            ));
+        }
+        generator = Some(parsed);
+    }
+    Ok(generator.unwrap_or(Generator::Generate))
+}
+
+/// Returns how to pick a field given its `#[pick]` or `#[pick(table = "..")]` **attr**.
+fn pick(attr: &Attribute) -> syn::Result<Generator> {
+    if let Meta::Path(_) = attr.meta {
+        return Ok(Generator::Pick);
+    }
+    let mut table = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("table") {
+            let function: LitStr = meta.value()?.parse()?;
+            function.parse::<Path>()?;
+            table = Some(function);
+            Ok(())
+        } else {
+            Err(meta.error("expected `table = \"function\"`"))
+        }
+    })?;
+    Ok(table.map_or(Generator::Pick, Generator::PickIn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_rolls_fail_to_expand() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Npc {
+                #[roll("3d")]
+                strength: u8,
+            }
+        };
+        let error = expand(&input).unwrap_err().to_string();
+        assert!(
+            error.starts_with("invalid \"3d\", expected a roll"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use seeded_dice_roller::*;
+
+#[derive(RandomPick, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Biome {
+    #[weight(5)]
+    Forest,
+    Desert,
+    #[weight(0)]
+    Volcano,
+    #[weight(2)]
+    Swamp,
+}
+
+#[derive(RandomPick, Debug, PartialEq)]
+#[roll_method("gaussian(3)")]
+enum Size {
+    Tiny,
+    Small,
+    Medium,
+    Large,
+    Huge,
+}
+
+#[derive(RandomPick, Debug, PartialEq)]
+#[roll_method(RollMethod::Exponential { decay: 50 })]
+enum Reward {
+    Coins,
+    Gem,
+}
+
+#[test]
+fn tables_list_every_variant_with_its_weight() {
+    let table = Biome::table();
+    let entries: Vec<(Biome, u32)> = table
+        .possible_results
+        .iter()
+        .map(|entry| (entry.result, entry.weight))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            (Biome::Forest, 5),
+            (Biome::Desert, 1),
+            (Biome::Volcano, 0),
+            (Biome::Swamp, 2),
+        ]
+    );
+    assert_eq!(table.roll_method, RollMethod::SimpleRoll);
+}
+
+#[test]
+fn roll_methods_come_from_notation_or_expressions() {
+    assert_eq!(Size::table().roll_method, RollMethod::GaussianRoll(3));
+    assert_eq!(
+        Reward::table().roll_method,
+        RollMethod::Exponential { decay: 50 }
+    );
+}
+
+#[test]
+fn picks_follow_the_weights() {
+    let mut rng = SeededDiceRoller::new("seed", "step");
+    let picks: Vec<Biome> = (0..800).map(|_| Biome::pick(&mut rng).unwrap()).collect();
+    let count = |biome| picks.iter().filter(|pick| **pick == biome).count();
+    assert_eq!(count(Biome::Volcano), 0);
+    assert!(count(Biome::Forest) > 450 && count(Biome::Forest) < 700);
+    assert!(count(Biome::Desert) < count(Biome::Swamp));
+}
+
+#[test]
+fn picks_are_deterministic() {
+    let mut rng = SeededDiceRoller::new("seed", "step");
+    let picks: Vec<Size> = (0..6).map(|_| Size::pick(&mut rng).unwrap()).collect();
+    assert_eq!(
+        picks,
+        vec![
+            Size::Large,
+            Size::Medium,
+            Size::Large,
+            Size::Medium,
+            Size::Large,
+            Size::Medium
+        ]
+    );
+    assert_eq!(
+        Reward::pick_with(&mut rng, RollMethod::Linear { start: 0, end: 1 }),
+        Some(Reward::Gem)
+    );
+}
@@ -0,0 +1,98 @@
+use seeded_dice_roller::*;
+
+#[derive(RandomPick, Clone, Copy, Debug, PartialEq)]
+enum Class {
+    #[weight(3)]
+    Fighter,
+    Wizard,
+}
+
+fn names() -> RollToProcess<String> {
+    RollToProcess::new(
+        SeededDiceRoller::to_possible_results(vec!["Ada".to_string(), "Brom".to_string()]),
+        RollMethod::SimpleRoll,
+    )
+}
+
+#[derive(SeededRandom, Debug, PartialEq)]
+struct Stats(#[roll("3d6")] u8, #[roll("1d4-1")] i32);
+
+#[derive(SeededRandom, Debug, PartialEq)]
+struct Npc {
+    #[pick(table = "names")]
+    name: String,
+    #[pick]
+    class: Class,
+    #[range(1..=10)]
+    level: u32,
+    stats: Stats,
+    hostile: bool,
+}
+
+/// The fields of [`Npc`] in another order, with a new one.
+#[derive(SeededRandom, Debug)]
+struct ReorderedNpc {
+    hostile: bool,
+    #[range(0.0..1.0)]
+    morale: f64,
+    stats: Stats,
+    #[range(1..=10)]
+    level: u32,
+    #[pick]
+    class: Class,
+    #[pick(table = "names")]
+    name: String,
+}
+
+#[test]
+fn fields_follow_their_attributes() {
+    let mut rng = SeededDiceRoller::new("seed", "npcs");
+    for _ in 0..200 {
+        let npc = Npc::generate(&mut rng);
+        assert!(npc.name == "Ada" || npc.name == "Brom");
+        assert!((1..=10).contains(&npc.level));
+        assert!((3..=18).contains(&npc.stats.0));
+        assert!((0..=3).contains(&npc.stats.1));
+    }
+}
+
+#[test]
+fn fields_keep_their_values_when_reordered() {
+    let mut rng = SeededDiceRoller::new("seed", "npcs");
+    let mut reordered_rng = SeededDiceRoller::new("seed", "npcs");
+    for _ in 0..20 {
+        let npc = Npc::generate(&mut rng);
+        let reordered = ReorderedNpc::generate(&mut reordered_rng);
+        assert_eq!(npc.name, reordered.name);
+        assert_eq!(npc.class, reordered.class);
+        assert_eq!(npc.level, reordered.level);
+        assert_eq!(npc.stats, reordered.stats);
+        assert_eq!(npc.hostile, reordered.hostile);
+        assert!((0.0..1.0).contains(&reordered.morale));
+    }
+}
+
+#[test]
+fn generated_values_are_deterministic() {
+    let mut rng = SeededDiceRoller::new("seed", "npcs");
+    let npcs: Vec<Npc> = (0..2).map(|_| Npc::generate(&mut rng)).collect();
+    assert_eq!(
+        npcs,
+        vec![
+            Npc {
+                name: "Ada".to_string(),
+                class: Class::Wizard,
+                level: 1,
+                stats: Stats(15, 3),
+                hostile: false,
+            },
+            Npc {
+                name: "Brom".to_string(),
+                class: Class::Fighter,
+                level: 8,
+                stats: Stats(10, 3),
+                hostile: true,
+            },
+        ]
+    );
+}
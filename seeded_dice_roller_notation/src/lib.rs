@@ -0,0 +1,202 @@
+//! # Seeded Dice Roller Notation
+//! Parser of the compact text notation of rolls and roll methods, like "3d6+2" or
+//! "gaussian(center=5, spread=2)", shared by the `seeded_dice_roller` crate, which parses data
+//! files with it, and the `seeded_dice_roller_derive` crate, which checks notations when its
+//! macros expand.
+//!
+//! ```rust
+//! use seeded_dice_roller_notation::*;
+//!
+//! assert_eq!(
+//!     parse_method("2d6+1"),
+//!     Some(Method::Prepared(Roll { dice: 2, die_type: 6, modifier: 1 }))
+//! );
+//! assert_eq!(parse_roll("3x6"), None);
+//! ```
+
+#![warn(clippy::all, clippy::pedantic)]
+
+/// What a roll formula should look like.
+pub const EXPECTED_ROLL: &str = "a roll like \"3d6+2\", \"d20\", \"d%\" or \"4\"";
+
+/// What a roll method should look like.
+pub const EXPECTED_METHOD: &str = "\"simple\", a roll like \"3d6+2\", \"gaussian(3)\", \
+    \"triangular(peak=2)\", \"exponential(decay=30)\", \"gaussian(center=5, spread=2)\" \
+    or \"linear(start=1, end=10)\"";
+
+/// A roll written in a formula: **dice** D **`die_type`** + **modifier**, without dice nor die
+/// type for a constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Roll {
+    /// The number of dice rolled.
+    pub dice: u16,
+    /// The number of faces of the dice.
+    pub die_type: u32,
+    /// The number added to the dice.
+    pub modifier: i32,
+}
+
+/// A roll method written in a notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// "simple".
+    Simple,
+    /// A roll like "3d6+2".
+    Prepared(Roll),
+    /// "gaussian(3)", rolling that many dice.
+    GaussianRoll(u16),
+    /// "triangular(peak=2)".
+    Triangular {
+        /// Index of the most likely entry.
+        peak: u16,
+    },
+    /// "exponential(decay=30)".
+    Exponential {
+        /// Percentage of likelihood lost from one entry to the next.
+        decay: u8,
+    },
+    /// "gaussian(center=5, spread=2)".
+    Gaussian {
+        /// Index of the most likely entry.
+        center: u16,
+        /// Standard deviation of the curve, in entries.
+        spread: u16,
+    },
+    /// "linear(start=1, end=10)".
+    Linear {
+        /// Relative chances of the first entry.
+        start: u16,
+        /// Relative chances of the last entry.
+        end: u16,
+    },
+}
+
+/// Returns **text** in lowercase, without the whitespace at its ends or next to a symbol like
+/// "+", "(" or "=". Whitespace between two letters or digits is kept as a single space, so that
+/// "1 0d6" isn't read as "10d6".
+fn compact(text: &str) -> String {
+    let mut compact = String::with_capacity(text.len());
+    let mut spaced = false;
+    for c in text.trim().chars() {
+        if c.is_whitespace() {
+            spaced = true;
+            continue;
+        }
+        if spaced && c.is_alphanumeric() && compact.ends_with(char::is_alphanumeric) {
+            compact.push(' ');
+        }
+        spaced = false;
+        compact.push(c.to_ascii_lowercase());
+    }
+    compact
+}
+
+/// Parses a roll like "3d6+2", "d20", "2D4 - 1", "d%" (one hundred-sided die) or "4" (a
+/// constant).
+#[must_use]
+pub fn parse_roll(text: &str) -> Option<Roll> {
+    let compact = compact(text);
+    if compact.is_empty() {
+        return None;
+    }
+
+    let Some((dice, rest)) = compact.split_once('d') else {
+        return Some(Roll {
+            dice: 0,
+            die_type: 0,
+            modifier: compact.parse().ok()?,
+        });
+    };
+    let dice = if dice.is_empty() {
+        1
+    } else {
+        dice.parse().ok()?
+    };
+    let split = rest.find(['+', '-']).unwrap_or(rest.len());
+    let (die_type, modifier) = rest.split_at(split);
+    let die_type = match die_type {
+        "%" => 100,
+        _ if die_type.starts_with('+') => return None,
+        _ => die_type.parse().ok()?,
+    };
+    let modifier = match modifier.split_at_checked(1) {
+        None => 0,
+        // The operator is the sign of the modifier, which can't have another one.
+        Some((_, digits)) if !digits.starts_with(|c: char| c.is_ascii_digit()) => return None,
+        Some(_) => modifier.parse().ok()?,
+    };
+    if die_type == 0 {
+        return None;
+    }
+    Some(Roll {
+        dice,
+        die_type,
+        modifier,
+    })
+}
+
+/// Parses a method written like "simple", "3d6+2", "gaussian(3)", "triangular(peak=2)",
+/// "exponential(decay=30)", "gaussian(center=5, spread=2)" or "linear(start=1, end=10)", the
+/// names of the arguments being optional when they are given in order. Each parameter must be
+/// given exactly once, and no argument can be given by position after a named one.
+#[must_use]
+pub fn parse_method(text: &str) -> Option<Method> {
+    let compact = compact(text);
+    if compact == "simple" {
+        return Some(Method::Simple);
+    }
+    let Some((name, arguments)) = compact
+        .strip_suffix(')')
+        .and_then(|call| call.split_once('('))
+    else {
+        return parse_roll(text).map(Method::Prepared);
+    };
+
+    let parameters: &[&str] = match name {
+        "gaussian" if !arguments.contains('=') && !arguments.contains(',') => &["dice"],
+        "gaussian" => &["center", "spread"],
+        "triangular" => &["peak"],
+        "exponential" => &["decay"],
+        "linear" => &["start", "end"],
+        _ => return None,
+    };
+    let arguments: Vec<&str> = arguments.split(',').collect();
+    if arguments.len() != parameters.len() {
+        return None;
+    }
+    let mut values = [None::<u16>; 2];
+    let mut named = false;
+    for (position, argument) in arguments.iter().enumerate() {
+        let (index, value) = match argument.split_once('=') {
+            Some((key, value)) => {
+                named = true;
+                (parameters.iter().position(|p| *p == key)?, value)
+            }
+            // Once an argument is named, the next ones can't rely on their position.
+            None if named => return None,
+            None => (position, *argument),
+        };
+        if values[index].replace(value.parse().ok()?).is_some() {
+            return None;
+        }
+    }
+    let [first, second] = values;
+    let first = first?;
+    let second = if parameters.len() > 1 { second? } else { 0 };
+
+    Some(match (name, parameters[0]) {
+        ("gaussian", "dice") => Method::GaussianRoll(first),
+        ("gaussian", _) => Method::Gaussian {
+            center: first,
+            spread: second,
+        },
+        ("triangular", _) => Method::Triangular { peak: first },
+        ("exponential", _) => Method::Exponential {
+            decay: u8::try_from(first).ok()?,
+        },
+        _ => Method::Linear {
+            start: first,
+            end: second,
+        },
+    })
+}
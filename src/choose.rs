@@ -0,0 +1,186 @@
+//! Picks made directly in slices and iterators, without building a [`crate::RollToProcess`].
+
+use crate::{SampleUniform, SeededDiceRoller};
+use log::trace;
+
+impl SeededDiceRoller {
+    /// Returns one of the **items**, each one being as likely to be chosen, or **None** if there
+    /// are no items.
+    ///
+    /// It gives the same result as picking in a table of the same items with
+    /// [`crate::RollMethod::SimpleRoll`], without building that table.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// let colors = ["red", "green", "blue"];
+    /// assert!(colors.contains(rng.choose(&colors).unwrap()));
+    /// assert_eq!(rng.choose::<&str>(&[]), None);
+    /// ```
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        self.choose_weighted(items, |_| 1)
+    }
+
+    /// Returns one of the **items**, each one having the chance to be chosen given by its
+    /// **weight**, or **None** if no item has a weight.
+    ///
+    /// It gives the same result as picking in a table of the same items with
+    /// [`crate::RollMethod::SimpleRoll`], without building that table. Weights adding up to more
+    /// than [`u32::MAX`], which a table can't pick from, are drawn over their `u64` total.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// let loot = [("gold", 10), ("gem", 1), ("cursed ring", 0)];
+    /// let (item, _) = rng.choose_weighted(&loot, |(_, weight)| *weight).unwrap();
+    /// assert_ne!(*item, "cursed ring");
+    /// ```
+    pub fn choose_weighted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> u32,
+    ) -> Option<&'a T> {
+        self.choose_weighted_iter(items, |item| weight(item))
+    }
+
+    /// Returns one of the **items** of an iterator, each one being as likely to be chosen, like
+    /// [`SeededDiceRoller::choose`] does.
+    ///
+    /// The iterator is cloned to go through the items more than once, so it should be cheap to
+    /// clone and give the same items every time.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// let even = rng.choose_iter((1..=20).filter(|n| n % 2 == 0)).unwrap();
+    /// assert_eq!(even % 2, 0);
+    /// ```
+    pub fn choose_iter<I>(&mut self, items: I) -> Option<I::Item>
+    where
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.choose_weighted_iter(items, |_| 1)
+    }
+
+    /// Returns one of the **items** of an iterator, each one having the chance to be chosen
+    /// given by its **weight**, like [`SeededDiceRoller::choose_weighted`] does.
+    ///
+    /// The iterator is cloned to go through the items more than once, so it should be cheap to
+    /// clone and give the same items every time.
+    pub fn choose_weighted_iter<I>(
+        &mut self,
+        items: I,
+        weight: impl Fn(&I::Item) -> u32,
+    ) -> Option<I::Item>
+    where
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        let mut items = items.into_iter();
+        let weights = items.clone().map(|item| weight(&item));
+        if weights.clone().all(|weight| weight == 0) {
+            trace!("   chosen: None, no item has a weight");
+            return None;
+        }
+        let total: u64 = weights.clone().map(u64::from).sum();
+        let index = if weights.clone().nth(1).is_none() {
+            0
+        } else if u32::try_from(total).is_ok() {
+            self.process_simple_roll(weights)?
+        } else {
+            // Too heavy for a table: draw over the whole total instead.
+            let roll = u64::sample_single(0, total, self);
+            let mut last_end = 0;
+            let index = weights.map(u64::from).position(|weight| {
+                last_end += weight;
+                roll < last_end
+            });
+            trace!("   chosen: {index:?}");
+            index?
+        };
+        items.nth(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RollMethod, RollToProcess, WeightedResult};
+
+    #[test]
+    fn choices_match_simple_roll_picks() {
+        let weights = [3, 0, 1, 7, 2];
+        let table = RollToProcess::new(
+            weights
+                .iter()
+                .map(|weight| WeightedResult::new(*weight, *weight))
+                .collect(),
+            RollMethod::SimpleRoll,
+        );
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut table_rng = rng.clone();
+        for _ in 0..200 {
+            assert_eq!(
+                rng.choose_weighted(&weights, |weight| *weight),
+                table_rng.get_result_ref(&table)
+            );
+        }
+        let uniform = RollToProcess::new(
+            SeededDiceRoller::to_possible_results(weights.to_vec()),
+            RollMethod::SimpleRoll,
+        );
+        for _ in 0..200 {
+            assert_eq!(rng.choose(&weights), table_rng.get_result_ref(&uniform));
+        }
+    }
+
+    #[test]
+    fn weights_may_add_up_to_more_than_a_u32() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let items = [u32::MAX, 1, u32::MAX];
+        let mut counts = [0; 3];
+        for _ in 0..200 {
+            let chosen = rng.choose_weighted(&items, |weight| *weight).unwrap();
+            counts[items
+                .iter()
+                .position(|item| std::ptr::eq(item, chosen))
+                .unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[0] > 70 && counts[2] > 70, "{counts:?}");
+    }
+
+    #[test]
+    fn iterators_give_the_same_choices_as_slices() {
+        let items: Vec<u32> = (0..12).collect();
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut iter_rng = rng.clone();
+        for _ in 0..100 {
+            assert_eq!(
+                rng.choose_weighted(&items, |n| n % 3).copied(),
+                iter_rng.choose_weighted_iter(items.iter().copied(), |n| n % 3)
+            );
+            assert_eq!(rng.choose(&items).copied(), iter_rng.choose_iter(0..12_u32));
+        }
+    }
+
+    #[test]
+    fn items_without_weight_are_never_chosen() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        assert_eq!(rng.choose_iter(std::iter::empty::<u8>()), None);
+        assert_eq!(rng.choose_weighted(&[1, 2, 3], |_| 0), None);
+        for _ in 0..50 {
+            assert_eq!(
+                rng.choose_weighted(&[1, 2, 3], |n| u32::from(*n == 2)),
+                Some(&2)
+            );
+        }
+        let mut same_rng = rng.clone();
+        assert_eq!(rng.choose(&["only"]), Some(&"only"));
+        assert_eq!(rng.gen_u32(), same_rng.gen_u32());
+    }
+}
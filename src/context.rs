@@ -0,0 +1,294 @@
+//! Picks whose weights depend on the context they happen in, such as the biome or the time of day
+//! of a spawn, without building a new list of possible results for every context.
+
+use crate::{
+    Edges, OutOfRangeError, OutOfRangePolicy, RollMethod, RollToProcess, SeededDiceRoller,
+    WeightedResult,
+};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// A value only available in contexts having every one of its **tags**.
+///
+/// Values without tags are available in every context.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tagged<T> {
+    /// The value picked.
+    pub value: T,
+    /// The tags a context needs for the value to be picked.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl<T> Tagged<T> {
+    /// Creates a new [`Tagged`] value requiring every one of the **tags**.
+    pub fn new<S: Into<String>>(value: T, tags: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            value,
+            tags: tags.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns true if every tag of the value is part of the **context**.
+    #[must_use]
+    pub fn matches(&self, context: &[&str]) -> bool {
+        self.tags.iter().all(|tag| context.contains(&tag.as_str()))
+    }
+}
+
+impl<T: Display> Display for Tagged<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.value, self.tags.join(", "))
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns the index of the result of a random selection in a **`to_process`** list whose
+    /// weights are replaced by the ones **weight** gives to each entry, for instance depending on
+    /// the level of the player or the time of day. Entries given a weight of 0 are never picked,
+    /// and no result is picked when every entry is given a weight of 0.
+    ///
+    /// The list itself is left untouched and no copy of it is made, so the same table can serve
+    /// every context.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// let monsters = RollToProcess::new(
+    ///     vec![
+    ///         WeightedResult::new(("rat", 1), 10),
+    ///         WeightedResult::new(("wolf", 3), 5),
+    ///         WeightedResult::new(("dragon", 20), 1),
+    ///     ],
+    ///     RollMethod::SimpleRoll,
+    /// );
+    /// let player_level = 4;
+    ///
+    /// let index = rng
+    ///     .get_result_index_in_context(&monsters, |entry| {
+    ///         let (_, level) = entry.result;
+    ///         if level > player_level { 0 } else { entry.weight * level }
+    ///     })
+    ///     .unwrap();
+    /// assert_ne!(monsters.possible_results[index].result.0, "dragon");
+    /// ```
+    pub fn get_result_index_in_context<T>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        weight: impl Fn(&WeightedResult<T>) -> u32,
+    ) -> Option<usize> {
+        self.try_get_result_index_in_context(to_process, weight)
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the index of the result of a random selection in a **`to_process`** list like
+    /// [`SeededDiceRoller::get_result_index_in_context`] does, but reports out of range rolls
+    /// when its [`OutOfRangePolicy`] is [`OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`RollMethod::PreparedRoll`] gives a result outside of
+    /// the list while its policy is [`OutOfRangePolicy::Error`].
+    pub fn try_get_result_index_in_context<T>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        weight: impl Fn(&WeightedResult<T>) -> u32,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        let entries = &to_process.possible_results;
+        self.pick_in_context(
+            &|i| weight(&entries[i]),
+            entries.len(),
+            &to_process.roll_method,
+            to_process.out_of_range,
+        )
+    }
+
+    /// Returns the result of a random selection in a **`to_process`** list whose weights are
+    /// replaced by the ones **weight** gives to each entry, like
+    /// [`SeededDiceRoller::get_result_index_in_context`] does.
+    pub fn get_result_in_context<T: Copy>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        weight: impl Fn(&WeightedResult<T>) -> u32,
+    ) -> Option<T> {
+        self.try_get_result_index_in_context(to_process, weight)
+            .ok()
+            .flatten()
+            .map(|index| to_process.possible_results[index].result)
+    }
+
+    /// Returns the index of the result of a random selection in a **`to_process`** list of
+    /// [`Tagged`] values, only considering the entries whose tags are all part of the
+    /// **context**. The other entries keep their weight.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// let spawns = RollToProcess::new(
+    ///     vec![
+    ///         WeightedResult::new(Tagged::new("deer", ["forest"]), 5),
+    ///         WeightedResult::new(Tagged::new("owl", ["forest", "night"]), 2),
+    ///         WeightedResult::new(Tagged::new("camel", ["desert"]), 5),
+    ///         WeightedResult::new(Tagged::new("crow", Vec::<String>::new()), 1),
+    ///     ],
+    ///     RollMethod::SimpleRoll,
+    /// );
+    ///
+    /// for _ in 0..20 {
+    ///     let index = rng.get_result_index_with_tags(&spawns, &["forest", "day"]).unwrap();
+    ///     assert!(["deer", "crow"].contains(&spawns.possible_results[index].result.value));
+    /// }
+    /// ```
+    pub fn get_result_index_with_tags<T>(
+        &mut self,
+        to_process: &RollToProcess<Tagged<T>>,
+        context: &[&str],
+    ) -> Option<usize> {
+        self.get_result_index_in_context(to_process, |entry| {
+            if entry.result.matches(context) {
+                entry.weight
+            } else {
+                0
+            }
+        })
+    }
+
+    /// Picks the index of one of **length** entries whose weights in the current context are
+    /// given by **weight** using a **method**, handling out of range rolls as the **policy**
+    /// requires. Clamped rolls go to the first or last entry having a weight.
+    pub(crate) fn pick_in_context(
+        &mut self,
+        weight: &dyn Fn(usize) -> u32,
+        length: usize,
+        method: &RollMethod,
+        policy: OutOfRangePolicy,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        if (0..length).all(|i| weight(i) == 0) {
+            trace!("   chosen: None, no entry has a weight in this context");
+            return Ok(None);
+        }
+        self.pick_weighted_with_method(weight, length, method, policy, Edges::Weighted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CopyableRollToProcess, PreparedRoll};
+
+    /// Returns a list of spawns tagged with the biome and the time of day they appear in.
+    fn spawns(method: RollMethod) -> RollToProcess<Tagged<&'static str>> {
+        RollToProcess::new(
+            vec![
+                WeightedResult::new(Tagged::new("bat", ["cave", "night"]), 3),
+                WeightedResult::new(Tagged::new("deer", ["forest"]), 5),
+                WeightedResult::new(Tagged::new("owl", ["forest", "night"]), 2),
+                WeightedResult::new(Tagged::new("crow", Vec::<String>::new()), 1),
+                WeightedResult::new(Tagged::new("camel", ["desert"]), 4),
+            ],
+            method,
+        )
+    }
+
+    #[test]
+    fn tagged_values_match_contexts_having_all_their_tags() {
+        let owl = Tagged::new("owl", ["forest", "night"]);
+        assert!(owl.matches(&["night", "forest", "rain"]));
+        assert!(!owl.matches(&["forest"]));
+        assert!(Tagged::new("crow", Vec::<String>::new()).matches(&[]));
+        assert_eq!(owl.to_string(), "owl [forest, night]");
+    }
+
+    #[test]
+    fn picks_ignore_entries_outside_of_the_context() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        for method in [
+            RollMethod::SimpleRoll,
+            RollMethod::GaussianRoll(3),
+            RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 0)),
+            RollMethod::Linear { start: 1, end: 1 },
+        ] {
+            let spawns = spawns(method);
+            for _ in 0..50 {
+                let index = rng
+                    .get_result_index_with_tags(&spawns, &["forest", "day"])
+                    .unwrap();
+                assert!(
+                    index == 1 || index == 3,
+                    "{method} picked {}",
+                    spawns.possible_results[index].result.value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn picks_without_any_matching_entry_give_none() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut spawns = spawns(RollMethod::SimpleRoll);
+        assert_eq!(rng.get_result_index_in_context(&spawns, |_| 0), None);
+
+        spawns.possible_results.truncate(1);
+        assert_eq!(rng.get_result_index_with_tags(&spawns, &["forest"]), None);
+    }
+
+    #[test]
+    fn adjusted_weights_change_the_distribution() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let table = RollToProcess::new(
+            SeededDiceRoller::to_possible_results(vec![1, 2, 3, 4]),
+            RollMethod::SimpleRoll,
+        );
+        let mut counts = [0; 4];
+        for _ in 0..10_000 {
+            let index = rng
+                .get_result_index_in_context(&table, |entry| entry.weight * entry.result)
+                .unwrap();
+            counts[index] += 1;
+        }
+        assert!(counts.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(counts[3] > 3_700 && counts[3] < 4_300);
+    }
+
+    #[test]
+    fn contextual_picks_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let spawns = spawns(RollMethod::SimpleRoll);
+        let picks: Vec<&str> = [["cave", "night"], ["forest", "night"], ["desert", "day"]]
+            .iter()
+            .flat_map(|context| {
+                (0..3)
+                    .map(|_| {
+                        let index = rng.get_result_index_with_tags(&spawns, context).unwrap();
+                        spawns.possible_results[index].result.value
+                    })
+                    .collect::<Vec<&str>>()
+            })
+            .collect();
+        assert_eq!(
+            picks,
+            vec!["crow", "bat", "bat", "deer", "deer", "deer", "camel", "camel", "camel"]
+        );
+    }
+
+    #[test]
+    fn clamped_rolls_skip_entries_outside_of_the_context() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        // 3d6-5 rolls from -2 to 13, often below or above the 2 entries having a weight.
+        let table = CopyableRollToProcess::new(
+            SeededDiceRoller::to_copyable_possible_results(vec!["a", "b", "c", "d"]),
+            RollMethod::PreparedRoll(PreparedRoll::new(3, 6, -5)),
+        )
+        .with_out_of_range(OutOfRangePolicy::Clamp);
+        for _ in 0..50 {
+            let result = rng.get_result_in_context(&table, |entry| {
+                u32::from(entry.result == "b" || entry.result == "c")
+            });
+            assert!(result == Some("b") || result == Some("c"));
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Random but fair sequences: decks of cards drawn one at a time and shuffle bags giving every
+//! one of their items once before any of them repeats.
+
+use crate::{SeededDiceRoller, WeightedResult};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+/// A pile of cards drawn from its top, alongside the pile of the cards discarded since its last
+/// reshuffle.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let mut deck = Deck::shuffled(vec!["ace", "king", "queen", "jack"], &mut rng);
+///
+/// let card = deck.draw().unwrap();
+/// deck.discard(card);
+/// assert_eq!((deck.len(), deck.discards().len()), (3, 1));
+///
+/// deck.reshuffle_discards(&mut rng);
+/// assert_eq!((deck.len(), deck.discards().len()), (4, 0));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Deck<T> {
+    /// The cards left to draw, the top of the pile first.
+    cards: VecDeque<T>,
+    /// The discarded cards, the last discarded one last.
+    discards: Vec<T>,
+}
+
+impl<T> Deck<T> {
+    /// Creates a new [`Deck`] of **cards** in the given order, the first one on top.
+    #[must_use]
+    pub fn new(cards: Vec<T>) -> Self {
+        Self {
+            cards: cards.into(),
+            discards: Vec::new(),
+        }
+    }
+
+    /// Creates a new [`Deck`] of **cards** shuffled by the **roller**.
+    pub fn shuffled(mut cards: Vec<T>, roller: &mut SeededDiceRoller) -> Self {
+        roller.shuffle(&mut cards);
+        Self::new(cards)
+    }
+
+    /// Creates a new [`Deck`] holding as many copies of each result of the **entries** as its
+    /// weight, shuffled by the **roller**.
+    pub fn from_weighted(entries: &[WeightedResult<T>], roller: &mut SeededDiceRoller) -> Self
+    where
+        T: Clone,
+    {
+        Self::shuffled(weighted_copies(entries), roller)
+    }
+
+    /// Returns the cards left to draw, the top of the pile first.
+    pub fn cards(&self) -> impl Iterator<Item = &T> {
+        self.cards.iter()
+    }
+
+    /// Returns the discarded cards, the last discarded one last.
+    #[must_use]
+    pub fn discards(&self) -> &[T] {
+        &self.discards
+    }
+
+    /// Returns how many cards are left to draw.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Returns true if there is no card left to draw.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Removes the card on top of the pile and returns it, or None if the pile is empty.
+    pub fn draw(&mut self) -> Option<T> {
+        self.cards.pop_front()
+    }
+
+    /// Removes up to **count** cards from the top of the pile and returns them, the top one
+    /// first.
+    pub fn draw_many(&mut self, count: usize) -> Vec<T> {
+        let count = count.min(self.cards.len());
+        self.cards.drain(..count).collect()
+    }
+
+    /// Returns the card on top of the pile without drawing it.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.cards.front()
+    }
+
+    /// Returns up to **count** cards from the top of the pile without drawing them, the top one
+    /// first.
+    pub fn peek_many(&self, count: usize) -> impl Iterator<Item = &T> {
+        self.cards.iter().take(count)
+    }
+
+    /// Puts a **card** on the discard pile.
+    pub fn discard(&mut self, card: T) {
+        self.discards.push(card);
+    }
+
+    /// Puts a **card** on top of the pile.
+    pub fn put_on_top(&mut self, card: T) {
+        self.cards.push_front(card);
+    }
+
+    /// Puts a **card** at the bottom of the pile.
+    pub fn put_at_bottom(&mut self, card: T) {
+        self.cards.push_back(card);
+    }
+
+    /// Puts a **card** anywhere in the pile, every position being as likely.
+    pub fn insert_at_random(&mut self, card: T, roller: &mut SeededDiceRoller) {
+        let position = roller.draw_index(self.cards.len() + 1);
+        self.cards.insert(position, card);
+    }
+
+    /// Shuffles the discarded cards and puts them at the bottom of the pile, leaving the cards
+    /// left to draw in the same order.
+    pub fn reshuffle_discards(&mut self, roller: &mut SeededDiceRoller) {
+        roller.shuffle(&mut self.discards);
+        self.cards.extend(self.discards.drain(..));
+    }
+
+    /// Shuffles the discarded cards back into the pile, alongside the cards left to draw.
+    pub fn reshuffle_all(&mut self, roller: &mut SeededDiceRoller) {
+        let mut cards: Vec<T> = self.cards.drain(..).collect();
+        cards.append(&mut self.discards);
+        roller.shuffle(&mut cards);
+        self.cards = cards.into();
+    }
+}
+
+impl<T> Display for Deck<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Deck {{ {} cards, {} discards }}",
+            self.cards.len(),
+            self.discards.len()
+        )
+    }
+}
+
+/// A bag giving each of its items once, in a shuffled order, before refilling itself with all of
+/// them, like the "7-bag" of falling block games.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let mut bag = ShuffleBag::new(vec!['I', 'J', 'L', 'O', 'S', 'T', 'Z']);
+///
+/// let mut round: Vec<char> = (0..7).map(|_| bag.draw(&mut rng).unwrap()).collect();
+/// round.sort_unstable();
+/// assert_eq!(round, vec!['I', 'J', 'L', 'O', 'S', 'T', 'Z']);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ShuffleBag<T> {
+    /// The items left to draw in the current round, and the ones already drawn.
+    deck: Deck<T>,
+}
+
+impl<T: Clone> ShuffleBag<T> {
+    /// Creates a new [`ShuffleBag`] of **items**, shuffled when the first of them is drawn.
+    #[must_use]
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            deck: Deck {
+                cards: VecDeque::new(),
+                discards: items,
+            },
+        }
+    }
+
+    /// Creates a new [`ShuffleBag`] holding as many copies of each result of the **entries** as
+    /// its weight, so that each round gives every result exactly that many times.
+    #[must_use]
+    pub fn from_weighted(entries: &[WeightedResult<T>]) -> Self {
+        Self::new(weighted_copies(entries))
+    }
+
+    /// Returns the items left to draw in the current round and the ones already drawn.
+    #[must_use]
+    pub fn deck(&self) -> &Deck<T> {
+        &self.deck
+    }
+
+    /// Returns how many items the bag holds, drawn or not.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.deck.len() + self.deck.discards.len()
+    }
+
+    /// Returns true if the bag holds no item.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how many items are left to draw before the bag refills itself.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.deck.len()
+    }
+
+    /// Draws the next item of the current round, refilling and shuffling the bag first if every
+    /// item has already been drawn. Returns None if the bag is empty.
+    pub fn draw(&mut self, roller: &mut SeededDiceRoller) -> Option<T> {
+        if self.deck.is_empty() {
+            self.deck.reshuffle_discards(roller);
+        }
+        let item = self.deck.draw()?;
+        self.deck.discard(item.clone());
+        Some(item)
+    }
+
+    /// Returns the next item of the current round without drawing it, or None if the bag has to
+    /// be refilled first.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.deck.peek()
+    }
+
+    /// Adds an **item** to the bag, at a random position among the items left to draw in the
+    /// current round.
+    pub fn insert_at_random(&mut self, item: T, roller: &mut SeededDiceRoller) {
+        self.deck.insert_at_random(item, roller);
+    }
+
+    /// Puts every item drawn in the current round back and shuffles the bag.
+    pub fn refill(&mut self, roller: &mut SeededDiceRoller) {
+        self.deck.reshuffle_all(roller);
+    }
+}
+
+impl<T> Display for ShuffleBag<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ShuffleBag {{ {} items, {} remaining }}",
+            self.deck.cards.len() + self.deck.discards.len(),
+            self.deck.cards.len()
+        )
+    }
+}
+
+/// Returns as many copies of each result of the **entries** as its weight, in order.
+fn weighted_copies<T: Clone>(entries: &[WeightedResult<T>]) -> Vec<T> {
+    entries
+        .iter()
+        .flat_map(|entry| std::iter::repeat_n(&entry.result, entry.weight as usize))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decks_draw_from_the_top() {
+        let mut deck = Deck::new(vec![1, 2, 3, 4, 5]);
+        assert_eq!(deck.peek(), Some(&1));
+        assert_eq!(deck.draw(), Some(1));
+        assert_eq!(deck.peek_many(2).collect::<Vec<_>>(), vec![&2, &3]);
+        assert_eq!(deck.draw_many(3), vec![2, 3, 4]);
+        deck.put_on_top(0);
+        deck.put_at_bottom(6);
+        assert_eq!(deck.draw_many(9), vec![0, 5, 6]);
+        assert_eq!(deck.draw(), None);
+        assert!(deck.is_empty());
+    }
+
+    #[test]
+    fn decks_shuffle_their_discards_back() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut deck = Deck::shuffled((0..10).collect(), &mut rng);
+        let drawn = deck.draw_many(4);
+        let left: Vec<u32> = deck.cards().copied().collect();
+        for card in drawn {
+            deck.discard(card);
+        }
+
+        deck.reshuffle_discards(&mut rng);
+        let cards: Vec<u32> = deck.cards().copied().collect();
+        assert_eq!(cards[..6], left[..]);
+        assert!(deck.discards().is_empty());
+
+        let card = deck.draw().unwrap();
+        deck.discard(card);
+        deck.reshuffle_all(&mut rng);
+        let mut cards: Vec<u32> = deck.cards().copied().collect();
+        cards.sort_unstable();
+        assert_eq!(cards, (0..10).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn decks_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut deck = Deck::shuffled(vec!["a", "b", "c", "d", "e"], &mut rng);
+        deck.insert_at_random("f", &mut rng);
+        assert_eq!(
+            deck.cards().copied().collect::<Vec<&str>>(),
+            vec!["c", "e", "d", "f", "b", "a"]
+        );
+    }
+
+    #[test]
+    fn weighted_fills_copy_each_result_as_many_times_as_its_weight() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let entries = vec![
+            WeightedResult::new("common", 3),
+            WeightedResult::new("never", 0),
+            WeightedResult::new("rare", 1),
+        ];
+        let deck = Deck::from_weighted(&entries, &mut rng);
+        let mut cards: Vec<&str> = deck.cards().copied().collect();
+        cards.sort_unstable();
+        assert_eq!(cards, vec!["common", "common", "common", "rare"]);
+
+        let mut bag = ShuffleBag::from_weighted(&entries);
+        assert_eq!(bag.len(), 4);
+        let rares = (0..40)
+            .filter(|_| bag.draw(&mut rng) == Some("rare"))
+            .count();
+        assert_eq!(rares, 10);
+    }
+
+    #[test]
+    fn shuffle_bags_give_every_item_once_per_round() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut bag = ShuffleBag::new((0..7).collect::<Vec<u8>>());
+        assert_eq!(bag.peek(), None);
+        for _ in 0..5 {
+            let mut round: Vec<u8> = (0..7).map(|_| bag.draw(&mut rng).unwrap()).collect();
+            round.sort_unstable();
+            assert_eq!(round, (0..7).collect::<Vec<u8>>());
+            assert_eq!(bag.remaining(), 0);
+        }
+
+        bag.draw(&mut rng);
+        bag.insert_at_random(7, &mut rng);
+        assert_eq!((bag.len(), bag.remaining()), (8, 7));
+        bag.refill(&mut rng);
+        assert_eq!(bag.remaining(), 8);
+        assert_eq!(ShuffleBag::<u8>::new(Vec::new()).draw(&mut rng), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn saved_bags_keep_their_round() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut bag = ShuffleBag::new(vec!["a", "b", "c", "d"]);
+        bag.draw(&mut rng);
+        let saved = serde_json::to_string(&bag).unwrap();
+        let mut loaded: ShuffleBag<&str> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded, bag);
+
+        let mut other_rng = rng.clone();
+        for _ in 0..10 {
+            assert_eq!(bag.draw(&mut rng), loaded.draw(&mut other_rng));
+        }
+    }
+}
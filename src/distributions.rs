@@ -0,0 +1,406 @@
+//! Continuous and discrete distributions that [`SeededDiceRoller`] can sample from.
+//!
+//! Every sampler is implemented in this crate so that its behaviour only depends on this crate's
+//! version:
+//! - normal samples use Marsaglia's polar method, discarding the second value of each pair,
+//! - log-normal samples are the exponential of a normal sample,
+//! - exponential samples invert the cumulative distribution function,
+//! - triangular samples invert the cumulative distribution function of each side of the mode,
+//! - gamma samples use Marsaglia and Tsang's method, boosted by a uniform draw for shapes
+//!   below one,
+//! - beta samples are built from two gamma samples,
+//! - Poisson samples multiply uniform draws for means below 12, and otherwise reduce the mean
+//!   using gamma and binomial samples as described by Knuth,
+//! - binomial samples count successes for 32 trials or less, and otherwise split the trials
+//!   using beta samples as described by Knuth.
+//!
+//! Square roots are exactly rounded on every platform, but logarithms, exponentials and powers
+//! come from the platform's math library, which may in rare cases round the last bit of a value
+//! differently from one platform to another. The samples of these distributions are therefore
+//! not covered by [`SeededDiceRoller::new_portable`]: a value may rarely differ in its last bits
+//! across platforms, and a Poisson or binomial sample built from such values may then differ as
+//! well. Round the samples that must match everywhere, or draw them with dice.
+
+use crate::SeededDiceRoller;
+use log::trace;
+
+/// The number of trials under which binomial samples are counted one by one.
+const BINOMIAL_DIRECT_TRIALS: u64 = 32;
+/// The mean under which Poisson samples multiply uniform draws.
+const POISSON_DIRECT_MEAN: f64 = 12.0;
+/// The highest mean Poisson samples can be drawn with, keeping them well within a [`u64`].
+const POISSON_MAX_MEAN: f64 = 4_611_686_018_427_387_904.0;
+
+impl SeededDiceRoller {
+    /// Returns a value in `(0, 1]`, suitable for logarithms.
+    fn draw_f64_open_zero(&mut self) -> f64 {
+        1.0 - self.draw_f64()
+    }
+
+    /// Returns a sample of the standard normal distribution.
+    fn draw_standard_normal(&mut self) -> f64 {
+        loop {
+            let u = 2.0 * self.draw_f64() - 1.0;
+            let v = 2.0 * self.draw_f64() - 1.0;
+            let s = u * u + v * v;
+            if s > 0.0 && s < 1.0 {
+                return u * (-2.0 * s.ln() / s).sqrt();
+            }
+        }
+    }
+
+    /// Returns a sample of the gamma distribution with the given **shape** and a scale of 1.
+    #[allow(clippy::many_single_char_names)]
+    fn draw_standard_gamma(&mut self, shape: f64) -> f64 {
+        if shape < 1.0 {
+            let boost = self.draw_f64_open_zero().powf(1.0 / shape);
+            return self.draw_standard_gamma(shape + 1.0) * boost;
+        }
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+        loop {
+            let x = self.draw_standard_normal();
+            let v = 1.0 + c * x;
+            if v <= 0.0 {
+                continue;
+            }
+            let v = v * v * v;
+            let u = self.draw_f64_open_zero();
+            let x_squared = x * x;
+            if u < 1.0 - 0.0331 * x_squared * x_squared
+                || u.ln() < 0.5 * x_squared + d * (1.0 - v + v.ln())
+            {
+                return d * v;
+            }
+        }
+    }
+
+    /// Returns a sample of the beta distribution with the given shapes.
+    fn draw_beta(&mut self, alpha: f64, beta: f64) -> f64 {
+        let x = self.draw_standard_gamma(alpha);
+        let y = self.draw_standard_gamma(beta);
+        x / (x + y)
+    }
+
+    /// Returns a sample of the binomial distribution with the given number of **trials**.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn draw_binomial(&mut self, trials: u64, p: f64) -> u64 {
+        if trials == 0 || p <= 0.0 {
+            return 0;
+        }
+        if p > 0.5 {
+            return trials - self.draw_binomial(trials, 1.0 - p);
+        }
+        if trials <= BINOMIAL_DIRECT_TRIALS {
+            return (0..trials).map(|_| u64::from(self.draw_f64() < p)).sum();
+        }
+        let a = 1 + trials / 2;
+        let b = trials - a + 1;
+        let x = self.draw_beta(a as f64, b as f64);
+        if x >= p {
+            self.draw_binomial(a - 1, p / x)
+        } else {
+            a + self.draw_binomial(b - 1, (p - x) / (1.0 - x))
+        }
+    }
+
+    /// Returns a sample of the Poisson distribution with the given **mean**.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn draw_poisson(&mut self, mean: f64) -> u64 {
+        let mut mean = mean;
+        let mut count = 0;
+        while mean >= POISSON_DIRECT_MEAN {
+            let m = (mean * 7.0 / 8.0).floor() as u64;
+            let x = self.draw_standard_gamma(m as f64);
+            if x >= mean {
+                return count + self.draw_binomial(m - 1, mean / x);
+            }
+            count += m;
+            mean -= x;
+        }
+        if mean <= 0.0 {
+            return count;
+        }
+        let limit = (-mean).exp();
+        let mut product = self.draw_f64_open_zero();
+        while product > limit {
+            count += 1;
+            product *= self.draw_f64_open_zero();
+        }
+        count
+    }
+
+    /// Returns a sample of the normal distribution with the given **mean** and standard
+    /// deviation **sd**.
+    ///
+    /// # Panics
+    /// Panics if **sd** is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use seeded_dice_roller::SeededDiceRoller;
+    /// let height = SeededDiceRoller::new("seed", "npc_height").gen_normal(175.0, 7.5);
+    /// ```
+    pub fn gen_normal(&mut self, mean: f64, sd: f64) -> f64 {
+        assert!(
+            sd >= 0.0 && sd.is_finite(),
+            "gen_normal: sd must be positive and finite"
+        );
+        let gen = mean + sd * self.draw_standard_normal();
+        trace!("   normal: N({mean}, {sd}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the log-normal distribution, whose logarithm follows a normal
+    /// distribution with the given **mu** mean and **sigma** standard deviation.
+    ///
+    /// # Panics
+    /// Panics if **sigma** is negative or not finite.
+    pub fn gen_log_normal(&mut self, mu: f64, sigma: f64) -> f64 {
+        assert!(
+            sigma >= 0.0 && sigma.is_finite(),
+            "gen_log_normal: sigma must be positive and finite"
+        );
+        let gen = (mu + sigma * self.draw_standard_normal()).exp();
+        trace!("log-normal: LogN({mu}, {sigma}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the exponential distribution with the given **rate**, which is the
+    /// inverse of its mean. Useful to get the time until the next spawn.
+    ///
+    /// # Panics
+    /// Panics if **rate** is not strictly positive.
+    pub fn gen_exponential(&mut self, rate: f64) -> f64 {
+        assert!(
+            rate > 0.0,
+            "gen_exponential: rate must be strictly positive"
+        );
+        let gen = -self.draw_f64_open_zero().ln() / rate;
+        trace!("exponential: Exp({rate}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the triangular distribution going from **min** to **max**, with the
+    /// highest density at **mode**.
+    ///
+    /// # Panics
+    /// Panics if **mode** isn't between **min** and **max**.
+    pub fn gen_triangular(&mut self, min: f64, max: f64, mode: f64) -> f64 {
+        assert!(
+            min <= mode && mode <= max,
+            "gen_triangular: mode must be between min and max"
+        );
+        let u = self.draw_f64();
+        let range = max - min;
+        let gen = if range <= 0.0 {
+            min
+        } else if u < (mode - min) / range {
+            min + (u * range * (mode - min)).sqrt()
+        } else {
+            max - ((1.0 - u) * range * (max - mode)).sqrt()
+        };
+        trace!("triangular: Tri({min}, {max}, {mode}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the gamma distribution with the given **shape** and **scale**.
+    ///
+    /// # Panics
+    /// Panics if **shape** or **scale** are not strictly positive.
+    pub fn gen_gamma(&mut self, shape: f64, scale: f64) -> f64 {
+        assert!(
+            shape > 0.0 && scale > 0.0,
+            "gen_gamma: shape and scale must be strictly positive"
+        );
+        let gen = self.draw_standard_gamma(shape) * scale;
+        trace!("    gamma: Gamma({shape}, {scale}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the beta distribution with the given **alpha** and **beta** shapes,
+    /// which is a value between 0 and 1.
+    ///
+    /// # Panics
+    /// Panics if **alpha** or **beta** are not strictly positive.
+    pub fn gen_beta(&mut self, alpha: f64, beta: f64) -> f64 {
+        assert!(
+            alpha > 0.0 && beta > 0.0,
+            "gen_beta: alpha and beta must be strictly positive"
+        );
+        let gen = self.draw_beta(alpha, beta);
+        trace!("     beta: Beta({alpha}, {beta}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the Poisson distribution with the given **mean**, which is the
+    /// number of events happening in an interval when **mean** of them are expected.
+    ///
+    /// # Panics
+    /// Panics if **mean** is negative or above 2^62.
+    pub fn gen_poisson(&mut self, mean: f64) -> u64 {
+        assert!(
+            (0.0..=POISSON_MAX_MEAN).contains(&mean),
+            "gen_poisson: mean must be positive and at most 2^62"
+        );
+        let gen = self.draw_poisson(mean);
+        trace!("  poisson: Poisson({mean}) = {gen}");
+        gen
+    }
+
+    /// Returns a sample of the binomial distribution, which is the number of successes among
+    /// **trials** attempts that each succeed with a probability of **p**.
+    ///
+    /// # Panics
+    /// Panics if **p** is not between 0 and 1.
+    pub fn gen_binomial(&mut self, trials: u64, p: f64) -> u64 {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "gen_binomial: p must be between 0 and 1"
+        );
+        let gen = self.draw_binomial(trials, p);
+        trace!(" binomial: B({trials}, {p}) = {gen}");
+        gen
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::float_cmp,
+    clippy::cast_precision_loss,
+    clippy::unreadable_literal
+)]
+mod tests {
+    use super::*;
+
+    /// Returns the mean and variance of the given samples.
+    fn mean_and_variance(samples: &[f64]) -> (f64, f64) {
+        let count = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / count;
+        let variance = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / count;
+        (mean, variance)
+    }
+
+    #[test]
+    fn distributions_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        assert_eq!(rng.gen_normal(175.0, 7.5), 170.12858786090183);
+        assert_eq!(rng.gen_log_normal(0.0, 0.5), 1.2018387264616457);
+        assert_eq!(rng.gen_exponential(0.2), 1.3997143566032948);
+        assert_eq!(rng.gen_triangular(1.0, 10.0, 3.0), 4.494387067988357);
+        assert_eq!(rng.gen_gamma(0.5, 2.0), 1.6733467611088002);
+        assert_eq!(rng.gen_gamma(9.0, 0.5), 2.825509026630019);
+        assert_eq!(rng.gen_beta(2.0, 5.0), 0.7905815794730994);
+        assert_eq!(rng.gen_poisson(3.5), 2);
+        assert_eq!(rng.gen_poisson(250.0), 238);
+        assert_eq!(rng.gen_binomial(10, 0.3), 2);
+        assert_eq!(rng.gen_binomial(10_000, 0.75), 7524);
+    }
+
+    #[test]
+    fn continuous_distributions_have_the_expected_moments() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let samples: Vec<f64> = (0..50_000).map(|_| rng.gen_normal(10.0, 2.0)).collect();
+        let (mean, variance) = mean_and_variance(&samples);
+        assert!((mean - 10.0).abs() < 0.05, "Mean was: {mean}");
+        assert!((variance - 4.0).abs() < 0.1, "Variance was: {variance}");
+
+        let samples: Vec<f64> = (0..50_000).map(|_| rng.gen_exponential(4.0)).collect();
+        let (mean, _) = mean_and_variance(&samples);
+        assert!((mean - 0.25).abs() < 0.01, "Mean was: {mean}");
+
+        let samples: Vec<f64> = (0..50_000)
+            .map(|_| rng.gen_triangular(0.0, 9.0, 3.0))
+            .collect();
+        let (mean, _) = mean_and_variance(&samples);
+        assert!((mean - 4.0).abs() < 0.05, "Mean was: {mean}");
+        assert!(samples.iter().all(|s| (0.0..=9.0).contains(s)));
+
+        for shape in [0.3, 1.0, 4.5] {
+            let samples: Vec<f64> = (0..50_000).map(|_| rng.gen_gamma(shape, 2.0)).collect();
+            let (mean, variance) = mean_and_variance(&samples);
+            assert!((mean - shape * 2.0).abs() < 0.1, "Mean was: {mean}");
+            assert!(
+                (variance - shape * 4.0).abs() < 0.1 * shape * 4.0,
+                "Variance was: {variance}"
+            );
+        }
+
+        let samples: Vec<f64> = (0..50_000).map(|_| rng.gen_beta(2.0, 6.0)).collect();
+        let (mean, _) = mean_and_variance(&samples);
+        assert!((mean - 0.25).abs() < 0.01, "Mean was: {mean}");
+        assert!(samples.iter().all(|s| (0.0..=1.0).contains(s)));
+
+        let samples: Vec<f64> = (0..50_000).map(|_| rng.gen_log_normal(0.0, 0.5)).collect();
+        let (mean, _) = mean_and_variance(&samples);
+        assert!((mean - 0.125f64.exp()).abs() < 0.02, "Mean was: {mean}");
+    }
+
+    #[test]
+    fn discrete_distributions_have_the_expected_moments() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        for mean in [0.5, 7.0, 40.0, 1_000.0] {
+            let samples: Vec<f64> = (0..20_000).map(|_| rng.gen_poisson(mean) as f64).collect();
+            let (sample_mean, variance) = mean_and_variance(&samples);
+            assert!(
+                (sample_mean - mean).abs() < 0.03 * mean,
+                "Mean was: {sample_mean}"
+            );
+            assert!(
+                (variance - mean).abs() < 0.06 * mean,
+                "Variance was: {variance}"
+            );
+        }
+        for (trials, p) in [(20, 0.1), (500, 0.5), (100_000, 0.02), (3_000, 0.9)] {
+            let samples: Vec<f64> = (0..20_000)
+                .map(|_| rng.gen_binomial(trials, p) as f64)
+                .collect();
+            let expected_mean = trials as f64 * p;
+            let expected_variance = expected_mean * (1.0 - p);
+            let (mean, variance) = mean_and_variance(&samples);
+            assert!(
+                (mean - expected_mean).abs() < 0.02 * expected_mean,
+                "Mean was: {mean}"
+            );
+            assert!(
+                (variance - expected_variance).abs() < 0.06 * expected_variance,
+                "Variance was: {variance}"
+            );
+            assert!(samples.iter().all(|s| *s <= trials as f64));
+        }
+        assert_eq!(rng.gen_binomial(50, 0.0), 0);
+        assert_eq!(rng.gen_binomial(50, 1.0), 50);
+        assert_eq!(rng.gen_poisson(0.0), 0);
+    }
+
+    #[test]
+    fn huge_parameters_stay_within_bounds() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let successes = rng.gen_binomial(u64::MAX, 0.3);
+        let expected = u64::MAX as f64 * 0.3;
+        assert!((successes as f64 - expected).abs() < 1e-6 * expected);
+        let events = rng.gen_poisson(POISSON_MAX_MEAN);
+        assert!((events as f64 - POISSON_MAX_MEAN).abs() < 1e-6 * POISSON_MAX_MEAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "mean must be positive and at most 2^62")]
+    fn poisson_with_a_huge_mean_panics() {
+        SeededDiceRoller::new("seed", "test").gen_poisson(1e300);
+    }
+
+    #[test]
+    #[should_panic(expected = "sd must be positive")]
+    fn normal_with_a_negative_sd_panics() {
+        SeededDiceRoller::new("seed", "test").gen_normal(0.0, -1.0);
+    }
+}
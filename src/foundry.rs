@@ -0,0 +1,595 @@
+//! Conversion between Foundry VTT's `RollTable` documents, as exported in JSON, and
+//! [`RollToProcess`] tables using a [`RollMethod::PreparedRoll`].
+
+use crate::{
+    OutOfRangePolicy, ParseRollError, PreparedRoll, RangedTableError, RollMethod, RollToProcess,
+    WeightedResult,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt::Display;
+
+/// A Foundry VTT `RollTable`, rolling its **formula** and giving the results whose range covers
+/// the roll. Fields this crate doesn't use, like `_id` or `img`, are kept in **other** so they
+/// can be exported back.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoundryRollTable {
+    /// The name of the table.
+    pub name: String,
+    /// The roll made to pick a result, like "1d20" or "2d6+1".
+    pub formula: String,
+    /// The possible results.
+    pub results: Vec<FoundryTableResult>,
+    /// The other fields of the table.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// A result of a [`FoundryRollTable`], given when the roll is within its **range**. Fields this
+/// crate doesn't use, like `type`, `img` or `documentId`, are kept in **other**.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FoundryTableResult {
+    /// The text of the result.
+    #[serde(default)]
+    pub text: String,
+    /// The weight of the result, Foundry's way to compute its range when editing the table.
+    #[serde(default)]
+    pub weight: u32,
+    /// The lowest and the highest roll giving this result, both included.
+    pub range: [i64; 2],
+    /// The other fields of the result.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// Reasons why a table can't be converted from or to a [`FoundryRollTable`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FoundryError {
+    /// The formula of the Foundry table isn't a roll this crate understands.
+    Formula(ParseRollError),
+    /// The ranges of the Foundry table's results overlap or leave gaps between them.
+    Ranges(RangedTableError),
+    /// The table doesn't pick its result with a [`RollMethod::PreparedRoll`].
+    NotPreparedRoll(RollMethod),
+    /// The table wraps, rerolls or scales out of range rolls, which Foundry can't do.
+    UnsupportedPolicy(OutOfRangePolicy),
+    /// The entry at **index** has a weight of 0, which can't be given a range.
+    ZeroWeight {
+        /// Index of the entry.
+        index: usize,
+    },
+    /// The modifier of the **formula** can't be adjusted to make the table start at **start**,
+    /// as it wouldn't fit in an [`i32`].
+    ModifierOverflow {
+        /// The formula of the Foundry table.
+        formula: String,
+        /// The roll the first range starts at.
+        start: i64,
+    },
+}
+
+impl Display for FoundryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FoundryError::Formula(error) => write!(f, "{error}"),
+            FoundryError::Ranges(error) => write!(f, "{error}"),
+            FoundryError::NotPreparedRoll(method) => {
+                write!(f, "{method} tables can't be exported to Foundry")
+            }
+            FoundryError::UnsupportedPolicy(policy) => {
+                write!(
+                    f,
+                    "the {policy} out of range policy isn't supported by Foundry"
+                )
+            }
+            FoundryError::ZeroWeight { index } => {
+                write!(
+                    f,
+                    "entry {index} has a weight of 0 and can't be given a range"
+                )
+            }
+            FoundryError::ModifierOverflow { formula, start } => {
+                write!(
+                    f,
+                    "the modifier of \"{formula}\" can't be adjusted to start at {start}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for FoundryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FoundryError::Formula(error) => Some(error),
+            FoundryError::Ranges(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl FoundryRollTable {
+    /// Converts the Foundry table into a [`RollToProcess`] of its results, sorted by range, each
+    /// weighing as many rolls as its range covers.
+    ///
+    /// As this crate's tables start at the number of dice rolled, the modifier of the formula is
+    /// adjusted when the first range starts elsewhere, unless no roll can be lower than the first
+    /// range. [`FoundryRollTable::from_imported`] gives the original formula and ranges back.
+    /// Foundry gives nothing for rolls outside of the ranges: when the roll can give such results,
+    /// the table uses an [`OutOfRangePolicy::Error`] policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FoundryError`] if the formula isn't understood, if the ranges are inverted,
+    /// overlap, leave gaps or cover more than [`u32::MAX`] rolls, or if they start too far from
+    /// the lowest roll of the formula for its modifier to be adjusted.
+    pub fn to_table(&self) -> Result<RollToProcess<FoundryTableResult>, FoundryError> {
+        let mut roll: PreparedRoll = self.formula.parse().map_err(FoundryError::Formula)?;
+        let mut results: Vec<(usize, &FoundryTableResult)> =
+            self.results.iter().enumerate().collect();
+        if results.is_empty() {
+            return Err(FoundryError::Ranges(RangedTableError::Empty));
+        }
+        if let Some(&(index, _)) = results.iter().find(|(_, r)| r.range[0] > r.range[1]) {
+            return Err(FoundryError::Ranges(RangedTableError::InvertedRange {
+                index,
+            }));
+        }
+        results.sort_by_key(|(index, r)| (r.range[0], *index));
+        for pair in results.windows(2) {
+            let (first, second) = (pair[0].1.range, pair[1].1.range);
+            if second[0] <= first[1] {
+                return Err(FoundryError::Ranges(RangedTableError::Overlap {
+                    first: pair[0].0.min(pair[1].0),
+                    second: pair[0].0.max(pair[1].0),
+                    roll: second[0],
+                }));
+            }
+            if second[0] > first[1] + 1 {
+                return Err(FoundryError::Ranges(RangedTableError::Gap {
+                    min: first[1] + 1,
+                    max: second[0] - 1,
+                }));
+            }
+        }
+
+        let (first, last) = (results[0].1.range[0], results[results.len() - 1].1.range[1]);
+        let covers_every_roll = first <= roll.min_result() && roll.max_result() <= last;
+        let dice = i64::from(roll.dice);
+        // Below the lowest roll, the first range can start at the number of dice as well.
+        let start = if first < dice && first <= roll.min_result() && results[0].1.range[1] >= dice {
+            dice
+        } else {
+            first
+        };
+        roll.modifier = dice
+            .checked_sub(start)
+            .and_then(|shift| shift.checked_add(i64::from(roll.modifier)))
+            .and_then(|modifier| i32::try_from(modifier).ok())
+            .ok_or_else(|| FoundryError::ModifierOverflow {
+                formula: self.formula.clone(),
+                start,
+            })?;
+
+        let possible_results = results
+            .into_iter()
+            .map(|(index, r)| {
+                let weight = r.range[1]
+                    .checked_sub(r.range[0].max(start))
+                    .and_then(|covered| covered.checked_add(1))
+                    .and_then(|covered| u32::try_from(covered).ok())
+                    .ok_or(FoundryError::Ranges(RangedTableError::TooWide { index }))?;
+                Ok(WeightedResult::new(r.clone(), weight))
+            })
+            .collect::<Result<_, FoundryError>>()?;
+        let table = RollToProcess::new(possible_results, RollMethod::PreparedRoll(roll));
+        Ok(if covers_every_roll {
+            table
+        } else {
+            table.with_out_of_range(OutOfRangePolicy::Error)
+        })
+    }
+
+    /// Converts a **table** picking its result with a [`RollMethod::PreparedRoll`] into a Foundry
+    /// table called **name**, using **text** to write each result.
+    ///
+    /// With the default [`OutOfRangePolicy::Clamp`] policy, the first and last ranges are
+    /// stretched to the lowest and highest rolls so Foundry gives the same results.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FoundryError`] if the table doesn't use a [`RollMethod::PreparedRoll`], wraps,
+    /// rerolls or scales out of range rolls, or has an entry with a weight of 0.
+    pub fn from_table<T>(
+        name: impl Into<String>,
+        table: &RollToProcess<T>,
+        mut text: impl FnMut(&T) -> String,
+    ) -> Result<Self, FoundryError> {
+        let RollMethod::PreparedRoll(roll) = table.roll_method else {
+            return Err(FoundryError::NotPreparedRoll(table.roll_method));
+        };
+        if matches!(
+            table.out_of_range,
+            OutOfRangePolicy::Wrap | OutOfRangePolicy::Reroll | OutOfRangePolicy::Scale
+        ) {
+            return Err(FoundryError::UnsupportedPolicy(table.out_of_range));
+        }
+        if let Some(index) = table.possible_results.iter().position(|r| r.weight == 0) {
+            return Err(FoundryError::ZeroWeight { index });
+        }
+
+        let mut start = i64::from(roll.dice);
+        let last_index = table.possible_results.len().saturating_sub(1);
+        let results = table
+            .possible_results
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let mut range = [start, start + i64::from(entry.weight) - 1];
+                start = range[1] + 1;
+                if table.out_of_range == OutOfRangePolicy::Clamp {
+                    if index == 0 {
+                        range[0] = range[0].min(roll.min_result());
+                    }
+                    if index == last_index {
+                        range[1] = range[1].max(roll.max_result());
+                    }
+                }
+                FoundryTableResult {
+                    text: text(&entry.result),
+                    weight: u32::try_from(range[1] - range[0] + 1).unwrap_or(u32::MAX),
+                    range,
+                    other: Map::new(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            name: name.into(),
+            formula: roll.formula(),
+            results,
+            other: Map::new(),
+        })
+    }
+
+    /// Converts a **table** imported with [`FoundryRollTable::to_table`] back into a Foundry table
+    /// called **name**, with the formula, the ranges and the other fields of its results as they
+    /// were imported, as long as the table still picks the same way.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FoundryError`] if the table doesn't use a [`RollMethod::PreparedRoll`], wraps,
+    /// rerolls or scales out of range rolls, or has an entry with a weight of 0.
+    pub fn from_imported(
+        name: impl Into<String>,
+        table: &RollToProcess<FoundryTableResult>,
+    ) -> Result<Self, FoundryError> {
+        let mut foundry = Self::from_table(name, table, |r| r.text.clone())?;
+        for (result, entry) in foundry.results.iter_mut().zip(&table.possible_results) {
+            result.other.clone_from(&entry.result.other);
+        }
+
+        // The modifier was adjusted on import if the first range didn't start at the number of
+        // dice: shift the roll and the ranges back.
+        let Some(shift) = foundry
+            .results
+            .first()
+            .zip(table.possible_results.first())
+            .and_then(|(exported, imported)| {
+                imported.result.range[1].checked_sub(exported.range[1])
+            })
+        else {
+            return Ok(foundry);
+        };
+        let mut roll: PreparedRoll = foundry.formula.parse().map_err(FoundryError::Formula)?;
+        let shifted = i64::from(roll.modifier)
+            .checked_add(shift)
+            .and_then(|modifier| i32::try_from(modifier).ok());
+        let ranges: Option<Vec<[i64; 2]>> = foundry
+            .results
+            .iter()
+            .map(|r| {
+                Some([
+                    r.range[0].checked_add(shift)?,
+                    r.range[1].checked_add(shift)?,
+                ])
+            })
+            .collect();
+        if let (Some(modifier), Some(ranges)) = (shifted, ranges) {
+            roll.modifier = modifier;
+            foundry.formula = roll.formula();
+            for (result, range) in foundry.results.iter_mut().zip(ranges) {
+                result.range = range;
+            }
+        }
+
+        // A first range starting below the lowest roll, or a last one ending above the highest
+        // roll, can't be told apart from a range stopping at that roll: give them their original
+        // bounds back.
+        let last = foundry.results.len().saturating_sub(1);
+        for (index, side) in [(0, 0), (last, 1)] {
+            let (Some(exported), Some(imported)) = (
+                foundry.results.get_mut(index),
+                table.possible_results.get(index),
+            ) else {
+                continue;
+            };
+            let (range, original) = (&mut exported.range, imported.result.range);
+            let outward = if side == 0 {
+                original[0] < range[0]
+            } else {
+                original[1] > range[1]
+            };
+            if outward && original[1 - side] == range[1 - side] {
+                range[side] = original[side];
+                exported.weight = u32::try_from(range[1] - range[0] + 1).unwrap_or(u32::MAX);
+            }
+        }
+        Ok(foundry)
+    }
+
+    /// Parses a table exported by Foundry in JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`serde_json::Error`] met if the text isn't a valid Foundry table.
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    /// Writes the table in JSON, ready to be imported in Foundry.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`serde_json::Error`] met if one of the other fields can't be written.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SeededDiceRoller;
+
+    /// A table as exported by Foundry.
+    const ENCOUNTERS: &str = r#"{
+        "name": "Encounters",
+        "img": "icons/svg/d20-grey.svg",
+        "description": "",
+        "formula": "1d20",
+        "replacement": true,
+        "displayRoll": true,
+        "results": [
+            {
+                "type": "text",
+                "text": "Orc",
+                "img": "icons/svg/d20-black.svg",
+                "weight": 10,
+                "range": [6, 15],
+                "drawn": false,
+                "_id": "b2"
+            },
+            {
+                "type": "text",
+                "text": "Goblin",
+                "img": "icons/svg/d20-black.svg",
+                "weight": 5,
+                "range": [1, 5],
+                "drawn": false,
+                "_id": "a1"
+            },
+            {
+                "type": "text",
+                "text": "Troll",
+                "img": "icons/svg/d20-black.svg",
+                "weight": 5,
+                "range": [16, 20],
+                "drawn": false,
+                "_id": "c3"
+            }
+        ],
+        "_id": "t1"
+    }"#;
+
+    #[test]
+    fn foundry_tables_are_imported() {
+        let foundry = FoundryRollTable::from_json(ENCOUNTERS).unwrap();
+        let table = foundry.to_table().unwrap();
+        assert_eq!(
+            table.roll_method,
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 20, 0))
+        );
+        assert_eq!(table.out_of_range, OutOfRangePolicy::Clamp);
+        let texts: Vec<(&str, u32)> = table
+            .possible_results
+            .iter()
+            .map(|r| (r.result.text.as_str(), r.weight))
+            .collect();
+        assert_eq!(texts, vec![("Goblin", 5), ("Orc", 10), ("Troll", 5)]);
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut other_rng = SeededDiceRoller::new("seed", "step");
+        for _ in 0..50 {
+            let roll = other_rng.roll(1, 20, 0);
+            let index = rng.get_result_index(&table).unwrap();
+            let range = table.possible_results[index].result.range;
+            assert!(range[0] <= roll && roll <= range[1]);
+        }
+    }
+
+    #[test]
+    fn foundry_tables_survive_a_round_trip() {
+        let foundry = FoundryRollTable::from_json(ENCOUNTERS).unwrap();
+        let table = foundry.to_table().unwrap();
+        let mut exported = FoundryRollTable::from_imported("Encounters", &table).unwrap();
+        exported.other = foundry.other.clone();
+
+        let mut expected: Value = serde_json::from_str(ENCOUNTERS).unwrap();
+        let results = expected["results"].as_array_mut().unwrap();
+        results.sort_by_key(|r| r["range"][0].as_i64());
+        let exported: Value = serde_json::from_str(&exported.to_json().unwrap()).unwrap();
+        assert_eq!(exported, expected);
+    }
+
+    #[test]
+    fn ranges_beyond_the_rolls_survive_a_round_trip() {
+        let mut foundry = FoundryRollTable::from_json(ENCOUNTERS).unwrap();
+        foundry.results[1].range = [-5, 5];
+        foundry.results[2].range = [16, 30];
+        let table = foundry.to_table().unwrap();
+        assert_eq!(
+            table.roll_method,
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 20, 0))
+        );
+
+        let exported = FoundryRollTable::from_imported("Encounters", &table).unwrap();
+        assert_eq!(exported.formula, "1d20");
+        let ranges: Vec<[i64; 2]> = exported.results.iter().map(|r| r.range).collect();
+        assert_eq!(ranges, vec![[-5, 5], [6, 15], [16, 30]]);
+        assert_eq!(exported.results[0].weight, 11);
+    }
+
+    #[test]
+    fn partial_foundry_tables_report_out_of_range_rolls() {
+        let foundry = FoundryRollTable {
+            name: "Partial".to_string(),
+            formula: "2d6+1".to_string(),
+            results: vec![
+                FoundryTableResult {
+                    text: "a".to_string(),
+                    range: [5, 8],
+                    ..FoundryTableResult::default()
+                },
+                FoundryTableResult {
+                    text: "b".to_string(),
+                    range: [9, 10],
+                    ..FoundryTableResult::default()
+                },
+            ],
+            ..FoundryRollTable::default()
+        };
+        let table = foundry.to_table().unwrap();
+        assert_eq!(
+            table.roll_method,
+            RollMethod::PreparedRoll(PreparedRoll::new(2, 6, -2))
+        );
+        assert_eq!(table.out_of_range, OutOfRangePolicy::Error);
+
+        let exported = FoundryRollTable::from_table("Partial", &table, |r| r.text.clone()).unwrap();
+        assert_eq!(exported.formula, "2d6-2");
+        let ranges: Vec<[i64; 2]> = exported.results.iter().map(|r| r.range).collect();
+        assert_eq!(ranges, vec![[2, 5], [6, 7]]);
+
+        let imported = FoundryRollTable::from_imported("Partial", &table).unwrap();
+        assert_eq!(imported.formula, "2d6+1");
+        let ranges: Vec<[i64; 2]> = imported.results.iter().map(|r| r.range).collect();
+        assert_eq!(ranges, vec![[5, 8], [9, 10]]);
+    }
+
+    #[test]
+    fn exported_tables_keep_their_formula_and_ranges() {
+        for roll in [
+            PreparedRoll::new(1, 6, -1),
+            PreparedRoll::new(2, 6, 1),
+            PreparedRoll::new(2, 6, -5),
+        ] {
+            let table = RollToProcess::new(
+                SeededDiceRoller::to_possible_results(vec!["low", "middle", "high"]),
+                RollMethod::PreparedRoll(roll),
+            );
+            let exported =
+                FoundryRollTable::from_table("Trip", &table, |r| (*r).to_string()).unwrap();
+            let imported = exported.to_table().unwrap();
+            assert_eq!(imported.roll_method, table.roll_method);
+            let again =
+                FoundryRollTable::from_table("Trip", &imported, |r| r.text.clone()).unwrap();
+            assert_eq!(again, exported);
+            assert_eq!(
+                FoundryRollTable::from_imported("Trip", &imported).unwrap(),
+                exported
+            );
+        }
+    }
+
+    #[test]
+    fn clamped_tables_are_stretched_on_export() {
+        let table = RollToProcess::new(
+            SeededDiceRoller::to_possible_results(vec!["low", "high"]),
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 6, -1)),
+        );
+        let exported =
+            FoundryRollTable::from_table("Clamped", &table, |r| (*r).to_string()).unwrap();
+        let ranges: Vec<[i64; 2]> = exported.results.iter().map(|r| r.range).collect();
+        assert_eq!(ranges, vec![[0, 1], [2, 5]]);
+        assert_eq!(exported.formula, "1d6-1");
+    }
+
+    #[test]
+    fn invalid_tables_are_refused() {
+        let mut foundry = FoundryRollTable::from_json(ENCOUNTERS).unwrap();
+        foundry.results[0].range = [7, 15];
+        assert_eq!(
+            foundry.to_table().unwrap_err(),
+            FoundryError::Ranges(RangedTableError::Gap { min: 6, max: 6 })
+        );
+        foundry.results[0].range = [5, 15];
+        assert!(matches!(
+            foundry.to_table(),
+            Err(FoundryError::Ranges(RangedTableError::Overlap { .. }))
+        ));
+        foundry.results[0].range = [i64::MIN, i64::MAX];
+        foundry.results.truncate(1);
+        assert_eq!(
+            foundry.to_table().unwrap_err(),
+            FoundryError::Ranges(RangedTableError::TooWide { index: 0 })
+        );
+        foundry.results[0].range = [i64::MIN, 0];
+        let overflow = foundry.to_table().unwrap_err();
+        assert_eq!(
+            overflow,
+            FoundryError::ModifierOverflow {
+                formula: foundry.formula.clone(),
+                start: i64::MIN
+            }
+        );
+        assert_eq!(
+            overflow.to_string(),
+            format!(
+                "the modifier of \"{}\" can't be adjusted to start at {}",
+                foundry.formula,
+                i64::MIN
+            )
+        );
+        foundry.formula = "1d20 + @level".to_string();
+        assert!(matches!(foundry.to_table(), Err(FoundryError::Formula(_))));
+
+        let table = RollToProcess::new(
+            SeededDiceRoller::to_possible_results(vec!["a", "b"]),
+            RollMethod::SimpleRoll,
+        );
+        assert_eq!(
+            FoundryRollTable::from_table("Simple", &table, |r| (*r).to_string()),
+            Err(FoundryError::NotPreparedRoll(RollMethod::SimpleRoll))
+        );
+        let d20 = RollToProcess {
+            roll_method: RollMethod::PreparedRoll(PreparedRoll::new(1, 20, 0)),
+            ..table
+        };
+        for policy in [
+            OutOfRangePolicy::Wrap,
+            OutOfRangePolicy::Reroll,
+            OutOfRangePolicy::Scale,
+        ] {
+            assert_eq!(
+                FoundryRollTable::from_table(
+                    "Policy",
+                    &d20.clone().with_out_of_range(policy),
+                    |r| { (*r).to_string() }
+                ),
+                Err(FoundryError::UnsupportedPolicy(policy))
+            );
+        }
+    }
+}
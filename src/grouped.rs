@@ -0,0 +1,287 @@
+//! Two-stage picks: a group is picked first, such as a rarity tier, then one of the results of
+//! that group, each group having its own method.
+
+use crate::probabilities::entry_probabilities;
+use crate::{
+    Edges, OutOfRangeError, OutOfRangePolicy, RollMethod, RollToProcess, SeededDiceRoller,
+    WeightedResult,
+};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Groups of possible results, a weighted group being picked using **`roll_method`** before one
+/// of its results gets picked using the method of that group.
+///
+/// Groups without any result having a weight can't be picked.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let loot = GroupedTable::new(
+///     vec![
+///         WeightedResult::new(
+///             RollToProcess::new(
+///                 SeededDiceRoller::to_possible_results(vec!["dagger", "club"]),
+///                 RollMethod::SimpleRoll,
+///             ),
+///             3,
+///         ),
+///         WeightedResult::new(
+///             RollToProcess::new(
+///                 SeededDiceRoller::to_possible_results(vec!["flaming sword"]),
+///                 RollMethod::SimpleRoll,
+///             ),
+///             1,
+///         ),
+///     ],
+///     RollMethod::SimpleRoll,
+/// );
+///
+/// assert!(rng.get_grouped_result(&loot).is_some());
+/// assert_eq!(
+///     loot.flattened_probabilities(),
+///     vec![(&"dagger", 0.375), (&"club", 0.375), (&"flaming sword", 0.25)]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GroupedTable<T> {
+    /// The groups of possible results, each with its weight.
+    pub groups: Vec<WeightedResult<RollToProcess<T>>>,
+    /// The method with which to pick a group.
+    pub roll_method: RollMethod,
+    /// What to do when a [`RollMethod::PreparedRoll`] gives a group outside of the list.
+    #[serde(default)]
+    pub out_of_range: OutOfRangePolicy,
+}
+
+impl<T> GroupedTable<T> {
+    /// Creates a new [`GroupedTable`] picking one of its **groups** using a **`roll_method`**.
+    #[must_use]
+    pub fn new(groups: Vec<WeightedResult<RollToProcess<T>>>, roll_method: RollMethod) -> Self {
+        Self {
+            groups,
+            roll_method,
+            out_of_range: OutOfRangePolicy::default(),
+        }
+    }
+
+    /// Sets what to do when a [`RollMethod::PreparedRoll`] gives a group outside of the list.
+    #[must_use]
+    pub fn with_out_of_range(mut self, out_of_range: OutOfRangePolicy) -> Self {
+        self.out_of_range = out_of_range;
+        self
+    }
+
+    /// Returns the chances of each result of each group to get picked by
+    /// [`SeededDiceRoller::get_grouped_result_index`], the results of the first group first.
+    ///
+    /// Like [`RollToProcess::probabilities`], the chances don't sum to 1 when a roll can be
+    /// reported as out of range instead of picking a result.
+    #[must_use]
+    pub fn flattened_probabilities(&self) -> Vec<(&T, f64)> {
+        let group_chances = entry_probabilities(
+            &|i| self.group_weight(i),
+            self.groups.len(),
+            &self.roll_method,
+            self.out_of_range,
+            Edges::Weighted,
+        );
+        self.groups
+            .iter()
+            .zip(group_chances)
+            .flat_map(|(group, group_chance)| {
+                let table = &group.result;
+                table
+                    .possible_results
+                    .iter()
+                    .zip(table.probabilities())
+                    .map(move |(entry, chance)| (&entry.result, group_chance * chance))
+            })
+            .collect()
+    }
+
+    /// Returns the weight of the group at **index**, or 0 if none of its results has a weight.
+    fn group_weight(&self, index: usize) -> u32 {
+        let group = &self.groups[index];
+        if group.result.possible_results.iter().any(|e| e.weight > 0) {
+            group.weight
+        } else {
+            0
+        }
+    }
+}
+
+impl<T> Display for GroupedTable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GroupedTable {{ {} groups, method: {} }}",
+            self.groups.len(),
+            self.roll_method
+        )
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns the index of the group picked in a **`grouped`** table, then the index of the
+    /// result picked in that group.
+    pub fn get_grouped_result_index<T>(
+        &mut self,
+        grouped: &GroupedTable<T>,
+    ) -> Option<(usize, usize)> {
+        self.try_get_grouped_result_index(grouped).ok().flatten()
+    }
+
+    /// Returns the index of the group picked in a **`grouped`** table, then the index of the
+    /// result picked in that group, like [`SeededDiceRoller::get_grouped_result_index`] does, but
+    /// reports out of range rolls when the [`OutOfRangePolicy`] of the groups or of the group
+    /// picked is [`OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`RollMethod::PreparedRoll`] gives a result outside of
+    /// the groups or of the group picked while its policy is [`OutOfRangePolicy::Error`].
+    pub fn try_get_grouped_result_index<T>(
+        &mut self,
+        grouped: &GroupedTable<T>,
+    ) -> Result<Option<(usize, usize)>, OutOfRangeError> {
+        let Some(group) = self.pick_in_context(
+            &|i| grouped.group_weight(i),
+            grouped.groups.len(),
+            &grouped.roll_method,
+            grouped.out_of_range,
+        )?
+        else {
+            return Ok(None);
+        };
+        trace!("    group: {group}");
+        let table = &grouped.groups[group].result;
+        Ok(self
+            .try_get_result_index(table)?
+            .map(|index| (group, index)))
+    }
+
+    /// Returns the result picked in the group picked in a **`grouped`** table.
+    pub fn get_grouped_result<T: Clone>(&mut self, grouped: &GroupedTable<T>) -> Option<T> {
+        self.get_grouped_result_index(grouped)
+            .map(|(group, index)| {
+                grouped.groups[group].result.possible_results[index]
+                    .result
+                    .clone()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PreparedRoll;
+
+    /// Returns loot split in three rarity tiers picked with 1d6: 1-3 common, 4-5 rare and 6
+    /// epic.
+    fn loot() -> GroupedTable<&'static str> {
+        let tier = |items: Vec<&'static str>, weight| {
+            WeightedResult::new(
+                RollToProcess::new(
+                    SeededDiceRoller::to_possible_results(items),
+                    RollMethod::SimpleRoll,
+                ),
+                weight,
+            )
+        };
+        GroupedTable::new(
+            vec![
+                tier(vec!["copper", "rope", "torch", "bread"], 3),
+                tier(vec!["potion", "scroll"], 2),
+                tier(vec!["crown"], 1),
+            ],
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 0)),
+        )
+    }
+
+    #[test]
+    fn flattened_probabilities_multiply_both_stages() {
+        let loot = loot();
+        let probabilities = loot.flattened_probabilities();
+        let expected = [
+            ("copper", 0.125),
+            ("rope", 0.125),
+            ("torch", 0.125),
+            ("bread", 0.125),
+            ("potion", 1.0 / 6.0),
+            ("scroll", 1.0 / 6.0),
+            ("crown", 1.0 / 6.0),
+        ];
+        assert_eq!(probabilities.len(), expected.len());
+        for ((item, chance), (expected_item, expected_chance)) in probabilities.iter().zip(expected)
+        {
+            assert_eq!(**item, expected_item);
+            assert!((chance - expected_chance).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn grouped_picks_follow_the_flattened_probabilities() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let loot = loot();
+        let mut counts = [0_u32; 7];
+        let offsets = [0, 4, 6];
+        for _ in 0..30_000 {
+            let (group, index) = rng.get_grouped_result_index(&loot).unwrap();
+            counts[offsets[group] + index] += 1;
+        }
+        for (count, (_, chance)) in counts.iter().zip(loot.flattened_probabilities()) {
+            assert!((f64::from(*count) / 30_000.0 - chance).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn groups_without_results_are_never_picked() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut loot = loot();
+        loot.roll_method = RollMethod::SimpleRoll;
+        loot.groups[0].result.possible_results.clear();
+        loot.groups[2].result.possible_results[0].weight = 0;
+        for _ in 0..50 {
+            assert_eq!(rng.get_grouped_result_index(&loot).map(|(g, _)| g), Some(1));
+        }
+        let flattened: f64 = loot.flattened_probabilities().iter().map(|(_, c)| c).sum();
+        assert!((flattened - 1.0).abs() < 1e-12);
+
+        loot.groups[1].result.possible_results.clear();
+        assert_eq!(rng.get_grouped_result(&loot), None);
+    }
+
+    #[test]
+    fn grouped_picks_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let loot = loot();
+        let picks: Vec<&str> = (0..8)
+            .map(|_| rng.get_grouped_result(&loot).unwrap())
+            .collect();
+        assert_eq!(
+            picks,
+            vec!["crown", "torch", "bread", "rope", "scroll", "potion", "bread", "crown"]
+        );
+    }
+
+    #[test]
+    fn out_of_range_rolls_are_reported() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut loot = loot().with_out_of_range(OutOfRangePolicy::Error);
+        loot.roll_method = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10));
+        assert!(rng.try_get_grouped_result_index(&loot).is_err());
+        assert_eq!(rng.get_grouped_result_index(&loot), None);
+
+        let mut loot = loot.with_out_of_range(OutOfRangePolicy::Clamp);
+        loot.roll_method = RollMethod::SimpleRoll;
+        for group in &mut loot.groups {
+            group.result.roll_method = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10));
+            group.result.out_of_range = OutOfRangePolicy::Error;
+        }
+        assert!(rng.try_get_grouped_result_index(&loot).is_err());
+        assert_eq!(rng.get_grouped_result(&loot), None);
+    }
+}
@@ -1,142 +1,187 @@
 //! # Seeded Dice Roller
-//! SeededDiceRoller is, as its name implies, a dice roller using a seed to give pseudo-random deterministic results.
+//! `SeededDiceRoller` is, as its name implies, a dice roller using a seed to give pseudo-random deterministic results.
 //!
 //! In other words, it returns "random" results, which will always be the same if you use the same seed and call the same methods in the
 //! same order.
 //!
 //! ## How does it work
-//! You generate a Dice Roller using a seed, then you can use it to make dice rolls, generate random numbers or booleans, or select a
+//! You generate a Dice Roller using a seed, then you can use it to make dice rolls, generate random numbers or booleans, shuffle lists, or select a
 //! specific result in an array of possible choices using either a predefined dice roll, a gaussian distribution or a simple pick at random.
 //!
 //! It is also possible to give weight to the various choices in order to multiply their chances to be selected.
 //!
+//! It can also sample common distributions: normal, log-normal, exponential, triangular, gamma, beta, Poisson and binomial.
+//!
 //! ## Seed
 //! The seed is split into two parts, the **seed** proper and a "**step**". The **seed** represents something like the "session" of the run,
 //! while the **step** represents the name of the task currently at hand. The idea is to keep seeded generation consistent between versions
 //! of your program.
 //!
 //! For example, if we want to generate a dungeon using the player-inputted **seed** "water temple", we might create three specific
-//! instances of **SeededDiceRoller** using "map_gen_shape", "map_gen_walls" and "map_gen_treasures" values for the **step** in order to
+//! instances of **`SeededDiceRoller`** using "`map_gen_shape`", "`map_gen_walls`" and "`map_gen_treasures`" values for the **step** in order to
 //! always get the same results for those specific tasks, no matter how many other tasks you might add or remove before them in the future.
 //!
 //! ## Examples
 //! ### Dice rolls
 //! ```rust
 //! # use seeded_dice_roller::*;
-//! #
-//! # #[test]
-//! # fn doc_test_dice_rolls() {
-//! 	let mut rng = SeededDiceRoller::new("seed", "step");
+//! let mut rng = SeededDiceRoller::new("seed", "step");
 //!
-//!     assert_eq!(rng.roll(1, 6, 0), 6);
-//!     assert_eq!(rng.roll(3, 6, -5), 1);
-//!     assert_eq!(rng.roll(3, 6, -5), 8);
-//! # }
+//! assert_eq!(rng.roll(1, 6, 0), 6);
+//! assert_eq!(rng.roll(3, 6, -5), 1);
+//! assert_eq!(rng.roll(3, 6, -5), 8);
 //! ```
 //!
 //! ### Random picks
 //! ###### Picks a result using a predefined roll type
 //! ```rust
 //! # use seeded_dice_roller::*;
-//! #
-//! # #[test]
-//! # fn doc_test_dice_rolls() {
-//! 	let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
+//! let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
 //!
-//! 	let possible_results = SeededDiceRoller::to_copyable_possible_results(vec![
-//! 	    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
-//! 	]);
-//!     let result = rng.get_result(&CopyableRollToProcess {
-//!                                     possible_results: possible_results.clone(),
-//!                                     roll_method: RollMethod::PreparedRoll(PreparedRoll {
-//!                                         dice: 2,
-//!                                         die_type: 6,
-//!                                         modifier: 0
-//!                                     }),
-//!                                 }).unwrap();
+//! let possible_results = SeededDiceRoller::to_copyable_possible_results(vec![
+//!     "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
+//! ]);
+//! let result = rng.get_result(&CopyableRollToProcess {
+//!                                 possible_results: possible_results.clone(),
+//!                                 roll_method: RollMethod::PreparedRoll(PreparedRoll {
+//!                                     dice: 2,
+//!                                     die_type: 6,
+//!                                     modifier: 0
+//!                                 }),
+//!                                 ..Default::default()
+//!                             }).unwrap();
 //!
-//!     assert_eq!(result, "g");
-//! # }
+//! assert_eq!(result, "g");
 //! ```
 //!
 //! ###### Picks a result with higher chances to get one from the middle of the array
 //! ```rust
 //! # use seeded_dice_roller::*;
-//! #
-//! # #[test]
-//! # fn doc_test_dice_rolls() {
-//! 	let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
+//! let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
 //!
-//! 	let possible_results = SeededDiceRoller::to_copyable_possible_results(vec![
-//! 	    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
-//! 	]);
-//!     let result = rng.get_result(&CopyableRollToProcess {
-//!                                     possible_results: possible_results.clone(),
-//!                                     roll_method: RollMethod::GaussianRoll(5),
-//!                                 }).unwrap();
+//! let possible_results = SeededDiceRoller::to_copyable_possible_results(vec![
+//!     "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
+//! ]);
+//! let result = rng.get_result(&CopyableRollToProcess {
+//!                                 possible_results: possible_results.clone(),
+//!                                 roll_method: RollMethod::GaussianRoll(5),
+//!                                 ..Default::default()
+//!                             }).unwrap();
 //!
-//!     assert_eq!(result, "e");
-//! # }
+//! assert_eq!(result, "e");
 //! ```
 //!
 //! ###### Picks a result randomly, each choice has an equal chance to be selected
 //! ```rust
 //! # use seeded_dice_roller::*;
-//! #
-//! # #[test]
-//! # fn doc_test_dice_rolls() {
-//! 	let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
+//! let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
 //!
-//! 	let possible_results = SeededDiceRoller::to_copyable_possible_results(vec![
-//! 	    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
-//! 	]);
-//!     let result = rng.get_result(&CopyableRollToProcess {
-//!                                     possible_results: possible_results.clone(),
-//!                                     roll_method: RollMethod::SimpleRoll,
-//!                                 }).unwrap();
+//! let possible_results = SeededDiceRoller::to_copyable_possible_results(vec![
+//!     "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
+//! ]);
+//! let result = rng.get_result(&CopyableRollToProcess {
+//!                                 possible_results: possible_results.clone(),
+//!                                 roll_method: RollMethod::SimpleRoll,
+//!                                 ..Default::default()
+//!                             }).unwrap();
 //!
-//!     assert_eq!(result, "c");
-//! # }
+//! assert_eq!(result, "c");
 //! ```
 //!
 //! ###### Picks a result randomly, "a" is 5 times more likely to be selected than "b" or "c"
 //! ```rust
 //! # use seeded_dice_roller::*;
-//! #
-//! # #[test]
-//! # fn doc_test_dice_rolls() {
-//! 	let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
+//! let mut rng = seeded_dice_roller::SeededDiceRoller::new("seed", "step");
 //!
-//!     let weighted_set = vec![
-//!         CopyableWeightedResult { result: "a", weight: 5 },
-//!         CopyableWeightedResult { result: "b", weight: 1 },
-//!         CopyableWeightedResult { result: "c", weight: 1 },
-//!     ];
-//!     let result = rng.get_result(&CopyableRollToProcess {
-//!                                     possible_results: weighted_set,
-//!                                     roll_method: RollMethod::SimpleRoll,
-//!                                 }).unwrap();
+//! let weighted_set = vec![
+//!     CopyableWeightedResult { result: "a", weight: 5 },
+//!     CopyableWeightedResult { result: "b", weight: 1 },
+//!     CopyableWeightedResult { result: "c", weight: 1 },
+//! ];
+//! let result = rng.get_result(&CopyableRollToProcess {
+//!                                 possible_results: weighted_set,
+//!                                 roll_method: RollMethod::SimpleRoll,
+//!                                 ..Default::default()
+//!                             }).unwrap();
 //!
-//!     assert_eq!(result, "c");
-//! # }
+//! assert_eq!(result, "c");
 //! ```
 
 #![warn(clippy::all, clippy::pedantic)]
-use log::*;
-use rand::prelude::*;
+
+use log::{trace, warn};
+use rand_core::RngCore;
 use rand_pcg::Pcg64;
 use rand_seeder::Seeder;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 use std::fmt::Display;
-use rand::distributions::uniform::{SampleRange, SampleUniform};
+
+mod choose;
+mod context;
+mod deck;
+mod distributions;
+#[cfg(feature = "json")]
+mod foundry;
+mod grouped;
+mod lint;
+#[cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+mod loader;
+mod markdown;
+mod nested;
+mod notation;
+mod pity;
+mod probabilities;
+mod random;
+mod ranged;
+mod recent;
+mod sampling;
+mod shapes;
+mod strategy;
+mod streak;
+mod table;
+mod urn;
+
+pub use context::Tagged;
+pub use deck::{Deck, ShuffleBag};
+#[cfg(feature = "json")]
+pub use foundry::{FoundryError, FoundryRollTable, FoundryTableResult};
+pub use grouped::GroupedTable;
+pub use lint::TableIssue;
+#[cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+pub use loader::LoadError;
+pub use markdown::MarkdownError;
+pub use nested::{
+    TableEntry, TableError, TableOutcome, TableSet, DEFAULT_MAX_DEPTH, DEFAULT_MAX_OUTCOMES,
+};
+pub use notation::ParseRollError;
+pub use pity::{PityRule, PityTable, PityTimer};
+pub use random::{RandomPick, SeededRandom};
+pub use ranged::{RangedEntry, RangedTable, RangedTableError};
+pub use recent::{RecentPicks, RepeatRule};
+pub use sampling::{SampleRange, SampleUniform};
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub use seeded_dice_roller_derive::roll_method as __table_method;
+#[cfg(feature = "derive")]
+pub use seeded_dice_roller_derive::{RandomPick, SeededRandom};
+pub use strategy::PickStrategy;
+pub use streak::{SmoothedChance, StreakRule};
+pub use table::Table;
+pub use urn::{UrnRule, UrnTable};
+
+use probabilities::{RollChances, LISTED_RESULTS};
+
+/// The number of rerolls after which a [`PreparedRoll`] rarely giving a result covered by a list
+/// using [`OutOfRangePolicy::Reroll`] picks an entry directly, with the same chances.
+const MAX_REROLLS: u32 = 1_000;
 
 /// Enum used to know how to determine the result of a random pick in a list of possible results.
 #[derive(
     Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, SmartDefault, Serialize, Deserialize,
 )]
 pub enum RollMethod {
-    /// Uses a prepared roll ("**dice** D **die_type** + **modifier**").
+    /// Uses a prepared roll ("**dice** D **`die_type`** + **modifier**").
     PreparedRoll(PreparedRoll),
     /// Uses the given number of dice in order to pick a random result, with increasingly higher
     /// chances to get one from the middle of the list as the value is high.
@@ -144,97 +189,186 @@ pub enum RollMethod {
     /// Simply rolls against the number of possible results to get a random one.
     #[default]
     SimpleRoll,
+    /// Favours the entry at index **peak** (the last one if out of bounds), the chances of the
+    /// others decreasing linearly with their distance to it.
+    Triangular {
+        /// Index of the most likely entry.
+        peak: u16,
+    },
+    /// Favours the start of the list, each entry being **decay** percent less likely than the
+    /// previous one (a **decay** above 100 is treated as 100).
+    Exponential {
+        /// Percentage of likelihood lost from one entry to the next.
+        decay: u8,
+    },
+    /// Favours the entry at index **center** (the last one if out of bounds) following a bell
+    /// curve whose standard deviation is **spread** entries. A **spread** of 0 always picks the
+    /// center.
+    Gaussian {
+        /// Index of the most likely entry.
+        center: u16,
+        /// Standard deviation of the curve, in entries.
+        spread: u16,
+    },
+    /// Ramps the chances linearly from **start** for the first entry to **end** for the last one.
+    Linear {
+        /// Relative chances of the first entry.
+        start: u16,
+        /// Relative chances of the last entry.
+        end: u16,
+    },
 }
 
 impl Display for RollMethod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            RollMethod::PreparedRoll(roll) => write!(f, "PreparedRoll({})", roll),
-            RollMethod::GaussianRoll(n) => write!(f, "GaussianRoll({})", n),
+            RollMethod::PreparedRoll(roll) => write!(f, "PreparedRoll({roll})"),
+            RollMethod::GaussianRoll(n) => write!(f, "GaussianRoll({n})"),
             RollMethod::SimpleRoll => write!(f, "SimpleRoll"),
+            RollMethod::Triangular { peak } => write!(f, "Triangular(peak: {peak})"),
+            RollMethod::Exponential { decay } => write!(f, "Exponential(decay: {decay}%)"),
+            RollMethod::Gaussian { center, spread } => {
+                write!(f, "Gaussian(center: {center}, spread: {spread})")
+            }
+            RollMethod::Linear { start, end } => write!(f, "Linear({start} to {end})"),
         }
     }
 }
 
-/// Data allowing to pick a result at random in a list of possible results.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct RollToProcess<T> {
-    /// A list of possible results that can be picked at random.
-    pub possible_results: Vec<WeightedResult<T>>,
-    /// The method with which to pick a desired result.
-    pub roll_method: RollMethod,
+/// What to do when a [`PreparedRoll`] gives a result outside of the rolls covered by a list of
+/// possible results, which start at the number of dice rolled and span the sum of their weights.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum OutOfRangePolicy {
+    /// Rolls below the first entry pick the first one, rolls above the last entry pick the last
+    /// one.
+    #[default]
+    Clamp,
+    /// Rolls again until the result is covered. Nothing is picked if the roll can never give a
+    /// covered result, and after a thousand rerolls an entry is picked directly with the same
+    /// chances as rolling until a covered result comes up.
+    Reroll,
+    /// Wraps the roll around the covered rolls, as if they were repeated endlessly.
+    Wrap,
+    /// Scales every roll, from the lowest to the highest the [`PreparedRoll`] can give, onto the
+    /// covered rolls.
+    Scale,
+    /// Refuses to pick a result and reports the roll with an [`OutOfRangeError`].
+    Error,
 }
 
-impl<T> RollToProcess<T> {
-    /// Creates a new [RollToProcess].
-    pub fn new(possible_results: Vec<WeightedResult<T>>, roll_method: RollMethod) -> Self {
-        Self {
-            possible_results,
-            roll_method,
+impl Display for OutOfRangePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutOfRangePolicy::Clamp => write!(f, "Clamp"),
+            OutOfRangePolicy::Reroll => write!(f, "Reroll"),
+            OutOfRangePolicy::Wrap => write!(f, "Wrap"),
+            OutOfRangePolicy::Scale => write!(f, "Scale"),
+            OutOfRangePolicy::Error => write!(f, "Error"),
         }
     }
 }
 
-impl<T> Display for RollToProcess<T> {
+/// A [`PreparedRoll`] gave a **roll** outside of the rolls covered by a list of possible results,
+/// from **min** to **max**, while its [`OutOfRangePolicy`] was [`OutOfRangePolicy::Error`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct OutOfRangeError {
+    /// The roll given by the [`PreparedRoll`].
+    pub roll: i64,
+    /// The lowest roll covered by the list.
+    pub min: i64,
+    /// The highest roll covered by the list.
+    pub max: i64,
+}
+
+impl Display for OutOfRangeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "RollToProcess {{ {} choices, method: {} }}",
-            self.possible_results.len(),
-            self.roll_method
+            "rolled {}, outside of the covered rolls from {} to {}",
+            self.roll, self.min, self.max
         )
     }
 }
 
-/// Data allowing to pick a result at random in a list of possible results. The results must
-/// be copyable.
+impl std::error::Error for OutOfRangeError {}
+
+/// Which entries get the rolls falling below or above the rolls covered by a list of possible
+/// results.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Edges {
+    /// The first and last entries of the list, whatever their weight.
+    Listed,
+    /// The first and last entries having a weight, so that entries given a weight of 0 are never
+    /// picked.
+    Weighted,
+}
+
+/// Data allowing to pick a result at random in a list of possible results.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct CopyableRollToProcess<T>
-where
-    T: Copy + std::fmt::Debug,
-{
+pub struct RollToProcess<T> {
     /// A list of possible results that can be picked at random.
-    pub possible_results: Vec<CopyableWeightedResult<T>>,
+    pub possible_results: Vec<WeightedResult<T>>,
     /// The method with which to pick a desired result.
     pub roll_method: RollMethod,
+    /// What to do when a [`RollMethod::PreparedRoll`] gives a result outside of the list.
+    #[serde(default)]
+    pub out_of_range: OutOfRangePolicy,
 }
 
-impl<T: Copy + std::fmt::Debug> CopyableRollToProcess<T> {
-    /// Creates a new [CopyableRollToProcess].
-    pub fn new(possible_results: Vec<CopyableWeightedResult<T>>, roll_method: RollMethod) -> Self {
+impl<T> RollToProcess<T> {
+    /// Creates a new [`RollToProcess`].
+    #[must_use]
+    pub fn new(possible_results: Vec<WeightedResult<T>>, roll_method: RollMethod) -> Self {
         Self {
             possible_results,
             roll_method,
+            out_of_range: OutOfRangePolicy::default(),
         }
     }
+
+    /// Sets what to do when a [`RollMethod::PreparedRoll`] gives a result outside of the list.
+    #[must_use]
+    pub fn with_out_of_range(mut self, out_of_range: OutOfRangePolicy) -> Self {
+        self.out_of_range = out_of_range;
+        self
+    }
 }
 
-impl<T: Copy + std::fmt::Debug> Display for CopyableRollToProcess<T> {
+impl<T> Display for RollToProcess<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "CopyableRollToProcess {{ choices: {:?}, method: {} }}",
-            self.possible_results, self.roll_method
+            "RollToProcess {{ {} choices, method: {} }}",
+            self.possible_results.len(),
+            self.roll_method
         )
     }
 }
 
+/// A [`RollToProcess`] whose results are copyable, so that [`SeededDiceRoller::get_result`]
+/// can return them.
+///
+/// It is the same type as [`RollToProcess`], kept under this name for compatibility.
+pub type CopyableRollToProcess<T> = RollToProcess<T>;
+
 /// A result able to be picked at random in a list of possible results. The **weight** is used
 /// to determine the chances of this result to be picked against all other possible choices.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub struct WeightedResult<T> {
     /// The result that can be selected at random.
     pub result: T,
     /// The eventual weight of this result. A higher weight means that the result will be more
     /// likely to be picked in an uniform distribution.
     ///
-    /// Or with an example: when using the SimpleRoll [RollMethod], an item with a weight of 5
+    /// Or with an example: when using the `SimpleRoll` [`RollMethod`], an item with a weight of 5
     /// will have 5 more chances to be selected than an item with a weight of one;
     pub weight: u32,
 }
 
 impl<T> WeightedResult<T> {
-    /// Creates a new [WeightedResult].
+    /// Creates a new [`WeightedResult`].
     pub fn new(result: T, weight: u32) -> Self {
         Self { result, weight }
     }
@@ -246,42 +380,12 @@ impl<T> Display for WeightedResult<T> {
     }
 }
 
-/// A result able to be picked at random in a list of possible results. The **weight** is used
-/// to determine the chances of this result to be picked against all other possible choices.
-/// The result must be copyable.
-#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
-pub struct CopyableWeightedResult<T>
-where
-    T: Copy + std::fmt::Debug,
-{
-    /// The result that can be selected at random.
-    pub result: T,
-    /// The eventual weight of this result. A higher weight means that the result will be more
-    /// likely to be picked in an uniform distribution.
-    ///
-    /// Or with an example: when using the SimpleRoll [RollMethod], an item with a weight of 5
-    /// will have 5 more chances to be selected than an item with a weight of one;
-    pub weight: u32,
-}
-
-impl<T: Copy + std::fmt::Debug> CopyableWeightedResult<T> {
-    /// Creates a new [CopyableWeightedResult].
-    pub fn new(result: T, weight: u32) -> Self {
-        Self { result, weight }
-    }
-}
-
-impl<T: Copy + std::fmt::Debug> Display for CopyableWeightedResult<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{{ result: {:?}, weight: {} }}",
-            self.result, self.weight
-        )
-    }
-}
+/// A [`WeightedResult`] whose result is copyable.
+///
+/// It is the same type as [`WeightedResult`], kept under this name for compatibility.
+pub type CopyableWeightedResult<T> = WeightedResult<T>;
 
-/// Data allowing to roll **dice** times a **die_type** sided die and add an eventual **modifier**.
+/// Data allowing to roll **dice** times a **`die_type`** sided die and add an eventual **modifier**.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub struct PreparedRoll {
     /// The number of dice to roll.
@@ -294,7 +398,8 @@ pub struct PreparedRoll {
 }
 
 impl PreparedRoll {
-    /// Creates a new [PreparedRoll].
+    /// Creates a new [`PreparedRoll`].
+    #[must_use]
     pub fn new(dice: u16, die_type: u32, modifier: i32) -> Self {
         Self {
             dice,
@@ -302,6 +407,50 @@ impl PreparedRoll {
             modifier,
         }
     }
+
+    /// Returns the lowest result this roll can give.
+    #[must_use]
+    pub fn min_result(&self) -> i64 {
+        i64::from(self.dice) + i64::from(self.modifier)
+    }
+
+    /// Returns the highest result this roll can give.
+    #[must_use]
+    pub fn max_result(&self) -> i64 {
+        i64::from(self.dice) * i64::from(self.die_type) + i64::from(self.modifier)
+    }
+
+    /// Returns the chances of every result this roll can give, from the lowest to the highest.
+    /// The chances of a roll without any die type are empty.
+    ///
+    /// Returns **None** if the roll gives more than a million different results, too many to
+    /// list: [`RollToProcess::probabilities`] approximates the chances of such rolls instead. The
+    /// cost grows with the square of the number of dice times the die type.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn probabilities(&self) -> Option<Vec<f64>> {
+        if self.die_type == 0 && self.dice > 0 {
+            return Some(Vec::new());
+        }
+        if self.max_result() - self.min_result() >= LISTED_RESULTS {
+            return None;
+        }
+        let faces = self.die_type as usize;
+        let mut chances = vec![1.0];
+        for _ in 0..self.dice {
+            let mut next = vec![0.0; chances.len() + faces - 1];
+            let mut window = 0.0;
+            for (sum, chance) in next.iter_mut().enumerate() {
+                window += chances.get(sum).copied().unwrap_or(0.0);
+                if sum >= faces {
+                    window -= chances[sum - faces];
+                }
+                *chance = window / faces as f64;
+            }
+            chances = next;
+        }
+        Some(chances)
+    }
 }
 
 impl Default for PreparedRoll {
@@ -320,54 +469,30 @@ impl Display for PreparedRoll {
     }
 }
 
-/// A temporary struct used for finding which result a dice roll returns.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-struct RangedResult {
-    /// The index of the result this struct represents.
-    pub result_index: usize,
-    /// The roll must be equal or greater that **min** value. Inclusive.
-    pub min: i64,
-    /// The roll must be lower that **max** value. Non-inclusive.
-    pub max: i64,
-}
-
-impl RangedResult {
-    /// Creates a new [RangedResult].
-    #[allow(dead_code)]
-    pub fn new(result_index: usize, min: i64, max: i64) -> Self {
-        Self {
-            result_index,
-            min,
-            max,
-        }
-    }
-}
-
-impl Display for RangedResult {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{{ result_index: {}, min: {}, max: {} }}",
-            self.result_index, self.min, self.max
-        )
-    }
-}
-
 /// Uses a Random Number Generator fed with a **seed** to generate dice roll results, booleans
 /// and numbers in a deterministic way.
 ///
 /// It ensures that as long as you ask for the same rolls or generate the same types, you will
 /// always get the same results in the same order for a given **seed** and **step**.
+///
+/// # Portability
+/// A roller created with [`SeededDiceRoller::new`] may give different results on 32 and 64 bits
+/// targets when generating pointer-sized integers or ranges of them. A roller created with
+/// [`SeededDiceRoller::new_portable`] defines every method in terms of fixed-width draws, so that
+/// a given **seed** and **step** give the same results on every target, `wasm32` included.
 #[derive(Clone, Debug)]
 pub struct SeededDiceRoller {
     /// The seeded random generator.
     rng: Pcg64,
+    /// Whether every generated value must only depend on fixed-width draws.
+    portable: bool,
 }
 
 impl Default for SeededDiceRoller {
     fn default() -> Self {
         Self {
             rng: Seeder::from("seed".to_string()).make_rng(),
+            portable: false,
         }
     }
 }
@@ -380,125 +505,194 @@ impl SeededDiceRoller {
     /// represents the name of the task currently at hand.
     ///
     /// For example, if we want to generate a dungeon using the player-inputted **seed**
-    /// "water temple", we might create three specific instances of **SeededDiceRoller** using
-    /// "map_gen_shape", "map_gen_walls" and "map_gen_treasures" values for the **step** in order
+    /// "water temple", we might create three specific instances of **`SeededDiceRoller`** using
+    /// "`map_gen_shape`", "`map_gen_walls`" and "`map_gen_treasures`" values for the **step** in order
     /// to always get the same results for that specific task, no matter how many other tasks
     /// you might add or remove before them in the future.
     ///
     /// It helps keeping seeded generation consistent between versions of your program.
+    #[must_use]
     pub fn new(seed: &str, step: &str) -> Self {
         Self {
-            rng: Seeder::from(format!("{}_{}", step, seed)).make_rng(),
+            rng: Seeder::from(format!("{step}_{seed}")).make_rng(),
+            portable: false,
+        }
+    }
+
+    /// Returns a generator initialized with the given seed and step, whose results are the same
+    /// on every target platform.
+    ///
+    /// Pointer-sized integers are drawn as 32 bits values and ranges are sampled using 64 bits
+    /// draws, so 32 bits targets like `wasm32` give the same results as 64 bits ones. See
+    /// [`SeededDiceRoller::new`] for the meaning of **seed** and **step**.
+    ///
+    /// Samples of distributions, like [`SeededDiceRoller::gen_normal`] or
+    /// [`SeededDiceRoller::gen_poisson`], use the logarithms and exponentials of the platform's
+    /// math library and may rarely differ in their last bits from one platform to another.
+    #[must_use]
+    pub fn new_portable(seed: &str, step: &str) -> Self {
+        Self {
+            portable: true,
+            ..Self::new(seed, step)
+        }
+    }
+
+    /// Returns **true** if this generator gives the same results on every target platform.
+    #[must_use]
+    pub fn is_portable(&self) -> bool {
+        self.portable
+    }
+
+    /// Returns a new generator derived from the current state of this one and a **name**,
+    /// without advancing this one.
+    ///
+    /// Children with different names give independent results, whatever the order in which
+    /// they are created, so that adding or reordering them doesn't change the others. The child
+    /// is portable if this generator is.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let rng = SeededDiceRoller::new("seed", "step");
+    /// let mut walls = rng.child("walls");
+    /// let _ = rng.child("floors").gen_u32();
+    /// assert_eq!(walls.gen_u32(), rng.child("walls").gen_u32());
+    /// ```
+    #[must_use]
+    pub fn child(&self, name: &str) -> Self {
+        let key = self.rng.clone().next_u64();
+        Self {
+            rng: Seeder::from(format!("{name}_{key}")).make_rng(),
+            portable: self.portable,
         }
     }
 
     /// Returns **true** or **false**.
     pub fn gen_bool(&mut self) -> bool {
-        let gen = self.rng.gen::<bool>();
-        trace!(" gen_bool: {}", gen);
+        let gen = self.draw_bool();
+        trace!(" gen_bool: {gen}");
         gen
     }
 
     /// Returns a random 8bit unsigned integer.
+    #[allow(clippy::cast_possible_truncation)]
     pub fn gen_u8(&mut self) -> u8 {
-        let gen = self.rng.gen::<u8>();
-        trace!("   gen_u8: {}", gen);
+        let gen = self.draw_u32() as u8;
+        trace!("   gen_u8: {gen}");
         gen
     }
 
     /// Returns a random 16bit unsigned integer.
+    #[allow(clippy::cast_possible_truncation)]
     pub fn gen_u16(&mut self) -> u16 {
-        let gen = self.rng.gen::<u16>();
-        trace!("  gen_u16: {}", gen);
+        let gen = self.draw_u32() as u16;
+        trace!("  gen_u16: {gen}");
         gen
     }
 
     /// Returns a random 32bit unsigned integer.
     pub fn gen_u32(&mut self) -> u32 {
-        let gen = self.rng.gen::<u32>();
-        trace!("  gen_u32: {}", gen);
+        let gen = self.draw_u32();
+        trace!("  gen_u32: {gen}");
         gen
     }
 
     /// Returns a random 64bit unsigned integer.
     pub fn gen_u64(&mut self) -> u64 {
-        let gen = self.rng.gen::<u64>();
-        trace!("  gen_u64: {}", gen);
+        let gen = self.draw_u64();
+        trace!("  gen_u64: {gen}");
         gen
     }
 
     /// Returns a random 128bit unsigned integer.
     pub fn gen_u128(&mut self) -> u128 {
-        let gen = self.rng.gen::<u128>();
-        trace!(" gen_u128: {}", gen);
+        let gen = self.draw_u128();
+        trace!(" gen_u128: {gen}");
         gen
     }
 
     /// Returns a random pointer-sized unsigned integer.
+    ///
+    /// In portable mode, the value is drawn as a 32 bits unsigned integer.
     pub fn gen_usize(&mut self) -> usize {
-        let gen = self.rng.gen::<usize>();
-        trace!("gen_usize: {}", gen);
+        let gen = self.draw_usize();
+        trace!("gen_usize: {gen}");
         gen
     }
 
     /// Returns a random 8bit signed integer.
+    #[allow(clippy::cast_possible_truncation)]
     pub fn gen_i8(&mut self) -> i8 {
-        let gen = self.rng.gen::<i8>();
-        trace!("   gen_i8: {}", gen);
+        let gen = self.draw_u32() as i8;
+        trace!("   gen_i8: {gen}");
         gen
     }
 
     /// Returns a random 16bit signed integer.
+    #[allow(clippy::cast_possible_truncation)]
     pub fn gen_i16(&mut self) -> i16 {
-        let gen = self.rng.gen::<i16>();
-        trace!("  gen_i16: {}", gen);
+        let gen = self.draw_u32() as i16;
+        trace!("  gen_i16: {gen}");
         gen
     }
 
     /// Returns a random 32bit signed integer.
+    #[allow(clippy::cast_possible_wrap)]
     pub fn gen_i32(&mut self) -> i32 {
-        let gen = self.rng.gen::<i32>();
-        trace!("  gen_i32: {}", gen);
+        let gen = self.draw_u32() as i32;
+        trace!("  gen_i32: {gen}");
         gen
     }
 
     /// Returns a random 64bit signed integer.
+    #[allow(clippy::cast_possible_wrap)]
     pub fn gen_i64(&mut self) -> i64 {
-        let gen = self.rng.gen::<i64>();
-        trace!("  gen_i64: {}", gen);
+        let gen = self.draw_u64() as i64;
+        trace!("  gen_i64: {gen}");
         gen
     }
 
     /// Returns a random 128bit signed integer.
+    #[allow(clippy::cast_possible_wrap)]
     pub fn gen_i128(&mut self) -> i128 {
-        let gen = self.rng.gen::<i128>();
-        trace!(" gen_i128: {}", gen);
+        let gen = self.draw_u128() as i128;
+        trace!(" gen_i128: {gen}");
         gen
     }
 
     /// Returns a random pointer-sized signed integer.
+    ///
+    /// In portable mode, the value is drawn as a 32 bits signed integer.
+    #[allow(clippy::cast_possible_wrap)]
     pub fn gen_isize(&mut self) -> isize {
-        let gen = self.rng.gen::<isize>();
-        trace!("gen_isize: {}", gen);
+        let gen = if self.portable {
+            self.draw_u32() as i32 as isize
+        } else {
+            self.draw_usize() as isize
+        };
+        trace!("gen_isize: {gen}");
         gen
     }
 
     /// Returns a random 32bit floating point type.
     pub fn gen_f32(&mut self) -> f32 {
-        let gen = self.rng.gen::<f32>();
-        trace!("  gen_f32: {}", gen);
+        let gen = self.draw_f32();
+        trace!("  gen_f32: {gen}");
         gen
     }
 
     /// Returns a random 64bit floating point type.
     pub fn gen_f64(&mut self) -> f64 {
-        let gen = self.rng.gen::<f64>();
-        trace!("  gen_f64: {}", gen);
+        let gen = self.draw_f64();
+        trace!("  gen_f64: {gen}");
         gen
     }
 
     /// Returns a random number in the given range.
     ///
+    /// # Panics
+    /// Panics if the range is empty.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -506,222 +700,414 @@ impl SeededDiceRoller {
     /// let n: usize = SeededDiceRoller::new("seed", "step").gen_range(10..=100);
     /// ```
     pub fn gen_range<T, R>(&mut self, range: R) -> T
-        where
-            T: SampleUniform + Display,
-            R: SampleRange<T>
+    where
+        T: SampleUniform + Display,
+        R: SampleRange<T>,
     {
-        let gen = self.rng.gen_range(range);
-        trace!("  gen_range: {}", gen);
+        assert!(!range.is_empty(), "cannot sample empty range");
+        let gen = range.sample_single(self);
+        trace!("  gen_range: {gen}");
+        gen
+    }
+
+    /// Returns **true** with a probability of **p**, which must be between 0 and 1.
+    ///
+    /// The probability is scaled to a 64 bits integer and compared against a `u64` draw, so
+    /// `chance(0.0)` is never **true** and `chance(1.0)` always is.
+    ///
+    /// # Panics
+    /// Panics if **p** is not between 0 and 1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use seeded_dice_roller::SeededDiceRoller;
+    /// let triggered = SeededDiceRoller::new("seed", "step").chance(0.15);
+    /// ```
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn chance(&mut self, p: f64) -> bool {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "chance: p must be between 0 and 1"
+        );
+        let draw = self.draw_u64();
+        let gen = if p >= 1.0 {
+            true
+        } else {
+            draw < (p * 18_446_744_073_709_551_616.0) as u64
+        };
+        trace!("   chance: {p} = {gen}");
         gen
     }
 
-    /// Rolls **dice** times a **die_type** sided die, adds an eventual **modifier** and returns
+    /// Returns **true** with a probability of **p** percent. Values above 100 are always **true**.
+    pub fn percent(&mut self, p: u8) -> bool {
+        self.ratio(u32::from(p), 100)
+    }
+
+    /// Returns **true** with a probability of one in **n**.
+    ///
+    /// # Panics
+    /// Panics if **n** is 0.
+    pub fn one_in(&mut self, n: u32) -> bool {
+        self.ratio(1, n)
+    }
+
+    /// Returns **true** with a probability of **numerator** in **denominator**, using exact
+    /// integer arithmetic. Ratios above one are always **true**.
+    ///
+    /// # Panics
+    /// Panics if **denominator** is 0.
+    pub fn ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+        assert!(denominator > 0, "ratio: denominator must not be 0");
+        let gen = u32::sample_single(0, denominator, self) < numerator;
+        trace!("    ratio: {numerator}/{denominator} = {gen}");
+        gen
+    }
+
+    /// Rolls **dice** times a **`die_type`** sided die, adds an eventual **modifier** and returns
     /// the result.
     pub fn roll(&mut self, dice: u16, die_type: u32, modifier: i32) -> i64 {
         let mut result = 0;
-        let die_type = die_type as i64;
+        let die_type = i64::from(die_type);
         for _ in 0..dice {
-            result += (self.rng.gen::<u32>() as i64).abs() % &die_type + 1;
+            result += i64::from(self.draw_u32()).abs() % die_type + 1;
         }
-        result += modifier as i64;
+        result += i64::from(modifier);
 
         trace!(
             "     roll: {}d{}{} = {}",
             dice,
             die_type,
-            if modifier > 0 {
-                format!(" + {}", modifier)
-            } else if modifier < 0 {
-                format!(" - {}", modifier * -1)
-            } else {
-                "".to_string()
+            match modifier.cmp(&0) {
+                std::cmp::Ordering::Greater => format!(" + {modifier}"),
+                std::cmp::Ordering::Less => format!(" - {}", -i64::from(modifier)),
+                std::cmp::Ordering::Equal => String::new(),
             },
             result
         );
         result
     }
 
-    /// Rolls **dice** times a **die_type** sided die, adds an eventual **modifier** and returns
+    /// Rolls **dice** times a **`die_type`** sided die, adds an eventual **modifier** and returns
     /// the result.
     pub fn roll_prepared(&mut self, to_roll: &PreparedRoll) -> i64 {
         self.roll(to_roll.dice, to_roll.die_type, to_roll.modifier)
     }
 
-    /// Returns the result of a random selection in a **to_process** list given alongside the
+    /// Returns the result of a random selection in a **`to_process`** list given alongside the
     /// details of the selection method. That method can either be to follow the rules dictated
-    /// in a [PreparedRoll] or by using a uniform or normal distribution.
-    pub fn get_result<T: Copy + std::fmt::Debug>(
+    /// in a [`PreparedRoll`] or by using a uniform or normal distribution.
+    ///
+    /// The result is copied out of the list: see [`SeededDiceRoller::get_result_ref`],
+    /// [`SeededDiceRoller::get_result_cloned`] and [`SeededDiceRoller::take_result`] for results
+    /// that can't be copied.
+    pub fn get_result<T: Copy>(&mut self, to_process: &RollToProcess<T>) -> Option<T> {
+        self.try_get_result(to_process).ok().flatten()
+    }
+
+    /// Returns the result of a random selection in a **`to_process`** list like
+    /// [`SeededDiceRoller::get_result`] does, but reports out of range rolls when its
+    /// [`OutOfRangePolicy`] is [`OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`RollMethod::PreparedRoll`] gives a result outside of
+    /// the list while its policy is [`OutOfRangePolicy::Error`].
+    pub fn try_get_result<T: Copy>(
         &mut self,
-        to_process: &CopyableRollToProcess<T>,
-    ) -> Option<T> {
-        let weighted_possible_results = (&to_process)
-            .possible_results
-            .iter()
-            .map(|e| WeightedResult {
-                result: e.result,
-                weight: e.weight,
-            })
-            .collect();
-        let standard_to_process = RollToProcess {
-            possible_results: weighted_possible_results,
-            roll_method: to_process.roll_method,
-        };
-        match self.get_result_index(&standard_to_process) {
-            Some(index) => Some((&to_process.possible_results[index]).result),
-            None => None,
-        }
+        to_process: &RollToProcess<T>,
+    ) -> Result<Option<T>, OutOfRangeError> {
+        Ok(self.try_get_result_ref(to_process)?.copied())
+    }
+
+    /// Returns a reference to the result of a random selection in a **`to_process`** list, like
+    /// [`SeededDiceRoller::get_result`] does for copyable results.
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// let names = RollToProcess::new(
+    ///     SeededDiceRoller::to_possible_results(vec!["Ada".to_string(), "Brom".to_string()]),
+    ///     RollMethod::SimpleRoll,
+    /// );
+    /// let name: &String = rng.get_result_ref(&names).unwrap();
+    /// assert!(name == "Ada" || name == "Brom");
+    /// ```
+    pub fn get_result_ref<'a, T>(&mut self, to_process: &'a RollToProcess<T>) -> Option<&'a T> {
+        self.try_get_result_ref(to_process).ok().flatten()
+    }
+
+    /// Returns a reference to the result of a random selection in a **`to_process`** list like
+    /// [`SeededDiceRoller::get_result_ref`] does, but reports out of range rolls when its
+    /// [`OutOfRangePolicy`] is [`OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`RollMethod::PreparedRoll`] gives a result outside of
+    /// the list while its policy is [`OutOfRangePolicy::Error`].
+    pub fn try_get_result_ref<'a, T>(
+        &mut self,
+        to_process: &'a RollToProcess<T>,
+    ) -> Result<Option<&'a T>, OutOfRangeError> {
+        Ok(self
+            .try_get_result_index(to_process)?
+            .map(|index| &to_process.possible_results[index].result))
+    }
+
+    /// Returns a clone of the result of a random selection in a **`to_process`** list, like
+    /// [`SeededDiceRoller::get_result`] does for copyable results.
+    pub fn get_result_cloned<T: Clone>(&mut self, to_process: &RollToProcess<T>) -> Option<T> {
+        self.get_result_ref(to_process).cloned()
     }
 
-    /// Returns the index of the result of a random selection in a **to_process** list given
+    /// Removes the result of a random selection from a **`to_process`** list and returns it, so
+    /// that it can't be picked again. The other entries keep their order.
+    pub fn take_result<T>(&mut self, to_process: &mut RollToProcess<T>) -> Option<T> {
+        let index = self.get_result_index(to_process)?;
+        Some(to_process.possible_results.remove(index).result)
+    }
+
+    /// Returns the index of the result of a random selection in a **`to_process`** list given
     /// alongside the details of the selection method. That method can either be to follow the rules
-    /// dictated in a [PreparedRoll] or by using a uniform or normal distribution.
+    /// dictated in a [`PreparedRoll`], by using a uniform or normal distribution, or by following
+    /// one of the curves of the shaped [`RollMethod`] variants.
     pub fn get_result_index<T>(&mut self, to_process: &RollToProcess<T>) -> Option<usize> {
-        let length = to_process.possible_results.len();
-        match length {
-            0 => None,
-            1 => Some(0),
-            _ => match &to_process.roll_method {
-                RollMethod::PreparedRoll(ref roll) => {
-                    let result = self.process_prepared_roll(&to_process, length, &roll);
-                    result
-                }
-                RollMethod::GaussianRoll(dice) => {
-                    let result = self.process_gaussian_roll(&to_process, length, *dice);
-                    result
-                }
-                RollMethod::SimpleRoll => {
-                    let result = self.process_simple_roll(&to_process, length);
-                    result
+        self.try_get_result_index(to_process).ok().flatten()
+    }
+
+    /// Returns the index of the result of a random selection in a **`to_process`** list like
+    /// [`SeededDiceRoller::get_result_index`] does, but reports out of range rolls when its
+    /// [`OutOfRangePolicy`] is [`OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`RollMethod::PreparedRoll`] gives a result outside of
+    /// the list while its policy is [`OutOfRangePolicy::Error`].
+    pub fn try_get_result_index<T>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        self.pick_with_method(
+            &to_process.possible_results,
+            &to_process.roll_method,
+            to_process.out_of_range,
+        )
+    }
+
+    /// Picks a result using the [`PreparedRoll`] given to pick an entry in a list of **length**
+    /// entries whose weights are given by **weight**, handling rolls outside of the list as the
+    /// **policy** requires and clamping them onto the **edges**.
+    pub(crate) fn process_prepared_roll(
+        &mut self,
+        weight: &dyn Fn(usize) -> u32,
+        length: usize,
+        prepared_roll: &PreparedRoll,
+        policy: OutOfRangePolicy,
+        edges: Edges,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        let first = i64::from(prepared_roll.dice);
+        let covered: i64 = (0..length).map(|i| i64::from(weight(i))).sum();
+        let last = first + covered - 1;
+        let mut roll = self.roll_prepared(prepared_roll);
+        if covered > 0 {
+            if policy == OutOfRangePolicy::Reroll
+                && (prepared_roll.max_result() < first || prepared_roll.min_result() > last)
+            {
+                trace!("   chosen: None, the roll never gives a covered result");
+                return Ok(None);
+            }
+            let mut rerolls = 0;
+            roll = loop {
+                match Self::fit_roll(roll, first, covered, prepared_roll, policy) {
+                    Some(fitted) => break fitted,
+                    None if policy == OutOfRangePolicy::Reroll && rerolls == MAX_REROLLS => {
+                        return Ok(self.pick_covered_entry(weight, length, prepared_roll, edges));
+                    }
+                    None if policy == OutOfRangePolicy::Reroll => {
+                        rerolls += 1;
+                        roll = self.roll_prepared(prepared_roll);
+                    }
+                    None => {
+                        return Err(OutOfRangeError {
+                            roll,
+                            min: first,
+                            max: last,
+                        })
+                    }
                 }
-            },
+            };
         }
+        let result = Self::choice_for_roll((0..length).map(weight), first, 1, roll, edges);
+        trace!("   chosen: {result:?}");
+
+        Ok(result)
     }
 
-    /// Picks a result using the [PreparedRoll] stored alongside a list **to_process**.
-    fn process_prepared_roll<T>(
+    /// Picks an entry in a list of **length** entries whose weights are given by **weight** with
+    /// the chance the [`PreparedRoll`] has to give one of its results, knowing that it gives one
+    /// of the results covered by the list. Used when rerolling takes too long to get one.
+    fn pick_covered_entry(
         &mut self,
-        to_process: &RollToProcess<T>,
+        weight: &dyn Fn(usize) -> u32,
         length: usize,
         prepared_roll: &PreparedRoll,
+        edges: Edges,
     ) -> Option<usize> {
-        // Transform the array so it can be used easily
-        let mut choices: Vec<RangedResult> = Vec::new();
-        self.fill_choices(
-            &to_process,
-            length,
-            prepared_roll.clone().dice as i64,
-            1,
-            &mut choices,
-        );
-
-        let roll = self.roll_prepared(prepared_roll);
-        let result = choices.iter().find(|r| roll >= r.min && roll < r.max);
-        trace!("   chosen: {:?}", result);
+        let first = i64::from(prepared_roll.dice);
+        let last = first + (0..length).map(|i| i64::from(weight(i))).sum::<i64>() - 1;
+        let chances = RollChances::new(prepared_roll);
+        let entry_chances: Vec<f64> = Self::roll_ranges((0..length).map(weight), first, 1, edges)
+            .map(|(start, end)| chances.between(start.max(first), (end - 1).min(last)))
+            .collect();
+        let mut target = self.gen_f64() * entry_chances.iter().sum::<f64>();
+        let result = entry_chances
+            .iter()
+            .position(|chance| {
+                target -= chance;
+                *chance > 0.0 && target < 0.0
+            })
+            .or_else(|| entry_chances.iter().rposition(|chance| *chance > 0.0));
+        trace!("   chosen: {result:?}, after {MAX_REROLLS} rerolls");
+        result
+    }
 
-        match result {
-            Some(ranged_result) => Some(ranged_result.result_index),
-            None => None,
+    /// Returns the **roll** of a [`PreparedRoll`] moved onto the **covered** results starting at
+    /// **first** as the **policy** requires, or None if it falls outside of them while the policy
+    /// is to reroll or to report it.
+    fn fit_roll(
+        roll: i64,
+        first: i64,
+        covered: i64,
+        prepared_roll: &PreparedRoll,
+        policy: OutOfRangePolicy,
+    ) -> Option<i64> {
+        match policy {
+            OutOfRangePolicy::Clamp => Some(roll),
+            OutOfRangePolicy::Wrap => Some(first + (roll - first).rem_euclid(covered)),
+            OutOfRangePolicy::Scale => {
+                let possible = i128::from(prepared_roll.max_result())
+                    - i128::from(prepared_roll.min_result())
+                    + 1;
+                let scaled = (i128::from(roll) - i128::from(prepared_roll.min_result()))
+                    * i128::from(covered)
+                    / possible;
+                Some(first + i64::try_from(scaled).unwrap_or(covered - 1))
+            }
+            OutOfRangePolicy::Reroll | OutOfRangePolicy::Error => {
+                (first..first + covered).contains(&roll).then_some(roll)
+            }
         }
     }
 
-    /// Picks a result from a list **to_process** using multiple dice in order to get
-    /// a normal distribution of the probabilities for each possible choice.
-    fn process_gaussian_roll<T>(
+    /// Picks a result from a list of **length** entries whose weights are given by **weight**
+    /// using multiple dice in order to get a normal distribution of the probabilities for each
+    /// possible choice.
+    pub(crate) fn process_gaussian_roll(
         &mut self,
-        to_process: &RollToProcess<T>,
+        weight: &dyn Fn(usize) -> u32,
         length: usize,
         dice: u16,
     ) -> Option<usize> {
-        // Transform the array so it can be used easily
-        let mut choices: Vec<RangedResult> = Vec::new();
-        self.fill_choices(&to_process, length, dice as i64, dice as i64, &mut choices);
-
-        let max = SeededDiceRoller::calculate_die_type(to_process);
+        let max = SeededDiceRoller::calculate_die_type((0..length).map(weight));
+        if max == 0 {
+            trace!("   chosen: None, every weight is 0");
+            return None;
+        }
         // Adds a modifier to avoid getting results skewed towards the beginning or the end of the set
-        let modifier = (dice / 2) as i32
-            + (if (dice % 2 == 0) && self.rng.gen::<bool>() {
+        let modifier = i32::from(dice / 2)
+            + (if dice.is_multiple_of(2) && self.draw_bool() {
                 -1
             } else {
                 0
             });
         let roll = self.roll(dice, max, modifier);
-        let result = choices.iter().find(|r| roll >= r.min && roll < r.max);
-        trace!("   chosen: {:?}", result);
+        let dice = i64::from(dice);
+        let result =
+            Self::choice_for_roll((0..length).map(weight), dice, dice, roll, Edges::Listed);
+        trace!("   chosen: {result:?}");
 
-        match result {
-            Some(ranged_result) => Some(ranged_result.result_index),
-            None => None,
-        }
+        result
     }
 
-    /// Picks a result from a list **to_process** at random while respecting the required weight
-    /// of each entry.
-    fn process_simple_roll<T>(
+    /// Picks a result from a list of entries whose **weights** are given in order at random while
+    /// respecting the required weight of each entry.
+    pub(crate) fn process_simple_roll(
         &mut self,
-        to_process: &RollToProcess<T>,
-        length: usize,
+        weights: impl Iterator<Item = u32> + Clone,
     ) -> Option<usize> {
-        // Transform the array so it can be used easily
-        let mut choices: Vec<RangedResult> = Vec::new();
-        self.fill_choices(&to_process, length, 1, 1, &mut choices);
-
-        let max = SeededDiceRoller::calculate_die_type(to_process);
+        let max = SeededDiceRoller::calculate_die_type(weights.clone());
+        if max == 0 {
+            trace!("   chosen: None, every weight is 0");
+            return None;
+        }
         let roll = self.roll(1, max, 0);
-        let result = choices.iter().find(|r| roll >= r.min && roll < r.max);
-        trace!("   chosen: {:?}", result);
+        let result = Self::choice_for_roll(weights, 1, 1, roll, Edges::Listed);
+        trace!("   chosen: {result:?}");
 
-        match result {
-            Some(ranged_result) => Some(ranged_result.result_index),
-            None => None,
-        }
+        result
     }
 
-    /// Fills a **choices** vector with values allowing to elect a result from a die roll
-    /// in a **to_process** list easily.
-    fn fill_choices<T>(
-        &self,
-        to_process: &RollToProcess<T>,
-        length: usize,
+    /// Returns the index of the entry a **roll** elects in a list of entries whose **weights** are
+    /// given in order, the entries covering the rolls given by [`SeededDiceRoller::roll_ranges`].
+    fn choice_for_roll(
+        weights: impl Iterator<Item = u32> + Clone,
+        min: i64,
+        weight_multiplier: i64,
+        roll: i64,
+        edges: Edges,
+    ) -> Option<usize> {
+        Self::roll_ranges(weights, min, weight_multiplier, edges)
+            .position(|(start, end)| roll >= start && roll < end)
+    }
+
+    /// Returns the rolls covered by each entry of a list whose **weights** are given in order,
+    /// from its first roll to the first roll of the next entry. The first entry covers the rolls
+    /// from **min** and every entry covers its weight times **`weight_multiplier`** rolls. Rolls
+    /// below or above the list go to the first or last entry of its **edges**.
+    pub(crate) fn roll_ranges(
+        weights: impl Iterator<Item = u32> + Clone,
         min: i64,
         weight_multiplier: i64,
-        choices: &mut Vec<RangedResult>,
-    ) {
-        let mut min = min;
+        edges: Edges,
+    ) -> impl Iterator<Item = (i64, i64)> {
+        let count = weights.clone().count();
+        let (first, last) = match edges {
+            Edges::Listed => (0, count.saturating_sub(1)),
+            Edges::Weighted => (
+                weights.clone().position(|weight| weight > 0).unwrap_or(0),
+                weights
+                    .clone()
+                    .enumerate()
+                    .filter(|(_, weight)| *weight > 0)
+                    .last()
+                    .map_or(count.saturating_sub(1), |(i, _)| i),
+            ),
+        };
         let mut last_end: i64 = min;
-        for i in 0..length {
-            let mut weight: i64 =
-                ((&to_process.possible_results[i]).weight as i64) * weight_multiplier;
-            if weight < 0 {
-                weight = 0
-            }
-            min = if i == 0 { i64::MIN } else { last_end };
-            last_end += weight;
-            let max: i64 = if i == length - 1 { i64::MAX } else { last_end };
-            choices.push(RangedResult {
-                result_index: i,
-                min,
-                max,
-            })
-        }
-         trace!(" pre-roll: {} possible results to choose from", choices.len());
-    }
-
-    /// Returns a vector of [CopyableWeightedResult] using the given **vec** of values.
-    /// The result can be used in a [CopyableRollToProcess].
-    pub fn to_copyable_possible_results<T: Copy + std::fmt::Debug>(
-        vec: Vec<T>,
-    ) -> Vec<CopyableWeightedResult<T>> {
-        vec.iter()
-            .copied()
-            .map(|c| CopyableWeightedResult {
-                result: c,
-                weight: 1,
-            })
-            .collect()
+        weights.enumerate().map(move |(i, weight)| {
+            let start = if i == first { i64::MIN } else { last_end };
+            last_end += (i64::from(weight) * weight_multiplier).max(0);
+            let end = if i == last { i64::MAX } else { last_end };
+            (start, end)
+        })
     }
 
-    /// Returns a vector of [WeightedResult] using the given **vec** of values.
-    /// The result can be used in a [RollToProcess].
+    /// Returns a vector of [`CopyableWeightedResult`] using the given **vec** of values, like
+    /// [`SeededDiceRoller::to_possible_results`] does.
+    #[must_use]
+    pub fn to_copyable_possible_results<T: Copy>(vec: Vec<T>) -> Vec<CopyableWeightedResult<T>> {
+        Self::to_possible_results(vec)
+    }
+
+    /// Returns a vector of [`WeightedResult`] using the given **vec** of values.
+    /// The result can be used in a [`RollToProcess`].
+    #[must_use]
     pub fn to_possible_results<T>(vec: Vec<T>) -> Vec<WeightedResult<T>> {
         vec.into_iter()
             .map(|item| WeightedResult {
@@ -731,24 +1117,29 @@ impl SeededDiceRoller {
             .collect()
     }
 
-    /// Adds the weight of every entry in a list **to_process** in order to determine the type
-    /// of die that must be rolled to find a desired result.
-    fn calculate_die_type<T>(to_process: &RollToProcess<T>) -> u32 {
-        let max = to_process
-            .possible_results
-            .iter()
-            .map(|r| r.weight)
+    /// Adds the **weights** of every entry in a list in order to determine the type of die that
+    /// must be rolled to find a desired result.
+    fn calculate_die_type(weights: impl Iterator<Item = u32>) -> u32 {
+        weights
             .reduce(|a, b| a + b)
-            .expect("Should be able to add the possible results' weights.");
-        max
+            .expect("Should be able to add the possible results' weights.")
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::unreadable_literal,
+    clippy::float_cmp,
+    clippy::too_many_lines,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
 mod tests {
     use super::*;
 
     #[test]
+    // Pointer-sized values of non-portable rollers depend on the target
+    #[cfg(target_pointer_width = "64")]
     fn dice_roller_is_deterministic() {
         let mut rng = SeededDiceRoller::new("seed", "test");
         {
@@ -760,7 +1151,7 @@ mod tests {
             assert_eq!(rng.roll(69, 6, 0), 242);
             assert_eq!(rng.roll(2, 123, 0), 81);
             assert_eq!(rng.roll(1, 6, 3343), 3348);
-            assert_eq!(rng.gen_bool(), false);
+            assert!(!rng.gen_bool());
             assert_eq!(rng.gen_u8(), 188);
             assert_eq!(rng.gen_u16(), 45209);
             assert_eq!(rng.gen_u32(), 2067204665);
@@ -782,6 +1173,7 @@ mod tests {
                         "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
                     ]),
                     roll_method: RollMethod::SimpleRoll,
+                    ..Default::default()
                 })
                 .unwrap()));
 
@@ -797,7 +1189,7 @@ mod tests {
             assert_eq!(rng.roll(69, 6, 0), 240);
             assert_eq!(rng.roll(2, 123, 0), 138);
             assert_eq!(rng.roll(1, 6, 3343), 3344);
-            assert_eq!(rng.gen_bool(), true);
+            assert!(rng.gen_bool());
             assert_eq!(rng.gen_u8(), 82);
             assert_eq!(rng.gen_u16(), 27159);
             assert_eq!(rng.gen_u32(), 3180098725);
@@ -819,6 +1211,7 @@ mod tests {
                         "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
                     ]),
                     roll_method: RollMethod::SimpleRoll,
+                    ..Default::default()
                 })
                 .unwrap()));
 
@@ -834,7 +1227,7 @@ mod tests {
             assert_eq!(rng.roll(69, 6, 0), 239);
             assert_eq!(rng.roll(2, 123, 0), 91);
             assert_eq!(rng.roll(1, 6, 3343), 3344);
-            assert_eq!(rng.gen_bool(), false);
+            assert!(!rng.gen_bool());
             assert_eq!(rng.gen_u8(), 162);
             assert_eq!(rng.gen_u16(), 34315);
             assert_eq!(rng.gen_u32(), 2687893072);
@@ -856,6 +1249,7 @@ mod tests {
                         "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
                     ]),
                     roll_method: RollMethod::SimpleRoll,
+                    ..Default::default()
                 })
                 .unwrap()));
 
@@ -863,20 +1257,177 @@ mod tests {
         }
     }
 
+    #[test]
+    fn portable_dice_roller_is_deterministic() {
+        // These values must stay the same on every target, 32 bits ones included
+        let mut rng = SeededDiceRoller::new_portable("seed", "test");
+        assert!(rng.is_portable());
+        assert_eq!(rng.roll(3, 6, -5), 1);
+        assert!(!rng.gen_bool());
+        assert_eq!(rng.gen_u8(), 119);
+        assert_eq!(rng.gen_u32(), 3296838525);
+        assert_eq!(rng.gen_u64(), 5754245934020121149);
+        assert_eq!(rng.gen_u128(), 281542538068669679993033692866066877497);
+        assert_eq!(rng.gen_usize(), 2778273655);
+        assert_eq!(rng.gen_i32(), 349532699);
+        assert_eq!(rng.gen_isize(), -1836233990);
+        assert_eq!(rng.gen_f32(), 0.83996683);
+        assert_eq!(rng.gen_f64(), 0.7833723382382272);
+        assert_eq!(rng.gen_range(0usize..50), 24);
+        assert_eq!(rng.gen_range(-1_000_000isize..=1_000_000), -724793);
+        assert_eq!(
+            rng.gen_range(0u128..=u128::MAX / 3),
+            61922304452419113697710203952701509053
+        );
+        assert_eq!(rng.gen_range(i8::MIN..=i8::MAX), -107);
+        assert_eq!(rng.gen_range(0.002..0.0065), 0.005798306397723234);
+        assert_eq!(rng.gen_range(-1.5f32..=1.5), -1.1544871);
+    }
+
+    #[test]
+    fn portable_pointer_sized_values_use_fixed_width_draws() {
+        let mut sized = SeededDiceRoller::new_portable("seed", "test");
+        let mut fixed = SeededDiceRoller::new_portable("seed", "test");
+        for _ in 0..100 {
+            assert_eq!(sized.gen_usize(), fixed.gen_u32() as usize);
+            assert_eq!(sized.gen_isize(), fixed.gen_i32() as isize);
+            assert_eq!(
+                sized.gen_range(0usize..100_000),
+                usize::try_from(fixed.gen_range(0u64..100_000)).unwrap()
+            );
+            assert_eq!(
+                sized.gen_range(-5isize..=5),
+                isize::try_from(fixed.gen_range(-5i64..=5)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn sampling_gives_the_same_results_as_rand_0_8() {
+        use rand::seq::SliceRandom;
+        use rand::Rng;
+
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let mut reference: Pcg64 = Seeder::from("test_seed").make_rng();
+        for _ in 0..1000 {
+            assert_eq!(rng.gen_bool(), reference.gen::<bool>());
+            assert_eq!(rng.gen_i16(), reference.gen::<i16>());
+            assert_eq!(rng.gen_f32(), reference.gen::<f32>());
+            assert_eq!(rng.gen_range(3u8..=200), reference.gen_range(3u8..=200));
+            assert_eq!(
+                rng.gen_range(-300i16..300),
+                reference.gen_range(-300i16..300)
+            );
+            assert_eq!(rng.gen_range(0u32..7), reference.gen_range(0u32..7));
+            assert_eq!(
+                rng.gen_range(i64::MIN..=0),
+                reference.gen_range(i64::MIN..=0)
+            );
+            assert_eq!(rng.gen_range(5usize..999), reference.gen_range(5usize..999));
+            assert_eq!(
+                rng.gen_range(1u128..u128::MAX / 7),
+                reference.gen_range(1u128..u128::MAX / 7)
+            );
+            assert_eq!(
+                rng.gen_range(0u64..=u64::MAX),
+                reference.gen_range(0u64..=u64::MAX)
+            );
+            assert_eq!(
+                rng.gen_range(-2.5f32..7.0),
+                reference.gen_range(-2.5f32..7.0)
+            );
+            assert_eq!(
+                rng.gen_range(0.1f64..=0.2),
+                reference.gen_range(0.1f64..=0.2)
+            );
+        }
+
+        let mut shuffled: Vec<u32> = (0..50).collect();
+        let mut reference_shuffled = shuffled.clone();
+        rng.shuffle(&mut shuffled);
+        reference_shuffled.shuffle(&mut reference);
+        assert_eq!(shuffled, reference_shuffled);
+    }
+
+    #[test]
+    fn shuffle_is_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let mut values: Vec<u8> = (1..=10).collect();
+        rng.shuffle(&mut values);
+        assert_eq!(values, vec![3, 4, 2, 7, 8, 5, 9, 1, 6, 10]);
+
+        let mut rng = SeededDiceRoller::new_portable("seed", "test");
+        let mut values: Vec<u8> = (1..=10).collect();
+        rng.shuffle(&mut values);
+        assert_eq!(values, vec![1, 9, 10, 6, 7, 2, 3, 5, 8, 4]);
+    }
+
+    #[test]
+    fn bernoulli_helpers_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let results: Vec<bool> = (0..3)
+            .map(|_| {
+                [
+                    rng.chance(0.5),
+                    rng.percent(15),
+                    rng.one_in(3),
+                    rng.ratio(2, 7),
+                ]
+            })
+            .flat_map(IntoIterator::into_iter)
+            .collect();
+        assert_eq!(
+            results,
+            vec![true, false, true, false, false, false, true, false, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn bernoulli_helpers_respect_certain_outcomes() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        for _ in 0..1000 {
+            assert!(!rng.chance(0.0));
+            assert!(rng.chance(1.0));
+            assert!(!rng.percent(0));
+            assert!(rng.percent(100));
+            assert!(rng.percent(u8::MAX));
+            assert!(rng.one_in(1));
+            assert!(!rng.ratio(0, 5));
+            assert!(rng.ratio(5, 5));
+        }
+    }
+
+    #[test]
+    fn bernoulli_helpers_follow_their_probability() {
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let hits = (0..100_000).filter(|_| rng.percent(15)).count();
+        assert!((14_000..16_000).contains(&hits), "Hits were: {hits}");
+        let hits = (0..100_000).filter(|_| rng.chance(0.7)).count();
+        assert!((69_000..71_000).contains(&hits), "Hits were: {hits}");
+        let hits = (0..100_000).filter(|_| rng.one_in(20)).count();
+        assert!((4_500..5_500).contains(&hits), "Hits were: {hits}");
+    }
+
+    #[test]
+    #[should_panic(expected = "denominator must not be 0")]
+    fn ratio_with_a_zero_denominator_panics() {
+        SeededDiceRoller::new("seed", "test").ratio(1, 0);
+    }
+
     #[test]
     fn roll_is_within_bounds() {
         let mut rng = SeededDiceRoller::new("seed", "test");
         for _ in 0..1000 {
             let n: i64 = rng.roll(1, 6, 0);
-            assert!(n >= 1 && n <= 6);
+            assert!((1..=6).contains(&n));
         }
         for _ in 0..1000 {
             let n: i64 = rng.roll(3, 6, 0);
-            assert!(n >= 3 && n <= 18);
+            assert!((3..=18).contains(&n));
         }
         for _ in 0..1000 {
             let n: i64 = rng.roll(1, 20, 0);
-            assert!(n >= 1 && n <= 20);
+            assert!((1..=20).contains(&n));
         }
     }
 
@@ -911,7 +1462,7 @@ mod tests {
     fn get_result_returns_a_random_result() {
         let mut rng = SeededDiceRoller::new("seed", "test");
         for _ in 0..1000 {
-            assert!(vec!["a", "b", "c", "d"].contains(
+            assert!(["a", "b", "c", "d"].contains(
                 &rng.get_result(&CopyableRollToProcess {
                     possible_results: SeededDiceRoller::to_copyable_possible_results(vec![
                         "a", "b", "c", "d"
@@ -921,28 +1472,31 @@ mod tests {
                         die_type: 6,
                         modifier: -3
                     }),
+                    ..Default::default()
                 })
                 .unwrap()
             ));
         }
         for _ in 0..1000 {
-            assert!(vec!["a", "b", "c", "d"].contains(
+            assert!(["a", "b", "c", "d"].contains(
                 &rng.get_result(&CopyableRollToProcess {
                     possible_results: SeededDiceRoller::to_copyable_possible_results(vec![
                         "a", "b", "c", "d"
                     ]),
                     roll_method: RollMethod::GaussianRoll(4),
+                    ..Default::default()
                 })
                 .unwrap()
             ));
         }
         for _ in 0..1000 {
-            assert!(vec!["a", "b", "c", "d"].contains(
+            assert!(["a", "b", "c", "d"].contains(
                 &rng.get_result(&CopyableRollToProcess {
                     possible_results: SeededDiceRoller::to_copyable_possible_results(vec![
                         "a", "b", "c", "d"
                     ]),
                     roll_method: RollMethod::SimpleRoll,
+                    ..Default::default()
                 })
                 .unwrap()
             ));
@@ -960,6 +1514,7 @@ mod tests {
                     die_type: 0,
                     modifier: i32::MIN
                 }),
+                ..Default::default()
             })
             .unwrap()));
         assert!("b".eq(rng
@@ -970,15 +1525,222 @@ mod tests {
                     die_type: u32::MAX,
                     modifier: i32::MAX
                 }),
+                ..Default::default()
             })
             .unwrap()));
     }
 
+    #[test]
+    fn out_of_range_policies_handle_rolls_outside_of_the_list() {
+        let d6 = PreparedRoll::new(1, 6, 0);
+        let expected = |policy, rng: &mut SeededDiceRoller| {
+            let roll = rng.roll_prepared(&d6);
+            match policy {
+                OutOfRangePolicy::Clamp => Ok(Some(roll.min(3) as usize - 1)),
+                OutOfRangePolicy::Reroll => {
+                    let mut roll = roll;
+                    while roll > 3 {
+                        roll = rng.roll_prepared(&d6);
+                    }
+                    Ok(Some(roll as usize - 1))
+                }
+                OutOfRangePolicy::Wrap => Ok(Some((roll as usize - 1) % 3)),
+                OutOfRangePolicy::Scale => Ok(Some((roll as usize - 1) / 2)),
+                OutOfRangePolicy::Error if roll > 3 => Err(OutOfRangeError {
+                    roll,
+                    min: 1,
+                    max: 3,
+                }),
+                OutOfRangePolicy::Error => Ok(Some(roll as usize - 1)),
+            }
+        };
+
+        for policy in [
+            OutOfRangePolicy::Clamp,
+            OutOfRangePolicy::Reroll,
+            OutOfRangePolicy::Wrap,
+            OutOfRangePolicy::Scale,
+            OutOfRangePolicy::Error,
+        ] {
+            let to_process = RollToProcess::new(
+                SeededDiceRoller::to_possible_results(vec!["a", "b", "c"]),
+                RollMethod::PreparedRoll(d6),
+            )
+            .with_out_of_range(policy);
+            let mut rng = SeededDiceRoller::new("seed", "step");
+            let mut other_rng = SeededDiceRoller::new("seed", "step");
+            for _ in 0..50 {
+                assert_eq!(
+                    rng.try_get_result_index(&to_process),
+                    expected(policy, &mut other_rng),
+                    "{policy}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_policies_handle_rolls_never_in_the_list() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let to_process = CopyableRollToProcess::new(
+            SeededDiceRoller::to_copyable_possible_results(vec!["a", "b", "c"]),
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10)),
+        );
+        assert_eq!(rng.get_result(&to_process), Some("c"));
+        let to_process = to_process.with_out_of_range(OutOfRangePolicy::Reroll);
+        assert_eq!(rng.get_result(&to_process), None);
+        let to_process = to_process.with_out_of_range(OutOfRangePolicy::Error);
+        assert_eq!(rng.get_result(&to_process), None);
+        assert!(rng.try_get_result(&to_process).is_err());
+        let to_process = to_process.with_out_of_range(OutOfRangePolicy::Scale);
+        assert!(rng.get_result(&to_process).is_some());
+
+        let lone = CopyableRollToProcess::new(
+            SeededDiceRoller::to_copyable_possible_results(vec!["a"]),
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10)),
+        );
+        assert_eq!(rng.get_result(&lone), Some("a"));
+        let lone = lone.with_out_of_range(OutOfRangePolicy::Reroll);
+        assert_eq!(rng.get_result(&lone), None);
+        let lone = lone.with_out_of_range(OutOfRangePolicy::Error);
+        let roll = rng.clone().roll_prepared(&PreparedRoll::new(1, 6, 10));
+        assert_eq!(
+            rng.try_get_result(&lone),
+            Err(OutOfRangeError {
+                roll,
+                min: 1,
+                max: 1
+            })
+        );
+        for policy in [OutOfRangePolicy::Wrap, OutOfRangePolicy::Scale] {
+            let lone = lone.clone().with_out_of_range(policy);
+            assert_eq!(rng.get_result(&lone), Some("a"));
+        }
+    }
+
+    #[test]
+    fn rerolls_end_when_the_list_is_rarely_rolled() {
+        // 40d6 gives 40 once and 41 forty times in 6^40 rolls
+        let to_process = CopyableRollToProcess::new(
+            SeededDiceRoller::to_copyable_possible_results(vec!["a", "b"]),
+            RollMethod::PreparedRoll(PreparedRoll::new(40, 6, 0)),
+        )
+        .with_out_of_range(OutOfRangePolicy::Reroll);
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let picks: Vec<&str> = (0..41)
+            .map(|_| rng.get_result(&to_process).unwrap())
+            .collect();
+        let first = picks.iter().filter(|pick| **pick == "a").count();
+        assert!(first < 5, "{picks:?}");
+    }
+
+    #[test]
+    fn clamped_rolls_go_to_the_edges_of_the_list_whatever_their_weight() {
+        // 1d6-3 rolls from -2 to 3 while the list covers 1 and 2, the first entry covering nothing
+        let to_process = CopyableRollToProcess::new(
+            vec![
+                CopyableWeightedResult::new("a", 0),
+                CopyableWeightedResult::new("b", 1),
+                CopyableWeightedResult::new("c", 1),
+                CopyableWeightedResult::new("d", 0),
+            ],
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 6, -3)),
+        );
+        assert_eq!(
+            to_process.probabilities(),
+            vec![0.5, 1.0 / 6.0, 1.0 / 6.0, 1.0 / 6.0]
+        );
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut other_rng = rng.clone();
+        for _ in 0..50 {
+            let expected = match other_rng.roll(1, 6, -3) {
+                ..=0 => "a",
+                1 => "b",
+                2 => "c",
+                _ => "d",
+            };
+            assert_eq!(rng.get_result(&to_process), Some(expected));
+        }
+        // Only picks depending on a context skip the entries without weight
+        for _ in 0..50 {
+            let result = rng.get_result_in_context(&to_process, |entry| entry.weight);
+            assert!(matches!(result, Some("b" | "c")));
+        }
+    }
+
+    #[test]
+    fn prepared_roll_probabilities_cover_every_result() {
+        let chances = PreparedRoll::new(2, 6, 1).probabilities().unwrap();
+        assert_eq!(chances.len(), 11);
+        assert!((chances[5] - 6.0 / 36.0).abs() < 1e-12);
+        assert!((chances[0] - 1.0 / 36.0).abs() < 1e-12);
+        assert!((chances.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(PreparedRoll::new(0, 6, 3).probabilities(), Some(vec![1.0]));
+        assert_eq!(PreparedRoll::new(2, 0, 0).probabilities(), Some(vec![]));
+        assert_eq!(PreparedRoll::new(1, u32::MAX, 0).probabilities(), None);
+        assert_eq!(
+            PreparedRoll::new(1, 1 << 20, 0)
+                .probabilities()
+                .map(|c| c.len()),
+            Some(1 << 20)
+        );
+    }
+
+    #[test]
+    fn children_only_depend_on_their_name_and_parent_state() {
+        let mut rng = SeededDiceRoller::new_portable("seed", "step");
+        let first: Vec<u32> = (0..4).map(|_| rng.child("a").gen_u32()).collect();
+        assert!(first.windows(2).all(|w| w[0] == w[1]));
+        assert!(rng.child("a").is_portable());
+        assert_ne!(rng.child("a").gen_u64(), rng.child("b").gen_u64());
+
+        let mut other = SeededDiceRoller::new_portable("seed", "step");
+        assert_eq!(rng.gen_u32(), other.gen_u32());
+        assert_ne!(rng.child("a").gen_u32(), first[0]);
+    }
+
+    #[test]
+    fn results_can_be_borrowed_cloned_or_taken() {
+        let names = || {
+            RollToProcess::new(
+                SeededDiceRoller::to_possible_results(vec![
+                    "Ada".to_string(),
+                    "Brom".to_string(),
+                    "Cid".to_string(),
+                ]),
+                RollMethod::SimpleRoll,
+            )
+        };
+        let mut names_to_take = names();
+        let names = names();
+        let mut rng = SeededDiceRoller::new("seed", "test");
+        let mut same_rng = rng.clone();
+        let mut other_rng = rng.clone();
+
+        let index = rng.get_result_index(&names).unwrap();
+        assert_eq!(
+            same_rng.get_result_ref(&names),
+            Some(&names.possible_results[index].result)
+        );
+        assert_eq!(
+            other_rng.get_result_cloned(&names),
+            Some(names.possible_results[index].result.clone())
+        );
+
+        let mut taken: Vec<String> = (0..3)
+            .map(|_| rng.take_result(&mut names_to_take).unwrap())
+            .collect();
+        assert_eq!(rng.take_result(&mut names_to_take), None);
+        taken.sort();
+        assert_eq!(taken, vec!["Ada", "Brom", "Cid"]);
+    }
+
     #[test]
     fn get_result_index_returns_a_random_index() {
         let mut rng = SeededDiceRoller::new("seed", "test");
         for _ in 0..1000 {
-            assert!(vec![0, 1, 2, 3, 4, 5, 6, 7].contains(
+            assert!([0, 1, 2, 3, 4, 5, 6, 7].contains(
                 &rng.get_result_index(&RollToProcess {
                     possible_results: SeededDiceRoller::to_possible_results(vec![
                         "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"
@@ -988,28 +1750,31 @@ mod tests {
                         die_type: 6,
                         modifier: -3
                     }),
+                    ..Default::default()
                 })
                 .unwrap()
             ));
         }
         for _ in 0..1000 {
-            assert!(vec![0, 1, 2, 3].contains(
+            assert!([0, 1, 2, 3].contains(
                 &rng.get_result_index(&RollToProcess {
                     possible_results: SeededDiceRoller::to_possible_results(vec![
                         "a", "b", "c", "d"
                     ]),
                     roll_method: RollMethod::GaussianRoll(3),
+                    ..Default::default()
                 })
                 .unwrap()
             ));
         }
         for _ in 0..1000 {
-            assert!(vec![0, 1, 2, 3].contains(
+            assert!([0, 1, 2, 3].contains(
                 &rng.get_result_index(&RollToProcess {
                     possible_results: SeededDiceRoller::to_possible_results(vec![
                         "a", "b", "c", "d"
                     ]),
                     roll_method: RollMethod::SimpleRoll,
+                    ..Default::default()
                 })
                 .unwrap()
             ));
@@ -1021,7 +1786,7 @@ mod tests {
         let mut rng = SeededDiceRoller::new("seed", "test");
         for _ in 0..1000 {
             let n: i32 = rng.gen_range(1..=6);
-            assert!(n >= 1 && n <= 6, "Value was: {}", n);
+            assert!((1..=6).contains(&n), "Value was: {n}");
         }
     }
 
@@ -1030,7 +1795,7 @@ mod tests {
         let mut rng = SeededDiceRoller::new("seed", "test");
         for _ in 0..1000 {
             let n: f64 = rng.gen_range(0.0..=10.0);
-            assert!(n >= 0.0 && n <= 10.0, "Value was: {}", n);
+            assert!((0.0..=10.0).contains(&n), "Value was: {n}");
         }
     }
 
@@ -1039,12 +1804,13 @@ mod tests {
         let mut rng = SeededDiceRoller::new("seed", "test");
         for _ in 0..1000 {
             let n: usize = rng.gen_range(10..=100);
-            assert!(n >= 10 && n <= 100, "Value was: {}", n);
+            assert!((10..=100).contains(&n), "Value was: {n}");
         }
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "cannot sample empty range")]
+    #[allow(clippy::reversed_empty_ranges)]
     fn gen_range_invalid_bounds() {
         let mut rng = SeededDiceRoller::new("seed", "test");
         let _: i32 = rng.gen_range(6..=1); // This should panic as the range is invalid
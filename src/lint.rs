@@ -0,0 +1,339 @@
+//! Analysis of tables looking for entries that can't be picked or whose chances are skewed.
+
+use crate::{
+    probabilities::RollChances, shapes::ShapeFactors, OutOfRangePolicy, PreparedRoll, RollMethod,
+    RollToProcess, WeightedResult,
+};
+use std::fmt::Display;
+
+/// A problem found in a table by [`RollToProcess::lint`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableIssue {
+    /// The entry at **index** has a weight of 0.
+    ZeroWeight {
+        /// Index of the entry.
+        index: usize,
+    },
+    /// The entry at **index** has a weight but can never be picked, for example because it
+    /// covers rolls higher than the highest result of a [`PreparedRoll`].
+    UnreachableEntry {
+        /// Index of the entry.
+        index: usize,
+    },
+    /// The [`PreparedRoll`] gives **rolls** results outside of the list, which are clamped onto
+    /// the entry at **index**, adding **probability** to its chances.
+    ClampedOverflow {
+        /// Index of the entry receiving the clamped rolls.
+        index: usize,
+        /// How many different results of the roll are clamped onto the entry.
+        rolls: u64,
+        /// The chances of the roll giving one of those results.
+        probability: f64,
+    },
+    /// The weights add up to **total**, more than the `u32::MAX` the uniform and normal roll
+    /// methods can handle.
+    WeightOverflow {
+        /// The sum of every weight.
+        total: u64,
+    },
+}
+
+impl Display for TableIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableIssue::ZeroWeight { index } => write!(f, "entry {index} has a weight of 0"),
+            TableIssue::UnreachableEntry { index } => {
+                write!(f, "entry {index} can never be picked")
+            }
+            TableIssue::ClampedOverflow {
+                index,
+                rolls,
+                probability,
+            } => write!(
+                f,
+                "{rolls} out of range rolls are clamped onto entry {index}, adding {:.2}% to its chances",
+                probability * 100.0
+            ),
+            TableIssue::WeightOverflow { total } => {
+                write!(f, "the weights add up to {total}, more than {}", u32::MAX)
+            }
+        }
+    }
+}
+
+impl<T> RollToProcess<T> {
+    /// Analyses the table and returns every [`TableIssue`] found, in the order of the entries
+    /// they concern. A table without issues returns an empty list.
+    #[must_use]
+    pub fn lint(&self) -> Vec<TableIssue> {
+        lint_entries(&self.possible_results, self.roll_method, self.out_of_range)
+    }
+}
+
+/// Returns the issues found in a list of **entries** picked using **method** and **policy**.
+fn lint_entries<T>(
+    entries: &[WeightedResult<T>],
+    method: RollMethod,
+    policy: OutOfRangePolicy,
+) -> Vec<TableIssue> {
+    let mut issues = Vec::new();
+    let total: u64 = entries.iter().map(|e| u64::from(e.weight)).sum();
+    if total > u64::from(u32::MAX) {
+        issues.push(TableIssue::WeightOverflow { total });
+    }
+
+    let reachable: Vec<bool> = match method {
+        RollMethod::PreparedRoll(roll) if entries.len() > 1 => {
+            prepared_reachability(entries, &roll, policy, total, &mut issues)
+        }
+        RollMethod::Triangular { .. }
+        | RollMethod::Exponential { .. }
+        | RollMethod::Gaussian { .. }
+        | RollMethod::Linear { .. } => ShapeFactors::new(method, entries.len())
+            .map(|factor| factor > 0)
+            .collect(),
+        _ => vec![true; entries.len()],
+    };
+
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.weight == 0 {
+            issues.push(TableIssue::ZeroWeight { index });
+        } else if !reachable[index] {
+            issues.push(TableIssue::UnreachableEntry { index });
+        }
+    }
+    issues.sort_by_key(|issue| match issue {
+        TableIssue::WeightOverflow { .. } => 0,
+        TableIssue::ZeroWeight { index }
+        | TableIssue::UnreachableEntry { index }
+        | TableIssue::ClampedOverflow { index, .. } => index + 1,
+    });
+    issues
+}
+
+/// Returns whether each of the **entries** can be picked by rolling **roll**, with rolls outside
+/// of the list handled following **policy**, and reports the rolls clamped onto the first and
+/// last entries.
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn prepared_reachability<T>(
+    entries: &[WeightedResult<T>],
+    roll: &PreparedRoll,
+    policy: OutOfRangePolicy,
+    total: u64,
+    issues: &mut Vec<TableIssue>,
+) -> Vec<bool> {
+    let (low, high) = (i128::from(roll.min_result()), i128::from(roll.max_result()));
+    if total == 0 || high < low {
+        return vec![false; entries.len()];
+    }
+    let first = i128::from(roll.dice);
+    let covered = i128::from(total);
+    let last = first + covered - 1;
+    let possible = high - low + 1;
+
+    if policy == OutOfRangePolicy::Clamp {
+        let chances = RollChances::new(roll);
+        let chances_between = |from: i128, to: i128| chances.between(from as i64, to as i64);
+        if low < first {
+            let to = high.min(first - 1);
+            issues.push(TableIssue::ClampedOverflow {
+                index: 0,
+                rolls: (to - low + 1) as u64,
+                probability: chances_between(low, to),
+            });
+        }
+        if high > last {
+            let from = low.max(last + 1);
+            issues.push(TableIssue::ClampedOverflow {
+                index: entries.len() - 1,
+                rolls: (high - from + 1) as u64,
+                probability: chances_between(from, high),
+            });
+        }
+    }
+
+    let mut start = first;
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let end = start + i128::from(entry.weight);
+            let (from, to) = (start - first, end - first);
+            start = end;
+            match policy {
+                OutOfRangePolicy::Clamp => {
+                    let from = if index == 0 { i128::MIN } else { from + first };
+                    let to = if index == entries.len() - 1 {
+                        i128::MAX
+                    } else {
+                        to + first
+                    };
+                    from <= high && low < to
+                }
+                OutOfRangePolicy::Reroll | OutOfRangePolicy::Error => {
+                    from + first <= high && low < to + first
+                }
+                OutOfRangePolicy::Wrap => {
+                    if possible >= covered {
+                        true
+                    } else {
+                        // The covered positions reached, starting from the lowest roll's.
+                        let reached_from = (low - first).rem_euclid(covered);
+                        let reached_to = reached_from + possible;
+                        (from < reached_to && reached_from < to)
+                            || (from + covered < reached_to && reached_from < to + covered)
+                    }
+                }
+                OutOfRangePolicy::Scale => {
+                    // The lowest roll offset reaching each bound of the entry once scaled.
+                    let ceil_div = |a: i128, b: i128| (a + b - 1) / b;
+                    let lowest = ceil_div(from * possible, covered);
+                    lowest < possible && lowest < ceil_div(to * possible, covered)
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn table(weights: &[u32], method: RollMethod) -> RollToProcess<usize> {
+        RollToProcess::new(
+            weights
+                .iter()
+                .enumerate()
+                .map(|(result, &weight)| WeightedResult { result, weight })
+                .collect(),
+            method,
+        )
+    }
+
+    #[test]
+    fn valid_tables_have_no_issues() {
+        assert!(table(&[1, 2, 3], RollMethod::SimpleRoll).lint().is_empty());
+        assert!(table(
+            &[5, 6, 5],
+            RollMethod::PreparedRoll(PreparedRoll::new(3, 6, 0))
+        )
+        .lint()
+        .is_empty());
+    }
+
+    #[test]
+    fn lint_reports_unreachable_and_zero_weight_entries() {
+        let d6 = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 0));
+        assert_eq!(
+            table(&[3, 0, 3, 1, 2], d6).lint(),
+            vec![
+                TableIssue::ZeroWeight { index: 1 },
+                TableIssue::UnreachableEntry { index: 3 },
+                TableIssue::UnreachableEntry { index: 4 },
+            ]
+        );
+        assert_eq!(
+            table(&[1, 1, 1], RollMethod::Linear { start: 0, end: 1 }).lint(),
+            vec![TableIssue::UnreachableEntry { index: 0 }]
+        );
+    }
+
+    #[test]
+    fn lint_reports_clamped_overflow() {
+        let roll = RollMethod::PreparedRoll(PreparedRoll::new(2, 6, -2));
+        let issues = table(&[2, 7], roll).lint();
+        assert_eq!(
+            issues,
+            vec![TableIssue::ClampedOverflow {
+                index: 0,
+                rolls: 2,
+                probability: 3.0 / 36.0,
+            },]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "2 out of range rolls are clamped onto entry 0, adding 8.33% to its chances"
+        );
+        assert_eq!(
+            table(
+                &[1, 1],
+                RollMethod::PreparedRoll(PreparedRoll::new(1, 4, 0))
+            )
+            .lint(),
+            vec![TableIssue::ClampedOverflow {
+                index: 1,
+                rolls: 2,
+                probability: 0.5,
+            }]
+        );
+        assert_eq!(
+            table(
+                &[1, 1, 0],
+                RollMethod::PreparedRoll(PreparedRoll::new(1, 4, 0))
+            )
+            .lint(),
+            vec![
+                TableIssue::ClampedOverflow {
+                    index: 2,
+                    rolls: 2,
+                    probability: 0.5,
+                },
+                TableIssue::ZeroWeight { index: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_handles_dice_with_billions_of_faces() {
+        let roll = RollMethod::PreparedRoll(PreparedRoll::new(1, u32::MAX, 0));
+        let issues = table(&[1, 1], roll).lint();
+        let [TableIssue::ClampedOverflow {
+            index: 1,
+            rolls,
+            probability,
+        }] = issues[..]
+        else {
+            panic!("{issues:?}");
+        };
+        assert_eq!(rolls, u64::from(u32::MAX) - 2);
+        assert!((probability - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lint_follows_the_out_of_range_policy() {
+        let roll = RollMethod::PreparedRoll(PreparedRoll::new(1, 4, 0));
+        let lint = |policy| {
+            table(&[1, 1, 1, 1, 1, 1], roll)
+                .with_out_of_range(policy)
+                .lint()
+        };
+        let unreachable = |indexes: &[usize]| {
+            indexes
+                .iter()
+                .map(|&index| TableIssue::UnreachableEntry { index })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lint(OutOfRangePolicy::Reroll), unreachable(&[4, 5]));
+        assert_eq!(lint(OutOfRangePolicy::Wrap), unreachable(&[4, 5]));
+        assert_eq!(lint(OutOfRangePolicy::Scale), unreachable(&[2, 5]));
+
+        let wrapped = RollMethod::PreparedRoll(PreparedRoll::new(1, 2, 4));
+        assert_eq!(
+            table(&[1, 1, 1, 1], wrapped)
+                .with_out_of_range(OutOfRangePolicy::Wrap)
+                .lint(),
+            unreachable(&[2, 3])
+        );
+    }
+
+    #[test]
+    fn lint_reports_weight_overflow() {
+        assert_eq!(
+            table(&[u32::MAX, 1], RollMethod::SimpleRoll).lint(),
+            vec![TableIssue::WeightOverflow {
+                total: u64::from(u32::MAX) + 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,890 @@
+//! Loading of [`TableSet`] from hand-authored JSON, TOML and RON files, using the compact syntax
+//! described in the [`TableSet`] documentation.
+
+use crate::{
+    OutOfRangePolicy, PreparedRoll, RollMethod, RollToProcess, TableEntry, TableError, TableSet,
+    WeightedResult,
+};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+#[cfg(any(feature = "json", feature = "ron"))]
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// The reason why a file couldn't be loaded as a [`TableSet`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The file is malformed or doesn't follow the expected syntax at **line** and **column**,
+    /// both starting at 1.
+    Syntax {
+        /// The line of the problem.
+        line: usize,
+        /// The column of the problem, in characters.
+        column: usize,
+        /// What is wrong.
+        message: String,
+    },
+    /// The tables are well written but can't be resolved, because of the reference to a table
+    /// written at **location**: its line and column, both starting at 1, when they are known.
+    Table {
+        /// The line and the column, in characters, of the reference.
+        location: Option<(usize, usize)>,
+        /// Why the tables can't be resolved.
+        error: TableError,
+    },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "line {line}, column {column}: {message}"),
+            LoadError::Table {
+                location: Some((line, column)),
+                error,
+            } => write!(f, "line {line}, column {column}: {error}"),
+            LoadError::Table {
+                location: None,
+                error,
+            } => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Table { error, .. } => Some(error),
+            LoadError::Syntax { .. } => None,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> TableSet<T> {
+    /// Loads tables written in JSON with the syntax described in the [`TableSet`]
+    /// documentation.
+    ///
+    /// ```
+    /// # use seeded_dice_roller::*;
+    /// let tables: TableSet<String> = TableSet::from_json(
+    ///     r#"{
+    ///         "rare": { "entries": { "ring": 1, "amulet": 2 } },
+    ///         "hoard": {
+    ///             "roll": "3d6",
+    ///             "entries": [{ "value": "gold", "quantity": "2d4" }, { "table": "rare" }]
+    ///         }
+    ///     }"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let mut rng = SeededDiceRoller::new("seed", "step");
+    /// assert_eq!(rng.roll_on_table(&tables, "rare").unwrap()[0].value, "amulet");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LoadError`] locating the problem if the text isn't valid JSON or doesn't follow
+    /// the syntax, or if the tables can't be resolved.
+    #[cfg(feature = "json")]
+    pub fn from_json(text: &str) -> Result<Self, LoadError> {
+        let file: TableFile<T, BorrowedName> = serde_json::from_str(text).map_err(|error| {
+            let location = format!(" at line {} column {}", error.line(), error.column());
+            let message = error.to_string();
+            LoadError::Syntax {
+                line: error.line(),
+                column: error.column(),
+                message: message
+                    .strip_suffix(&location)
+                    .unwrap_or(&message)
+                    .to_string(),
+            }
+        })?;
+        file.into_table_set(text)
+    }
+
+    /// Loads tables written in TOML with the syntax described in the [`TableSet`]
+    /// documentation.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LoadError`] locating the problem if the text isn't valid TOML or doesn't follow
+    /// the syntax, or if the tables can't be resolved.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, LoadError> {
+        let file: TableFile<T, toml::Spanned<String>> = toml::from_str(text).map_err(|error| {
+            let offset = error.span().map_or(0, |span| span.start);
+            let (line, column) = line_and_column(text, offset);
+            LoadError::Syntax {
+                line,
+                column,
+                message: error.message().to_string(),
+            }
+        })?;
+        file.into_table_set(text)
+    }
+
+    /// Loads tables written in RON with the syntax described in the [`TableSet`]
+    /// documentation, tables and detailed entries being written as structs:
+    /// `{ "rare": (entries: { "ring": 1, "gold": (quantity: "2d4") }) }`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LoadError`] locating the problem if the text isn't valid RON or doesn't follow
+    /// the syntax, or if the tables can't be resolved.
+    #[cfg(feature = "ron")]
+    pub fn from_ron(text: &str) -> Result<Self, LoadError> {
+        let file: TableFile<T, BorrowedName> =
+            ron::from_str(text).map_err(|error| LoadError::Syntax {
+                line: error.position.line,
+                column: error.position.col,
+                message: error.code.to_string(),
+            })?;
+        file.into_table_set(text)
+    }
+}
+
+/// Returns the line and column, both starting at 1, of the character at **offset** bytes in
+/// **text**.
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// The name of a table referred to by an entry, as read from a file.
+trait Reference {
+    /// Returns the name of the table.
+    fn name(&self) -> &str;
+
+    /// Returns the offset in **text** of the name, quotes included, when it is known.
+    fn offset(&self, text: &str) -> Option<usize>;
+}
+
+#[cfg(feature = "toml")]
+impl Reference for toml::Spanned<String> {
+    fn name(&self) -> &str {
+        self.get_ref()
+    }
+
+    fn offset(&self, _text: &str) -> Option<usize> {
+        Some(self.span().start)
+    }
+}
+
+/// A name read from a JSON or RON text, borrowed from it and so located when it is written
+/// without escapes.
+#[cfg(any(feature = "json", feature = "ron"))]
+struct BorrowedName<'a>(Cow<'a, str>);
+
+#[cfg(any(feature = "json", feature = "ron"))]
+impl Reference for BorrowedName<'_> {
+    fn name(&self) -> &str {
+        &self.0
+    }
+
+    fn offset(&self, text: &str) -> Option<usize> {
+        let Cow::Borrowed(name) = self.0 else {
+            return None;
+        };
+        let start = (name.as_ptr() as usize).checked_sub(text.as_ptr() as usize)?;
+        if start + name.len() > text.len() {
+            return None;
+        }
+        Some(if text[..start].ends_with(['"', '\'']) {
+            start - 1
+        } else {
+            start
+        })
+    }
+}
+
+#[cfg(any(feature = "json", feature = "ron"))]
+impl<'de> Deserialize<'de> for BorrowedName<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl<'de> Visitor<'de> for NameVisitor {
+            type Value = BorrowedName<'de>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "the name of a table")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, name: &'de str) -> Result<Self::Value, E> {
+                Ok(BorrowedName(Cow::Borrowed(name)))
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(BorrowedName(Cow::Owned(name.to_string())))
+            }
+        }
+
+        deserializer.deserialize_str(NameVisitor)
+    }
+}
+
+/// The content of a file, in the order the tables are written, with the names of the tables
+/// referred to read as **R**.
+struct TableFile<T, R> {
+    tables: Vec<(String, RollToProcess<TableEntry<T>>)>,
+    /// The table each reference is written in and the reference, in file order.
+    references: Vec<(String, R)>,
+}
+
+impl<T, R: Reference> TableFile<T, R> {
+    /// Returns the tables as a validated [`TableSet`], locating the reference at fault in the
+    /// **text** they were read from when they can't be resolved.
+    fn into_table_set(self, text: &str) -> Result<TableSet<T>, LoadError> {
+        let references = self.references;
+        let mut set = TableSet::new();
+        for (name, table) in self.tables {
+            set.insert(name, table);
+        }
+        set.validate().map_err(|error| {
+            let at_fault = match &error {
+                TableError::UnknownTable(name) => {
+                    references.iter().find(|(_, to)| to.name() == name)
+                }
+                TableError::Cycle(names) => match names.as_slice() {
+                    [.., from, to] => references
+                        .iter()
+                        .find(|(name, reference)| name == from && reference.name() == to),
+                    _ => None,
+                },
+                TableError::TooDeep { max_depth } => references
+                    .iter()
+                    .find(|(_, to)| set.depth(to.name()) == Ok(*max_depth)),
+                TableError::TooManyOutcomes { .. } | TableError::OutOfRange(_) => None,
+            };
+            LoadError::Table {
+                location: at_fault
+                    .and_then(|(_, reference)| reference.offset(text))
+                    .map(|offset| line_and_column(text, offset)),
+                error,
+            }
+        })?;
+        Ok(set)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, R: Deserialize<'de> + Reference> Deserialize<'de>
+    for TableFile<T, R>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FileVisitor<T, R>(PhantomData<(T, R)>);
+
+        impl<'de, T: Deserialize<'de>, R: Deserialize<'de> + Reference> Visitor<'de> for FileVisitor<T, R> {
+            type Value = TableFile<T, R>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a map of table names to tables")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut tables: Vec<(String, RollToProcess<TableEntry<T>>)> = Vec::new();
+                let mut references = Vec::new();
+                while let Some(name) = map.next_key::<String>()? {
+                    if tables.iter().any(|(n, _)| *n == name) {
+                        return Err(de::Error::custom(format!(
+                            "the table \"{name}\" is defined twice"
+                        )));
+                    }
+                    let table: TableData<T, R> = map.next_value()?;
+                    references.extend(table.entries.1.into_iter().map(|r| (name.clone(), r)));
+                    tables.push((
+                        name,
+                        RollToProcess::new(table.entries.0, table.roll.0)
+                            .with_out_of_range(table.out_of_range.0),
+                    ));
+                }
+                Ok(TableFile { tables, references })
+            }
+        }
+
+        deserializer.deserialize_map(FileVisitor(PhantomData))
+    }
+}
+
+/// A table as written in a file.
+#[derive(Deserialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(deserialize = "T: Deserialize<'de>, R: Deserialize<'de> + Reference")
+)]
+struct TableData<T, R> {
+    #[serde(default)]
+    roll: Notation<RollMethod>,
+    #[serde(default)]
+    out_of_range: Notation<OutOfRangePolicy>,
+    entries: Entries<T, R>,
+}
+
+/// A value written with the notation parsed by its `FromStr` implementation.
+#[derive(Default)]
+struct Notation<N>(N);
+
+impl<'de, N> Deserialize<'de> for Notation<N>
+where
+    N: FromStr,
+    N::Err: Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map(Notation).map_err(de::Error::custom)
+    }
+}
+
+/// The entries of a table, written either as a map or as a list, and the references they make
+/// in the order they are written.
+struct Entries<T, R>(Vec<WeightedResult<TableEntry<T>>>, Vec<R>);
+
+impl<'de, T: Deserialize<'de>, R: Deserialize<'de> + Reference> Deserialize<'de> for Entries<T, R> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor<T, R>(PhantomData<(T, R)>);
+
+        impl<'de, T: Deserialize<'de>, R: Deserialize<'de> + Reference> Visitor<'de>
+            for EntriesVisitor<T, R>
+        {
+            type Value = Entries<T, R>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a map of values to weights, or a list of entries")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    let value = T::deserialize(de::value::StrDeserializer::<A::Error>::new(&key))?;
+                    let details: MapEntryData = map.next_value()?;
+                    entries.push(details.0.into_entry(value).map_err(de::Error::custom)?);
+                }
+                Ok(Entries(entries, Vec::new()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let (mut entries, mut references) = (Vec::new(), Vec::new());
+                while let Some(entry) = seq.next_element::<ListEntry<T, R>>()? {
+                    entries.push(entry.0);
+                    references.extend(entry.1);
+                }
+                Ok(Entries(entries, references))
+            }
+        }
+
+        deserializer.deserialize_any(EntriesVisitor(PhantomData))
+    }
+}
+
+/// Deserializes an optional field that is present, without requiring RON's `Some(...)`.
+fn present<'de, D: Deserializer<'de>, X: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<X>, D::Error> {
+    X::deserialize(deserializer).map(Some)
+}
+
+/// The details of an entry.
+#[derive(Deserialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(deserialize = "T: Deserialize<'de>, R: Deserialize<'de>")
+)]
+struct EntryFields<T, R> {
+    #[serde(default, deserialize_with = "present")]
+    value: Option<T>,
+    #[serde(default, deserialize_with = "present")]
+    table: Option<R>,
+    #[serde(default, deserialize_with = "present")]
+    quantity: Option<Notation<PreparedRoll>>,
+    #[serde(default, deserialize_with = "present")]
+    times: Option<u32>,
+    #[serde(default, deserialize_with = "present")]
+    weight: Option<u32>,
+}
+
+impl<T, R: Reference> EntryFields<T, R> {
+    /// Returns the entry described and the reference it makes.
+    fn into_entry(self) -> Result<(WeightedResult<TableEntry<T>>, Option<R>), &'static str> {
+        let name = self.table.as_ref().map(|table| table.name().to_string());
+        let entry = EntryFields {
+            value: self.value,
+            table: name,
+            quantity: self.quantity,
+            times: self.times,
+            weight: self.weight,
+        }
+        .into_named_entry()?;
+        Ok((entry, self.table))
+    }
+}
+
+impl<T> EntryFields<T, String> {
+    /// Returns the entry described.
+    fn into_named_entry(self) -> Result<WeightedResult<TableEntry<T>>, &'static str> {
+        let mut entry = match (self.value, self.table, self.quantity) {
+            (Some(_), Some(_), _) => return Err("an entry can't have both a value and a table"),
+            (None, Some(_), Some(_)) => return Err("a table can't have a quantity"),
+            (None, None, _) => return Err("an entry needs either a value or a table"),
+            (Some(value), None, Some(Notation(roll))) => TableEntry::Quantity { value, roll },
+            (Some(value), None, None) => TableEntry::Value(value),
+            (None, Some(table), None) => TableEntry::Table(table),
+        };
+        if let Some(times) = self.times {
+            entry = TableEntry::Repeat {
+                times,
+                entry: Box::new(entry),
+            };
+        }
+        Ok(WeightedResult::new(entry, self.weight.unwrap_or(1)))
+    }
+}
+
+/// The details of an entry of a map, whose value is given by its key.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MapEntry {
+    #[serde(default, deserialize_with = "present")]
+    quantity: Option<Notation<PreparedRoll>>,
+    #[serde(default, deserialize_with = "present")]
+    times: Option<u32>,
+    #[serde(default, deserialize_with = "present")]
+    weight: Option<u32>,
+}
+
+impl MapEntry {
+    /// Returns the entry described, giving the **value**.
+    fn into_entry<T>(self, value: T) -> Result<WeightedResult<TableEntry<T>>, &'static str> {
+        EntryFields {
+            value: Some(value),
+            table: None,
+            quantity: self.quantity,
+            times: self.times,
+            weight: self.weight,
+        }
+        .into_named_entry()
+    }
+}
+
+/// What follows the value of an entry in a map: either a weight or the details of the entry.
+struct MapEntryData(MapEntry);
+
+impl<'de> Deserialize<'de> for MapEntryData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapEntryVisitor;
+
+        impl<'de> Visitor<'de> for MapEntryVisitor {
+            type Value = MapEntryData;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a weight or the details of an entry")
+            }
+
+            fn visit_u64<E: de::Error>(self, weight: u64) -> Result<Self::Value, E> {
+                let weight = u32::try_from(weight).map_err(|_| {
+                    E::invalid_value(
+                        de::Unexpected::Unsigned(weight),
+                        &"a weight up to 4294967295",
+                    )
+                })?;
+                Ok(MapEntryData(MapEntry {
+                    weight: Some(weight),
+                    ..MapEntry::default()
+                }))
+            }
+
+            fn visit_i64<E: de::Error>(self, weight: i64) -> Result<Self::Value, E> {
+                let weight = u64::try_from(weight).map_err(|_| {
+                    E::invalid_value(de::Unexpected::Signed(weight), &"a positive weight")
+                })?;
+                self.visit_u64(weight)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                MapEntry::deserialize(de::value::MapAccessDeserializer::new(map)).map(MapEntryData)
+            }
+        }
+
+        deserializer.deserialize_any(MapEntryVisitor)
+    }
+}
+
+/// An entry of a list: either a value or the details of an entry, and the reference it makes.
+struct ListEntry<T, R>(WeightedResult<TableEntry<T>>, Option<R>);
+
+impl<T, R> ListEntry<T, R> {
+    /// Returns the entry of weight 1 whose value is read by the **deserializer** of a bare value.
+    fn bare<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let value = T::deserialize(deserializer)?;
+        Ok(ListEntry(
+            WeightedResult::new(TableEntry::Value(value), 1),
+            None,
+        ))
+    }
+}
+
+impl<'de, T: Deserialize<'de>, R: Deserialize<'de> + Reference> Deserialize<'de>
+    for ListEntry<T, R>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ListEntryVisitor<T, R>(PhantomData<(T, R)>);
+
+        impl<'de, T: Deserialize<'de>, R: Deserialize<'de> + Reference> Visitor<'de>
+            for ListEntryVisitor<T, R>
+        {
+            type Value = ListEntry<T, R>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a value or the details of an entry")
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+                ListEntry::bare(value.into_deserializer())
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                ListEntry::bare(value.into_deserializer())
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                ListEntry::bare(value.into_deserializer())
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                ListEntry::bare(value.into_deserializer())
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                ListEntry::bare(value.into_deserializer())
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let fields = EntryFields::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                let (entry, reference) = fields.into_entry().map_err(de::Error::custom)?;
+                Ok(ListEntry(entry, reference))
+            }
+        }
+
+        deserializer.deserialize_any(ListEntryVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SeededDiceRoller, DEFAULT_MAX_DEPTH};
+
+    /// Returns the entries of the table **name** as (entry, weight) pairs.
+    fn entries(tables: &TableSet<String>, name: &str) -> Vec<(TableEntry<String>, u32)> {
+        tables
+            .get(name)
+            .unwrap()
+            .possible_results
+            .iter()
+            .map(|r| (r.result.clone(), r.weight))
+            .collect()
+    }
+
+    fn value(value: &str) -> TableEntry<String> {
+        TableEntry::Value(value.to_string())
+    }
+
+    /// Checks that **tables** hold what the test files describe.
+    fn check_tables(tables: &TableSet<String>) {
+        assert_eq!(
+            entries(tables, "rare"),
+            vec![
+                (value("ring"), 1),
+                (value("amulet"), 2),
+                (value("crown"), 1)
+            ]
+        );
+        let rare = tables.get("rare").unwrap();
+        assert_eq!(rare.roll_method, RollMethod::SimpleRoll);
+        assert_eq!(rare.out_of_range, OutOfRangePolicy::Clamp);
+
+        let hoard = tables.get("hoard").unwrap();
+        assert_eq!(
+            hoard.roll_method,
+            RollMethod::PreparedRoll(PreparedRoll::new(3, 6, 0))
+        );
+        assert_eq!(hoard.out_of_range, OutOfRangePolicy::Wrap);
+        assert_eq!(
+            entries(tables, "hoard"),
+            vec![
+                (
+                    TableEntry::Quantity {
+                        value: "gold".to_string(),
+                        roll: PreparedRoll::new(2, 4, 0),
+                    },
+                    3
+                ),
+                (
+                    TableEntry::Repeat {
+                        times: 2,
+                        entry: Box::new(TableEntry::Table("rare".to_string())),
+                    },
+                    1
+                ),
+                (value("potion"), 1),
+            ]
+        );
+        assert_eq!(
+            entries(tables, "coins"),
+            vec![(
+                TableEntry::Quantity {
+                    value: "copper".to_string(),
+                    roll: PreparedRoll::new(1, 6, 2),
+                },
+                4
+            )]
+        );
+    }
+
+    #[test]
+    fn tables_are_loaded_from_json() {
+        let tables = TableSet::from_json(
+            r#"{
+                "rare": { "entries": { "ring": 1, "amulet": 2, "crown": 1 } },
+                "hoard": {
+                    "roll": "3d6",
+                    "out_of_range": "wrap",
+                    "entries": [
+                        { "value": "gold", "quantity": "2d4", "weight": 3 },
+                        { "table": "rare", "times": 2 },
+                        "potion"
+                    ]
+                },
+                "coins": { "entries": { "copper": { "quantity": "1d6+2", "weight": 4 } } }
+            }"#,
+        )
+        .unwrap();
+        check_tables(&tables);
+    }
+
+    #[test]
+    fn tables_are_loaded_from_toml() {
+        let tables = TableSet::from_toml(
+            r#"
+            [rare]
+            entries = { ring = 1, amulet = 2, crown = 1 }
+
+            [hoard]
+            roll = "3d6"
+            out_of_range = "wrap"
+            entries = [
+                { value = "gold", quantity = "2d4", weight = 3 },
+                { table = "rare", times = 2 },
+                "potion",
+            ]
+
+            [coins.entries]
+            copper = { quantity = "1d6+2", weight = 4 }
+            "#,
+        )
+        .unwrap();
+        check_tables(&tables);
+    }
+
+    #[test]
+    fn tables_are_loaded_from_ron() {
+        let tables = TableSet::from_ron(
+            r#"{
+                "rare": (entries: { "ring": 1, "amulet": 2, "crown": 1 }),
+                "hoard": (
+                    roll: "3d6",
+                    out_of_range: "wrap",
+                    entries: [
+                        (value: "gold", quantity: "2d4", weight: 3),
+                        (table: "rare", times: 2),
+                        "potion",
+                    ],
+                ),
+                "coins": (entries: { "copper": (quantity: "1d6+2", weight: 4) }),
+            }"#,
+        )
+        .unwrap();
+        check_tables(&tables);
+    }
+
+    #[test]
+    fn errors_give_their_line() {
+        let error = |result: Result<TableSet<String>, LoadError>| match result.unwrap_err() {
+            LoadError::Syntax { line, message, .. } => (line, message),
+            LoadError::Table { error, .. } => panic!("unexpected {error}"),
+        };
+
+        let (line, message) = error(TableSet::from_json(
+            "{\n  \"rare\": {\n    \"roll\": \"3d\",\n    \"entries\": {}\n  }\n}",
+        ));
+        assert_eq!(line, 3);
+        assert!(message.starts_with("invalid \"3d\""), "{message}");
+
+        let (line, message) = error(TableSet::from_toml(
+            "[rare]\nentries = [\n  { value = \"ring\", table = \"other\" },\n]\n",
+        ));
+        assert_eq!(line, 3);
+        assert_eq!(message, "an entry can't have both a value and a table");
+
+        let (line, message) = error(TableSet::from_ron(
+            "{\n  \"rare\": (\n    entries: { \"ring\": -1 },\n  ),\n}",
+        ));
+        assert_eq!(line, 3);
+        assert!(message.contains("positive weight"), "{message}");
+
+        let (line, message) = error(TableSet::from_json(
+            "{\n  \"a\": { \"entries\": [\"x\"] },\n  \"a\": { \"entries\": [\"y\"] }\n}",
+        ));
+        assert_eq!(line, 3);
+        assert_eq!(message, "the table \"a\" is defined twice");
+
+        let (line, message) = error(TableSet::from_json(
+            "{\n  \"a\": {\n    \"entries\": [\"x\"],\n    \"weight\": 2\n  }\n}",
+        ));
+        assert_eq!(line, 4);
+        assert!(message.starts_with("unknown field `weight`"), "{message}");
+    }
+
+    #[test]
+    fn unresolvable_tables_are_reported_where_they_are_referred_to() {
+        let error = |result: Result<TableSet<String>, LoadError>| match result.unwrap_err() {
+            LoadError::Table {
+                location: Some((line, column)),
+                error,
+            } => (line, column, error),
+            LoadError::Table { error, .. } => panic!("unlocated {error}"),
+            LoadError::Syntax { message, .. } => panic!("unexpected {message}"),
+        };
+
+        assert_eq!(
+            error(TableSet::from_json(
+                r#"{ "a": { "entries": [{ "table": "b" }] } }"#
+            )),
+            (1, 33, TableError::UnknownTable("b".to_string()))
+        );
+        assert_eq!(
+            error(TableSet::from_toml(
+                "[a]\n# entries = [{ table = \"b\" }]\nentries = [\n  'x',\n  { table = \"b\" },\n]\n\
+                 [b]\nentries = [{ table = 'a', times = 2 }]\n",
+            )),
+            (
+                8,
+                22,
+                TableError::Cycle(vec!["a".to_string(), "b".to_string(), "a".to_string()])
+            )
+        );
+        assert_eq!(
+            error(TableSet::from_ron(
+                "{\n  \"a\": (entries: [\"table\", (value: \"x\")]),\n  // (table: \"c\")\n  \
+                 \"b\": (entries: [(value: \"y\"), (table: \"c\")]),\n}",
+            )),
+            (4, 41, TableError::UnknownTable("c".to_string()))
+        );
+
+        let chain: Vec<String> = (0..=DEFAULT_MAX_DEPTH)
+            .map(|i| {
+                format!(
+                    "\"t{i}\": {{ \"entries\": [{{ \"table\": \"t{}\" }}] }}",
+                    i + 1
+                )
+            })
+            .chain([format!(
+                "\"t{}\": {{ \"entries\": [\"x\"] }}",
+                DEFAULT_MAX_DEPTH + 1
+            )])
+            .collect();
+        let (line, _, error) = error(TableSet::from_json(&format!(
+            "{{\n{}\n}}",
+            chain.join(",\n")
+        )));
+        assert_eq!(
+            error,
+            TableError::TooDeep {
+                max_depth: DEFAULT_MAX_DEPTH
+            }
+        );
+        // t1 is the first table nesting too many tables, through its reference to t2.
+        assert_eq!(line, 3);
+    }
+
+    #[test]
+    fn only_references_are_located() {
+        let error = TableSet::<serde_json::Value>::from_json(
+            r#"{ "a": { "entries": [{ "value": { "table": "decoy" } }, { "table": "missing" }] } }"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 68: no table is named \"missing\""
+        );
+
+        let escaped = TableSet::<String>::from_json(
+            r#"{ "a": { "entries": [{ "table": "\u006dissing" }] } }"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            escaped,
+            LoadError::Table {
+                location: None,
+                error: TableError::UnknownTable("missing".to_string()),
+            }
+        );
+        assert_eq!(escaped.to_string(), "no table is named \"missing\"");
+    }
+
+    #[test]
+    fn tables_whose_weights_are_all_zero_roll_nothing() {
+        let tables = TableSet::<String>::from_json(
+            r#"{
+                "empty": { "entries": { "a": 0, "b": 0 } },
+                "gaussian": { "roll": "gaussian(3)", "entries": { "a": 0, "b": 0 } },
+                "cellar": { "entries": [{ "table": "empty" }, { "table": "gaussian" }] }
+            }"#,
+        )
+        .unwrap();
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        for name in ["empty", "gaussian", "cellar"] {
+            assert_eq!(rng.roll_on_table(&tables, name), Ok(vec![]));
+        }
+    }
+
+    #[test]
+    fn bare_numbers_and_booleans_are_list_values() {
+        let numbers = TableSet::<u32>::from_json(
+            r#"{ "d": { "entries": [1, 2, { "value": 3, "weight": 2 }] } }"#,
+        )
+        .unwrap();
+        let weights: Vec<(TableEntry<u32>, u32)> = numbers
+            .get("d")
+            .unwrap()
+            .possible_results
+            .iter()
+            .map(|r| (r.result.clone(), r.weight))
+            .collect();
+        assert_eq!(
+            weights,
+            vec![
+                (TableEntry::Value(1), 1),
+                (TableEntry::Value(2), 1),
+                (TableEntry::Value(3), 2)
+            ]
+        );
+
+        let flags = TableSet::<bool>::from_toml("[coin]\nentries = [true, false]\n").unwrap();
+        assert_eq!(flags.get("coin").unwrap().possible_results.len(), 2);
+        let floats = TableSet::<f64>::from_ron("{ \"f\": (entries: [0.5, 2]) }").unwrap();
+        assert_eq!(
+            floats.get("f").unwrap().possible_results[1].result,
+            TableEntry::Value(2.0)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Parsing and rendering of roll tables written in Markdown, like:
+//!
+//! ```markdown
+//! | d20   | Result |
+//! |-------|--------|
+//! | 1-4   | Goblin |
+//! | 5-15  | Orc    |
+//! | 16-20 | Troll  |
+//! ```
+
+use crate::probabilities::RollChances;
+use crate::{PreparedRoll, RangedEntry, RangedTable, RangedTableError};
+use std::fmt::{Display, Write};
+
+/// Reasons why a Markdown text can't be parsed as a [`RangedTable`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkdownError {
+    /// The text has no table whose first header cell is a roll like "d20" or "2d6".
+    NoTable,
+    /// The row at **line**, starting at 1, isn't a valid entry.
+    Row {
+        /// The line of the row.
+        line: usize,
+        /// What is wrong.
+        message: String,
+    },
+    /// The entries overlap, leave gaps, or don't cover every result of the roll.
+    Ranges(RangedTableError),
+}
+
+impl Display for MarkdownError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkdownError::NoTable => write!(f, "no table starting with a roll like \"d20\""),
+            MarkdownError::Row { line, message } => write!(f, "line {line}: {message}"),
+            MarkdownError::Ranges(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for MarkdownError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MarkdownError::Ranges(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl RangedTable<String> {
+    /// Parses the first Markdown table of **text** whose header starts with the roll to make, like
+    /// "d20", "2d6" or "d%". Each row gives in its first cell the rolls covered, like "7",
+    /// "1-4" or "1–4", and in its second cell the result. Other columns are ignored.
+    ///
+    /// With a d100, "00" is read as 100.
+    ///
+    /// ```
+    /// # use seeded_dice_roller::*;
+    /// let table = RangedTable::from_markdown(
+    ///     "| d20 | Result |\n\
+    ///      |-----|--------|\n\
+    ///      | 1-4 | Goblin |\n\
+    ///      | 5-20 | Orc |",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(table.entries()[0], RangedEntry::new("Goblin".to_string(), 1, 4));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MarkdownError`] if no table starts with a roll, if a row's rolls can't be read,
+    /// or if the entries overlap, leave gaps or don't cover every result of the roll.
+    pub fn from_markdown(text: &str) -> Result<Self, MarkdownError> {
+        let mut lines = text.lines().enumerate().peekable();
+        let roll = loop {
+            let (_, line) = lines.next().ok_or(MarkdownError::NoTable)?;
+            let header_roll = table_row(line)
+                .and_then(|cells| cells.first().and_then(|cell| cell.parse().ok()))
+                .filter(|roll: &PreparedRoll| roll.dice > 0);
+            let followed_by_separator = lines.peek().is_some_and(|(_, next)| {
+                table_row(next).is_some_and(|cells| cells.iter().all(|cell| is_separator(cell)))
+            });
+            if let (Some(roll), true) = (header_roll, followed_by_separator) {
+                break roll;
+            }
+        };
+
+        let mut entries = Vec::new();
+        while let Some((index, line)) = lines.next_if(|(_, line)| table_row(line).is_some()) {
+            let cells = table_row(line).unwrap_or_default();
+            if cells.iter().all(|cell| is_separator(cell)) {
+                continue;
+            }
+            let row_error = |message: String| MarkdownError::Row {
+                line: index + 1,
+                message,
+            };
+            let (min, max) = parse_rolls(&cells[0], &roll).ok_or_else(|| {
+                row_error(format!("\"{}\" isn't a roll or range of rolls", cells[0]))
+            })?;
+            let result = cells
+                .get(1)
+                .ok_or_else(|| row_error("the row has no result".to_string()))?;
+            entries.push(RangedEntry::new(result.clone(), min, max));
+        }
+
+        RangedTable::new(roll, entries).map_err(MarkdownError::Ranges)
+    }
+}
+
+impl<T> RangedTable<T> {
+    /// Renders the table in Markdown, using **text** to write each result, with the chances of
+    /// each entry in a third column. Entries are sorted by range, and the rolls of a d100 are
+    /// written with two digits, 100 being written "00".
+    ///
+    /// ```
+    /// # use seeded_dice_roller::*;
+    /// let table = RangedTable::new(
+    ///     PreparedRoll::new(1, 20, 0),
+    ///     vec![RangedEntry::new("Goblin", 1, 4), RangedEntry::new("Orc", 5, 20)],
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     table.to_markdown(|r| r.to_string()),
+    ///     "| d20 | Result | Chance |\n\
+    ///      |:---:|--------|-------:|\n\
+    ///      | 1-4 | Goblin | 20% |\n\
+    ///      | 5-20 | Orc | 80% |\n"
+    /// );
+    /// ```
+    pub fn to_markdown(&self, mut text: impl FnMut(&T) -> String) -> String {
+        let roll = self.roll();
+        let chances = RollChances::new(roll);
+        let percent = roll.dice == 1 && roll.die_type == 100 && roll.modifier == 0;
+        let write_roll = |value: i64| match value {
+            100 if percent => "00".to_string(),
+            _ if percent => format!("{value:02}"),
+            _ => value.to_string(),
+        };
+
+        let header = match roll.formula() {
+            formula if roll.dice == 1 => formula[1..].to_string(),
+            formula => formula,
+        };
+        let mut markdown = format!("| {header} | Result | Chance |\n|:---:|--------|-------:|\n");
+        let mut entries: Vec<&RangedEntry<T>> = self.entries().iter().collect();
+        entries.sort_by_key(|entry| entry.min);
+        for entry in entries {
+            let rolls = if entry.min == entry.max {
+                write_roll(entry.min)
+            } else {
+                format!("{}-{}", write_roll(entry.min), write_roll(entry.max))
+            };
+            let chance = chances.between(entry.min, entry.max);
+            let _ = writeln!(
+                markdown,
+                "| {rolls} | {} | {} |",
+                text(&entry.result).replace('|', "\\|"),
+                format_percentage(chance)
+            );
+        }
+        markdown
+    }
+}
+
+/// Returns the cells of a Markdown table **line**, or **None** if it isn't a table row.
+fn table_row(line: &str) -> Option<Vec<String>> {
+    let line = line.trim();
+    let inner = line.strip_prefix('|')?;
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    let mut cells = vec![String::new()];
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut()?.push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut()?.push(c),
+        }
+    }
+    Some(cells.iter().map(|cell| cell.trim().to_string()).collect())
+}
+
+/// Returns true if a **cell** separates the header of a table from its rows, like "---" or ":-:".
+fn is_separator(cell: &str) -> bool {
+    cell.contains('-') && cell.chars().all(|c| matches!(c, '-' | ':' | ' '))
+}
+
+/// Returns the lowest and highest rolls written in a **cell** like "7", "1-4", "01–15" or "00".
+fn parse_rolls(cell: &str, roll: &PreparedRoll) -> Option<(i64, i64)> {
+    let value = |text: &str| -> Option<i64> {
+        let text = text.trim();
+        if text == "00" && roll.max_result() == 100 {
+            return Some(100);
+        }
+        text.parse().ok()
+    };
+    let cell = cell.trim();
+    // Skips the first character so negative single rolls like "-1" aren't read as ranges.
+    let split = cell
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| matches!(c, '-' | '–' | '—'))
+        .map(|(index, _)| index);
+    match split {
+        Some(index) => {
+            let separator = cell[index..].chars().next()?;
+            Some((
+                value(&cell[..index])?,
+                value(&cell[index + separator.len_utf8()..])?,
+            ))
+        }
+        None => value(cell).map(|single| (single, single)),
+    }
+}
+
+/// Writes a **chance** as a percentage with up to two decimals, like "20%" or "2.78%".
+fn format_percentage(chance: f64) -> String {
+    let formatted = format!("{:.2}", chance * 100.0);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    format!("{trimmed}%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RollToProcess, SeededDiceRoller};
+
+    const ENCOUNTERS: &str = "# Encounters
+
+Roll on this table when the party camps.
+
+| d100  | Result           | Notes |
+|:-----:|------------------|-------|
+| 01–15 | Goblin           |       |
+| 16-40 | Orc \\| Orc chief | rare  |
+| 41-00 | Troll            |       |
+
+| d6 | Other |
+|----|-------|
+| 1  | Ignored |
+";
+
+    #[test]
+    fn markdown_tables_are_parsed() {
+        let table = RangedTable::from_markdown(ENCOUNTERS).unwrap();
+        assert_eq!(*table.roll(), PreparedRoll::new(1, 100, 0));
+        assert_eq!(
+            table.entries(),
+            &[
+                RangedEntry::new("Goblin".to_string(), 1, 15),
+                RangedEntry::new("Orc | Orc chief".to_string(), 16, 40),
+                RangedEntry::new("Troll".to_string(), 41, 100),
+            ]
+        );
+
+        let to_process: RollToProcess<String> = table.into();
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        assert!(rng.get_result_index(&to_process).is_some());
+    }
+
+    #[test]
+    fn markdown_tables_are_rendered() {
+        let table = RangedTable::from_markdown(ENCOUNTERS).unwrap();
+        assert_eq!(
+            table.to_markdown(Clone::clone),
+            "| d100 | Result | Chance |\n\
+             |:---:|--------|-------:|\n\
+             | 01-15 | Goblin | 15% |\n\
+             | 16-40 | Orc \\| Orc chief | 25% |\n\
+             | 41-00 | Troll | 60% |\n"
+        );
+
+        let table = RangedTable::new(
+            PreparedRoll::new(2, 6, 0),
+            vec![
+                RangedEntry::new("snake eyes", 2, 2),
+                RangedEntry::new("anything else", 3, 12),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            table.to_markdown(|r| (*r).to_string()),
+            "| 2d6 | Result | Chance |\n\
+             |:---:|--------|-------:|\n\
+             | 2 | snake eyes | 2.78% |\n\
+             | 3-12 | anything else | 97.22% |\n"
+        );
+        assert_eq!(
+            RangedTable::from_markdown(&table.to_markdown(|r| (*r).to_string()))
+                .unwrap()
+                .entries()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn invalid_markdown_tables_are_reported() {
+        assert_eq!(
+            RangedTable::from_markdown("| Name | Result |\n|---|---|\n| 1 | a |"),
+            Err(MarkdownError::NoTable)
+        );
+        assert_eq!(
+            RangedTable::from_markdown("| d6 | Result |\n|---|---|\n| 1-3 | a |\n| four | b |"),
+            Err(MarkdownError::Row {
+                line: 4,
+                message: "\"four\" isn't a roll or range of rolls".to_string()
+            })
+        );
+        assert_eq!(
+            RangedTable::from_markdown("| d6 | Result |\n|---|---|\n| 1-3 | a |\n| 5-6 | b |"),
+            Err(MarkdownError::Ranges(RangedTableError::Gap {
+                min: 4,
+                max: 4
+            }))
+        );
+    }
+}
@@ -0,0 +1,639 @@
+//! Tables whose entries can refer to other tables, roll quantities or repeat picks, resolved
+//! recursively into a list of outcomes.
+
+use crate::{OutOfRangeError, PreparedRoll, RollToProcess, SeededDiceRoller};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// The default maximum number of tables that can be nested while resolving a [`TableSet`].
+pub const DEFAULT_MAX_DEPTH: usize = 16;
+
+/// The default maximum number of entries resolved while resolving a [`TableSet`].
+pub const DEFAULT_MAX_OUTCOMES: usize = 10_000;
+
+/// An entry of a table stored in a [`TableSet`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TableEntry<T> {
+    /// Gives the value once.
+    Value(T),
+    /// Gives the value as many times as the result of the roll, like "2d4 gold".
+    Quantity {
+        /// The value given.
+        value: T,
+        /// The roll giving the quantity.
+        roll: PreparedRoll,
+    },
+    /// Picks an entry in the table of the same [`TableSet`] with that name.
+    Table(String),
+    /// Resolves the entry **times** times, like "roll twice on the rare table".
+    Repeat {
+        /// The number of times the entry is resolved.
+        times: u32,
+        /// The entry to resolve.
+        entry: Box<TableEntry<T>>,
+    },
+}
+
+impl<T> TableEntry<T> {
+    /// Calls **f** with the name of every table this entry refers to.
+    pub(crate) fn for_each_reference<'a>(&'a self, f: &mut impl FnMut(&'a str)) {
+        match self {
+            TableEntry::Value(_) | TableEntry::Quantity { .. } => {}
+            TableEntry::Table(name) => f(name),
+            TableEntry::Repeat { entry, .. } => entry.for_each_reference(f),
+        }
+    }
+}
+
+impl<T> Display for TableEntry<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableEntry::Value(_) => write!(f, "Value"),
+            TableEntry::Quantity { roll, .. } => write!(f, "Quantity({roll})"),
+            TableEntry::Table(name) => write!(f, "Table({name})"),
+            TableEntry::Repeat { times, entry } => write!(f, "Repeat({times} times {entry})"),
+        }
+    }
+}
+
+/// A value given by resolving a [`TableSet`], **quantity** times.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableOutcome<T> {
+    /// The value given.
+    pub value: T,
+    /// How many times the value is given.
+    pub quantity: i64,
+}
+
+/// Reasons why a [`TableSet`] can't be resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// No table has that name.
+    UnknownTable(String),
+    /// The tables refer to each other in a loop, listed from the first table of the loop to the
+    /// table referring to it again.
+    Cycle(Vec<String>),
+    /// More than **`max_depth`** tables were nested.
+    TooDeep {
+        /// The maximum number of nested tables.
+        max_depth: usize,
+    },
+    /// More than **`max_outcomes`** entries were resolved, counting every repeat.
+    TooManyOutcomes {
+        /// The maximum number of entries resolved.
+        max_outcomes: usize,
+    },
+    /// A table with an [`crate::OutOfRangePolicy::Error`] policy rolled out of range.
+    OutOfRange(OutOfRangeError),
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::UnknownTable(name) => write!(f, "no table is named \"{name}\""),
+            TableError::Cycle(names) => {
+                write!(f, "tables refer to each other: {}", names.join(" -> "))
+            }
+            TableError::TooDeep { max_depth } => {
+                write!(f, "more than {max_depth} tables are nested")
+            }
+            TableError::TooManyOutcomes { max_outcomes } => {
+                write!(f, "more than {max_outcomes} entries are resolved")
+            }
+            TableError::OutOfRange(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for TableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TableError::OutOfRange(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<OutOfRangeError> for TableError {
+    fn from(error: OutOfRangeError) -> Self {
+        TableError::OutOfRange(error)
+    }
+}
+
+/// A collection of named tables whose entries can refer to each other.
+///
+/// ```
+/// # use seeded_dice_roller::*;
+/// let mut tables = TableSet::new();
+/// tables.insert(
+///     "rare",
+///     RollToProcess::new(
+///         vec![WeightedResult::new(TableEntry::Value("ring"), 1)],
+///         RollMethod::SimpleRoll,
+///     ),
+/// );
+/// tables.insert(
+///     "chest",
+///     RollToProcess::new(
+///         vec![WeightedResult::new(
+///             TableEntry::Repeat {
+///                 times: 2,
+///                 entry: Box::new(TableEntry::Table("rare".to_string())),
+///             },
+///             1,
+///         )],
+///         RollMethod::SimpleRoll,
+///     ),
+/// );
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let loot = rng.roll_on_table(&tables, "chest").unwrap();
+/// assert_eq!(loot.len(), 2);
+/// assert_eq!(loot[0], TableOutcome { value: "ring", quantity: 1 });
+/// ```
+///
+/// # Files
+/// With the `json`, `toml` and `ron` features, sets can be loaded from hand-authored files by
+/// `TableSet::from_json`, `TableSet::from_toml` and `TableSet::from_ron`, using a compact syntax.
+///
+/// A file maps the name of each table to its **entries**, optionally alongside the **roll**
+/// method written as parsed by [`crate::RollMethod`]'s `FromStr` ("simple" by default) and the
+/// **`out_of_range`** policy written as parsed by [`crate::OutOfRangePolicy`]'s `FromStr`
+/// ("clamp" by default):
+///
+/// ```toml
+/// [rare]
+/// entries = { ring = 1, amulet = 2 }
+///
+/// [hoard]
+/// roll = "3d6"
+/// out_of_range = "wrap"
+/// entries = [
+///     { value = "gold", quantity = "2d4", weight = 3 },
+///     { table = "rare", times = 2 },
+///     "potion",
+/// ]
+/// ```
+///
+/// Entries are either a map of values to their weight, or a list of values (with a weight of 1)
+/// and of detailed entries. A detailed entry gives either a **value** or the name of a **table**
+/// to pick from, and optionally a **quantity** roll, a number of **times** to resolve it and a
+/// **weight** (1 by default). Weights of a map can also be detailed entries without **value**.
+/// Entries keep the order in which they are written.
+///
+/// Values written as map keys are read from strings, whereas values of a list can also be
+/// numbers or booleans: write the entries of a `TableSet<u32>` as a list.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TableSet<T> {
+    /// The tables, by name.
+    pub tables: BTreeMap<String, RollToProcess<TableEntry<T>>>,
+    /// The maximum number of tables that can be nested while resolving one of them.
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// The maximum number of entries resolved while resolving one of them, counting each time a
+    /// repeated entry is resolved and so bounding the number of outcomes given.
+    #[serde(default = "default_max_outcomes")]
+    pub max_outcomes: usize,
+}
+
+/// Returns the [`DEFAULT_MAX_DEPTH`], for serde.
+fn default_max_depth() -> usize {
+    DEFAULT_MAX_DEPTH
+}
+
+/// Returns the [`DEFAULT_MAX_OUTCOMES`], for serde.
+fn default_max_outcomes() -> usize {
+    DEFAULT_MAX_OUTCOMES
+}
+
+impl<T> Default for TableSet<T> {
+    fn default() -> Self {
+        Self {
+            tables: BTreeMap::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_outcomes: DEFAULT_MAX_OUTCOMES,
+        }
+    }
+}
+
+impl<T> TableSet<T> {
+    /// Creates a new empty [`TableSet`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of tables that can be nested while resolving one of them.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum number of entries resolved while resolving one of them.
+    #[must_use]
+    pub fn with_max_outcomes(mut self, max_outcomes: usize) -> Self {
+        self.max_outcomes = max_outcomes;
+        self
+    }
+
+    /// Adds a **table** under a **name**, returning the table previously having that name.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        table: RollToProcess<TableEntry<T>>,
+    ) -> Option<RollToProcess<TableEntry<T>>> {
+        self.tables.insert(name.into(), table)
+    }
+
+    /// Returns the table with that **name**.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&RollToProcess<TableEntry<T>>> {
+        self.tables.get(name)
+    }
+
+    /// Checks that every table referred to exists, that no table can end up referring to itself,
+    /// and that no chain of references is deeper than the maximum depth.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`TableError`] found, looking at the tables in the order of their names.
+    pub fn validate(&self) -> Result<(), TableError> {
+        let mut depths = BTreeMap::new();
+        for name in self.tables.keys() {
+            self.validate_table(name, &mut Vec::new(), &mut depths)?;
+        }
+        Ok(())
+    }
+
+    /// Returns how many tables are nested from the table named **name**, itself included, or the
+    /// first [`TableError`] found below it.
+    #[cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+    pub(crate) fn depth(&self, name: &str) -> Result<usize, TableError> {
+        self.validate_table(name, &mut Vec::new(), &mut BTreeMap::new())
+    }
+
+    /// Checks the table named **name** reached through the tables of the **stack**, storing in
+    /// **depths** how many tables are nested below each table already checked.
+    fn validate_table<'a>(
+        &'a self,
+        name: &'a str,
+        stack: &mut Vec<&'a str>,
+        depths: &mut BTreeMap<&'a str, usize>,
+    ) -> Result<usize, TableError> {
+        if let Some(start) = stack.iter().position(|n| *n == name) {
+            let mut cycle: Vec<String> = stack[start..].iter().map(ToString::to_string).collect();
+            cycle.push(name.to_string());
+            return Err(TableError::Cycle(cycle));
+        }
+        if let Some(depth) = depths.get(name) {
+            return Ok(*depth);
+        }
+        let table = self
+            .get(name)
+            .ok_or_else(|| TableError::UnknownTable(name.to_string()))?;
+
+        stack.push(name);
+        let mut references = Vec::new();
+        for entry in &table.possible_results {
+            entry.result.for_each_reference(&mut |n| references.push(n));
+        }
+        let mut depth = 1;
+        for reference in references {
+            depth = depth.max(1 + self.validate_table(reference, stack, depths)?);
+        }
+        stack.pop();
+
+        if depth > self.max_depth {
+            return Err(TableError::TooDeep {
+                max_depth: self.max_depth,
+            });
+        }
+        depths.insert(name, depth);
+        Ok(depth)
+    }
+}
+
+impl SeededDiceRoller {
+    /// Picks an entry in the table named **name** of a [`TableSet`] and resolves it, following
+    /// references to other tables, rolling quantities and repeating picks as required.
+    ///
+    /// Tables picking nothing, because they're empty or their weights are all 0, give nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TableError`] if a table referred to doesn't exist, if a table ends up referring
+    /// to itself, if more tables are nested than the maximum depth of the set, if more entries
+    /// are resolved than the maximum number of outcomes of the set, or if a table with an
+    /// [`crate::OutOfRangePolicy::Error`] policy rolled out of range.
+    pub fn roll_on_table<T: Clone>(
+        &mut self,
+        tables: &TableSet<T>,
+        name: &str,
+    ) -> Result<Vec<TableOutcome<T>>, TableError> {
+        let mut outcomes = Vec::new();
+        let mut resolved = 0;
+        self.resolve_table(tables, name, &mut Vec::new(), &mut resolved, &mut outcomes)?;
+        Ok(outcomes)
+    }
+
+    /// Picks an entry in the table named **name** reached through the tables of the **stack**,
+    /// and adds what it gives to the **outcomes**, counting the entries **resolved** so far.
+    fn resolve_table<'a, T: Clone>(
+        &mut self,
+        tables: &'a TableSet<T>,
+        name: &'a str,
+        stack: &mut Vec<&'a str>,
+        resolved: &mut usize,
+        outcomes: &mut Vec<TableOutcome<T>>,
+    ) -> Result<(), TableError> {
+        if stack.contains(&name) {
+            let mut cycle: Vec<String> = stack.iter().map(ToString::to_string).collect();
+            cycle.push(name.to_string());
+            return Err(TableError::Cycle(cycle));
+        }
+        if stack.len() >= tables.max_depth {
+            return Err(TableError::TooDeep {
+                max_depth: tables.max_depth,
+            });
+        }
+        let table = tables
+            .get(name)
+            .ok_or_else(|| TableError::UnknownTable(name.to_string()))?;
+
+        trace!("    table: {name}");
+        stack.push(name);
+        if let Some(index) = self.try_get_result_index(table)? {
+            self.resolve_entry(
+                tables,
+                &table.possible_results[index].result,
+                stack,
+                resolved,
+                outcomes,
+            )?;
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    /// Adds what an **entry** gives to the **outcomes**, counting the entries **resolved** so far.
+    fn resolve_entry<'a, T: Clone>(
+        &mut self,
+        tables: &'a TableSet<T>,
+        entry: &'a TableEntry<T>,
+        stack: &mut Vec<&'a str>,
+        resolved: &mut usize,
+        outcomes: &mut Vec<TableOutcome<T>>,
+    ) -> Result<(), TableError> {
+        if *resolved >= tables.max_outcomes {
+            return Err(TableError::TooManyOutcomes {
+                max_outcomes: tables.max_outcomes,
+            });
+        }
+        *resolved += 1;
+        match entry {
+            TableEntry::Value(value) => outcomes.push(TableOutcome {
+                value: value.clone(),
+                quantity: 1,
+            }),
+            TableEntry::Quantity { value, roll } => outcomes.push(TableOutcome {
+                value: value.clone(),
+                quantity: self.roll_prepared(roll),
+            }),
+            TableEntry::Table(name) => {
+                self.resolve_table(tables, name, stack, resolved, outcomes)?;
+            }
+            TableEntry::Repeat { times, entry } => {
+                for _ in 0..*times {
+                    self.resolve_entry(tables, entry, stack, resolved, outcomes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutOfRangePolicy, RollMethod, WeightedResult};
+
+    fn table(
+        entries: Vec<(TableEntry<&'static str>, u32)>,
+    ) -> RollToProcess<TableEntry<&'static str>> {
+        RollToProcess::new(
+            entries
+                .into_iter()
+                .map(|(entry, weight)| WeightedResult::new(entry, weight))
+                .collect(),
+            RollMethod::SimpleRoll,
+        )
+    }
+
+    fn reference(name: &str) -> TableEntry<&'static str> {
+        TableEntry::Table(name.to_string())
+    }
+
+    fn loot_tables() -> TableSet<&'static str> {
+        let mut tables = TableSet::new();
+        tables.insert(
+            "rare",
+            table(vec![
+                (TableEntry::Value("ring"), 1),
+                (TableEntry::Value("amulet"), 1),
+            ]),
+        );
+        tables.insert(
+            "chest",
+            table(vec![(
+                TableEntry::Repeat {
+                    times: 3,
+                    entry: Box::new(reference("rare")),
+                },
+                1,
+            )]),
+        );
+        tables.insert(
+            "hoard",
+            table(vec![
+                (
+                    TableEntry::Quantity {
+                        value: "gold",
+                        roll: PreparedRoll::new(2, 4, 0),
+                    },
+                    1,
+                ),
+                (reference("chest"), 0),
+            ]),
+        );
+        tables
+    }
+
+    #[test]
+    fn nested_tables_are_resolved() {
+        let tables = loot_tables();
+        assert_eq!(tables.validate(), Ok(()));
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let chest = rng.roll_on_table(&tables, "chest").unwrap();
+        assert_eq!(chest.len(), 3);
+        assert!(chest
+            .iter()
+            .all(|o| ["ring", "amulet"].contains(&o.value) && o.quantity == 1));
+
+        for _ in 0..20 {
+            let hoard = rng.roll_on_table(&tables, "hoard").unwrap();
+            assert_eq!(hoard.len(), 1);
+            assert_eq!(hoard[0].value, "gold");
+            assert!((2..=8).contains(&hoard[0].quantity));
+        }
+    }
+
+    #[test]
+    fn nested_tables_are_deterministic() {
+        let tables = loot_tables();
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let values: Vec<&str> = (0..3)
+            .flat_map(|_| rng.roll_on_table(&tables, "chest").unwrap())
+            .map(|o| o.value)
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                "amulet", "amulet", "ring", "ring", "amulet", "ring", "amulet", "amulet", "amulet"
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_tables_whose_weights_are_all_zero_give_nothing() {
+        let mut tables = loot_tables();
+        for method in [RollMethod::SimpleRoll, RollMethod::GaussianRoll(3)] {
+            tables.insert(
+                "empty",
+                RollToProcess {
+                    roll_method: method,
+                    ..table(vec![
+                        (TableEntry::Value("a"), 0),
+                        (TableEntry::Value("b"), 0),
+                    ])
+                },
+            );
+            tables.insert(
+                "cellar",
+                table(vec![
+                    (reference("empty"), 1),
+                    (TableEntry::Value("barrel"), 0),
+                ]),
+            );
+            let mut rng = SeededDiceRoller::new("seed", "step");
+            assert_eq!(rng.roll_on_table(&tables, "empty"), Ok(vec![]));
+            assert_eq!(rng.roll_on_table(&tables, "cellar"), Ok(vec![]));
+        }
+    }
+
+    #[test]
+    fn missing_tables_and_cycles_are_reported() {
+        let mut tables = loot_tables();
+        tables.insert("broken", table(vec![(reference("missing"), 1)]));
+        assert_eq!(
+            tables.validate(),
+            Err(TableError::UnknownTable("missing".to_string()))
+        );
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        assert_eq!(
+            rng.roll_on_table(&tables, "broken"),
+            Err(TableError::UnknownTable("missing".to_string()))
+        );
+
+        let mut tables = loot_tables();
+        tables.insert("a", table(vec![(reference("b"), 1)]));
+        tables.insert(
+            "b",
+            table(vec![(reference("rare"), 1), (reference("a"), 1)]),
+        );
+        let cycle = TableError::Cycle(vec!["a".to_string(), "b".to_string(), "a".to_string()]);
+        assert_eq!(tables.validate(), Err(cycle.clone()));
+        assert_eq!(cycle.to_string(), "tables refer to each other: a -> b -> a");
+        let results: Vec<_> = (0..20).map(|_| rng.roll_on_table(&tables, "a")).collect();
+        assert!(results.contains(&Err(cycle)));
+        assert!(results.iter().any(Result::is_ok));
+    }
+
+    #[test]
+    fn nesting_is_limited_in_depth() {
+        let tables = loot_tables().with_max_depth(2);
+        assert_eq!(tables.validate(), Err(TableError::TooDeep { max_depth: 2 }));
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        assert!(rng.roll_on_table(&tables, "chest").is_ok());
+        let mut tables = tables;
+        tables.insert("deep", table(vec![(reference("chest"), 1)]));
+        assert_eq!(
+            rng.roll_on_table(&tables, "deep"),
+            Err(TableError::TooDeep { max_depth: 2 })
+        );
+    }
+
+    #[test]
+    fn outcomes_are_limited_in_number() {
+        let mut tables = loot_tables();
+        tables.insert(
+            "vault",
+            table(vec![(
+                TableEntry::Repeat {
+                    times: 1000,
+                    entry: Box::new(reference("chest")),
+                },
+                1,
+            )]),
+        );
+        tables.insert(
+            "kingdom",
+            table(vec![(
+                TableEntry::Repeat {
+                    times: u32::MAX,
+                    entry: Box::new(reference("vault")),
+                },
+                1,
+            )]),
+        );
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        assert_eq!(rng.roll_on_table(&tables, "vault").unwrap().len(), 3000);
+        assert_eq!(
+            rng.roll_on_table(&tables, "kingdom"),
+            Err(TableError::TooManyOutcomes {
+                max_outcomes: DEFAULT_MAX_OUTCOMES
+            })
+        );
+
+        // The repeat, then a reference and an entry of "rare" for each of the 3 picks.
+        let tables = tables.with_max_outcomes(7);
+        assert_eq!(rng.roll_on_table(&tables, "chest").unwrap().len(), 3);
+        let tables = tables.with_max_outcomes(6);
+        assert_eq!(
+            rng.roll_on_table(&tables, "chest"),
+            Err(TableError::TooManyOutcomes { max_outcomes: 6 })
+        );
+    }
+
+    #[test]
+    fn out_of_range_errors_are_reported() {
+        let mut tables = TableSet::new();
+        let mut strict = table(vec![
+            (TableEntry::Value("a"), 1),
+            (TableEntry::Value("b"), 1),
+        ])
+        .with_out_of_range(OutOfRangePolicy::Error);
+        strict.roll_method = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10));
+        tables.insert("strict", strict);
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        assert!(matches!(
+            rng.roll_on_table(&tables, "strict"),
+            Err(TableError::OutOfRange(_))
+        ));
+    }
+}
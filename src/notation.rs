@@ -0,0 +1,233 @@
+//! Compact text notation of rolls and roll methods, like "3d6+2" or "gaussian(center=5, spread=2)",
+//! used by hand-authored data files.
+
+use crate::{OutOfRangePolicy, PreparedRoll, RollMethod};
+use seeded_dice_roller_notation::{
+    parse_method, parse_roll, Method, Roll, EXPECTED_METHOD, EXPECTED_ROLL,
+};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The reason why a text couldn't be parsed as a roll, roll method or out of range policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseRollError {
+    /// The text that couldn't be parsed.
+    input: String,
+    /// What was expected instead.
+    expected: &'static str,
+}
+
+impl ParseRollError {
+    fn new(input: &str, expected: &'static str) -> Self {
+        Self {
+            input: input.to_string(),
+            expected,
+        }
+    }
+}
+
+impl Display for ParseRollError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid \"{}\", expected {}", self.input, self.expected)
+    }
+}
+
+impl std::error::Error for ParseRollError {}
+
+/// What an out of range policy should look like.
+const EXPECTED_POLICY: &str = "\"clamp\", \"reroll\", \"wrap\", \"scale\" or \"error\"";
+
+impl PreparedRoll {
+    /// Returns the roll written like "3d6+2", "1d20", "2d4-1", or "4" for a roll without dice
+    /// nor die type. The formula of a roll with a die type, or without dice, can be parsed back
+    /// into the same roll. Dice without a die type are written like "2d0", which isn't a valid
+    /// roll.
+    #[must_use]
+    pub fn formula(&self) -> String {
+        let modifier = match self.modifier {
+            0 => String::new(),
+            m if m > 0 => format!("+{m}"),
+            m => format!("{m}"),
+        };
+        if self.dice == 0 && self.die_type == 0 {
+            format!("{}", self.modifier)
+        } else {
+            format!("{}d{}{modifier}", self.dice, self.die_type)
+        }
+    }
+}
+
+impl From<Roll> for PreparedRoll {
+    fn from(roll: Roll) -> Self {
+        PreparedRoll::new(roll.dice, roll.die_type, roll.modifier)
+    }
+}
+
+impl From<Method> for RollMethod {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::Simple => RollMethod::SimpleRoll,
+            Method::Prepared(roll) => RollMethod::PreparedRoll(roll.into()),
+            Method::GaussianRoll(dice) => RollMethod::GaussianRoll(dice),
+            Method::Triangular { peak } => RollMethod::Triangular { peak },
+            Method::Exponential { decay } => RollMethod::Exponential { decay },
+            Method::Gaussian { center, spread } => RollMethod::Gaussian { center, spread },
+            Method::Linear { start, end } => RollMethod::Linear { start, end },
+        }
+    }
+}
+
+impl FromStr for PreparedRoll {
+    type Err = ParseRollError;
+
+    /// Parses a roll like "3d6+2", "d20", "2D4 - 1", "d%" (one hundred-sided die) or "4" (a
+    /// constant).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_roll(s)
+            .map(PreparedRoll::from)
+            .ok_or_else(|| ParseRollError::new(s, EXPECTED_ROLL))
+    }
+}
+
+impl RollMethod {
+    /// Returns the method written like "simple", "3d6+2", "gaussian(3)", "triangular(peak=2)",
+    /// "exponential(decay=30)", "gaussian(center=5, spread=2)" or "linear(start=1, end=10)". The
+    /// notation can be parsed back into the same method, unless it is a prepared roll whose
+    /// [`PreparedRoll::formula`] can't.
+    #[must_use]
+    pub fn notation(&self) -> String {
+        match self {
+            RollMethod::PreparedRoll(roll) => roll.formula(),
+            RollMethod::GaussianRoll(dice) => format!("gaussian({dice})"),
+            RollMethod::SimpleRoll => "simple".to_string(),
+            RollMethod::Triangular { peak } => format!("triangular(peak={peak})"),
+            RollMethod::Exponential { decay } => format!("exponential(decay={decay})"),
+            RollMethod::Gaussian { center, spread } => {
+                format!("gaussian(center={center}, spread={spread})")
+            }
+            RollMethod::Linear { start, end } => format!("linear(start={start}, end={end})"),
+        }
+    }
+}
+
+impl FromStr for RollMethod {
+    type Err = ParseRollError;
+
+    /// Parses a method written like [`RollMethod::notation`] returns it. The names of the
+    /// arguments can be omitted when they are given in order, like in "linear(1, 10)".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_method(s)
+            .map(RollMethod::from)
+            .ok_or_else(|| ParseRollError::new(s, EXPECTED_METHOD))
+    }
+}
+
+impl FromStr for OutOfRangePolicy {
+    type Err = ParseRollError;
+
+    /// Parses a policy named "clamp", "reroll", "wrap", "scale" or "error", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "clamp" => Ok(OutOfRangePolicy::Clamp),
+            "reroll" => Ok(OutOfRangePolicy::Reroll),
+            "wrap" => Ok(OutOfRangePolicy::Wrap),
+            "scale" => Ok(OutOfRangePolicy::Scale),
+            "error" => Ok(OutOfRangePolicy::Error),
+            _ => Err(ParseRollError::new(s, EXPECTED_POLICY)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_are_parsed() {
+        assert_eq!("3d6+2".parse(), Ok(PreparedRoll::new(3, 6, 2)));
+        assert_eq!("d20".parse(), Ok(PreparedRoll::new(1, 20, 0)));
+        assert_eq!(" 2D4 - 1 ".parse(), Ok(PreparedRoll::new(2, 4, -1)));
+        assert_eq!("d%".parse(), Ok(PreparedRoll::new(1, 100, 0)));
+        assert_eq!("-4".parse(), Ok(PreparedRoll::new(0, 0, -4)));
+        for invalid in [
+            "", "d", "3d", "3d0", "d+2", "3d6+", "3x6", "1d6+2+3", "70000d6", "1 0d6", "1d6+-2",
+        ] {
+            assert!(invalid.parse::<PreparedRoll>().is_err(), "{invalid}");
+        }
+        assert_eq!(
+            "3d".parse::<PreparedRoll>().unwrap_err().to_string(),
+            "invalid \"3d\", expected a roll like \"3d6+2\", \"d20\", \"d%\" or \"4\""
+        );
+    }
+
+    #[test]
+    fn formulas_are_parsed_back() {
+        for roll in [
+            PreparedRoll::new(3, 6, 2),
+            PreparedRoll::new(1, 20, 0),
+            PreparedRoll::new(2, 4, -1),
+            PreparedRoll::new(0, 0, 7),
+            PreparedRoll::new(0, 6, 3),
+            PreparedRoll::new(0, 100, 0),
+        ] {
+            assert_eq!(roll.formula().parse(), Ok(roll));
+        }
+        assert_eq!(PreparedRoll::new(2, 4, -1).formula(), "2d4-1");
+        assert_eq!(PreparedRoll::new(0, 6, 3).formula(), "0d6+3");
+        assert_eq!(PreparedRoll::new(2, 0, 1).formula(), "2d0+1");
+        assert!("2d0+1".parse::<PreparedRoll>().is_err());
+    }
+
+    #[test]
+    fn roll_methods_are_parsed() {
+        assert_eq!("Simple".parse(), Ok(RollMethod::SimpleRoll));
+        assert_eq!(
+            "2d6".parse(),
+            Ok(RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 0)))
+        );
+        assert_eq!("gaussian(3)".parse(), Ok(RollMethod::GaussianRoll(3)));
+        assert_eq!(
+            "gaussian(spread=2, center=5)".parse(),
+            Ok(RollMethod::Gaussian {
+                center: 5,
+                spread: 2
+            })
+        );
+        assert_eq!(
+            "linear(1, 10)".parse(),
+            Ok(RollMethod::Linear { start: 1, end: 10 })
+        );
+        for invalid in [
+            "triangle(2)",
+            "linear(1)",
+            "exponential(decay=300)",
+            "triangular(top=2)",
+            "gaussian()",
+            "linear(start=1, start=2)",
+            "gaussian(center=3, center=4)",
+            "linear(end=1, 2)",
+        ] {
+            assert!(invalid.parse::<RollMethod>().is_err(), "{invalid}");
+        }
+        for method in [
+            RollMethod::SimpleRoll,
+            RollMethod::GaussianRoll(4),
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 100, -5)),
+            RollMethod::Triangular { peak: 2 },
+            RollMethod::Exponential { decay: 30 },
+            RollMethod::Gaussian {
+                center: 5,
+                spread: 2,
+            },
+            RollMethod::Linear { start: 1, end: 10 },
+        ] {
+            assert_eq!(method.notation().parse(), Ok(method));
+        }
+    }
+
+    #[test]
+    fn out_of_range_policies_are_parsed() {
+        assert_eq!("Wrap".parse(), Ok(OutOfRangePolicy::Wrap));
+        assert!("skip".parse::<OutOfRangePolicy>().is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! Bad luck protection, raising the chances of rare entries each time a pick misses them.
+
+use crate::{OutOfRangeError, RollToProcess, SeededDiceRoller};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// How the chances of a protected entry grow with each pick missing it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PityRule {
+    /// Adds **increment** to the weight of the entry after each miss.
+    Linear {
+        /// The weight added after each miss.
+        increment: u32,
+    },
+    /// Adds **increment** to the weight of the entry every **every** misses in a row.
+    Step {
+        /// How many misses in a row it takes to raise the weight.
+        every: u32,
+        /// The weight added every **every** misses.
+        increment: u32,
+    },
+    /// Picks the entry without rolling once it has been missed **after** times in a row.
+    Guarantee {
+        /// How many misses in a row are allowed before the entry is picked.
+        after: u32,
+    },
+}
+
+impl PityRule {
+    /// Returns the weight added to the entry after **misses** misses in a row.
+    fn bonus(self, misses: u32) -> u32 {
+        match self {
+            PityRule::Linear { increment } => increment.saturating_mul(misses),
+            PityRule::Step { every, increment } => increment.saturating_mul(misses / every.max(1)),
+            PityRule::Guarantee { .. } => 0,
+        }
+    }
+
+    /// Returns true if the entry must be picked after **misses** misses in a row.
+    fn guarantees(self, misses: u32) -> bool {
+        matches!(self, PityRule::Guarantee { after } if misses >= after)
+    }
+}
+
+impl Display for PityRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PityRule::Linear { increment } => write!(f, "Linear(+{increment} per miss)"),
+            PityRule::Step { every, increment } => {
+                write!(f, "Step(+{increment} every {every} misses)")
+            }
+            PityRule::Guarantee { after } => write!(f, "Guarantee(after {after} misses)"),
+        }
+    }
+}
+
+/// The bad luck protection of one entry of a [`PityTable`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PityTimer {
+    /// Index of the protected entry.
+    pub index: usize,
+    /// How the chances of the entry grow with each miss.
+    pub rule: PityRule,
+    /// How many picks in a row missed the entry.
+    #[serde(default)]
+    pub misses: u32,
+}
+
+/// A [`RollToProcess`] whose protected entries get better chances each time a pick misses them,
+/// until they get picked.
+///
+/// The misses are part of the table, so serializing it saves the progress of every timer and
+/// picks keep giving the same results once it is loaded back.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let mut loot = PityTable::new(RollToProcess::new(
+///     vec![
+///         WeightedResult::new("junk", 99),
+///         WeightedResult::new("legendary", 1),
+///     ],
+///     RollMethod::SimpleRoll,
+/// ))
+/// .with_timer(1, PityRule::Guarantee { after: 9 });
+///
+/// let picks: Vec<usize> = (0..10)
+///     .map(|_| rng.get_result_index_with_pity(&mut loot).unwrap())
+///     .collect();
+/// assert!(picks.contains(&1));
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PityTable<T> {
+    /// The table whose entries get picked.
+    pub table: RollToProcess<T>,
+    /// The protection of each protected entry.
+    #[serde(default)]
+    pub timers: Vec<PityTimer>,
+}
+
+impl<T> PityTable<T> {
+    /// Creates a new [`PityTable`] without any protected entry.
+    #[must_use]
+    pub fn new(table: RollToProcess<T>) -> Self {
+        Self {
+            table,
+            timers: Vec::new(),
+        }
+    }
+
+    /// Protects the entry at **index** following **rule**, replacing its previous protection.
+    #[must_use]
+    pub fn with_timer(mut self, index: usize, rule: PityRule) -> Self {
+        self.timers.retain(|timer| timer.index != index);
+        self.timers.push(PityTimer {
+            index,
+            rule,
+            misses: 0,
+        });
+        self
+    }
+
+    /// Returns how many picks in a row missed the entry at **index**, or None if it isn't
+    /// protected.
+    #[must_use]
+    pub fn misses(&self, index: usize) -> Option<u32> {
+        self.timers
+            .iter()
+            .find(|timer| timer.index == index)
+            .map(|timer| timer.misses)
+    }
+
+    /// Returns the weight of the entry at **index** given its current protection, or None if the
+    /// table has no such entry.
+    ///
+    /// The weights of the table add up to [`u32::MAX`] at most: once they would go above it,
+    /// the timers coming last in [`PityTable::timers`] add less, or nothing, to their entry.
+    #[must_use]
+    pub fn effective_weight(&self, index: usize) -> Option<u32> {
+        self.boosted_weight(index, self.room())
+    }
+
+    /// Returns the weight that the timers can add before the weights add up to [`u32::MAX`].
+    fn room(&self) -> u32 {
+        let total: u64 = self
+            .table
+            .possible_results
+            .iter()
+            .map(|e| u64::from(e.weight))
+            .sum();
+        u32::try_from(u64::from(u32::MAX).saturating_sub(total)).unwrap_or(0)
+    }
+
+    /// Returns the weight of the entry at **index** given its current protection, the bonuses
+    /// of the timers being added in order as long as they fit in the **room** left.
+    fn boosted_weight(&self, index: usize, mut room: u32) -> Option<u32> {
+        let length = self.table.possible_results.len();
+        let mut weight = self.table.possible_results.get(index)?.weight;
+        for timer in self.timers.iter().filter(|timer| timer.index < length) {
+            let bonus = timer.rule.bonus(timer.misses).min(room);
+            room -= bonus;
+            if timer.index == index {
+                weight += bonus;
+            }
+        }
+        Some(weight)
+    }
+
+    /// Forgets every miss, as if no pick ever happened.
+    pub fn reset(&mut self) {
+        for timer in &mut self.timers {
+            timer.misses = 0;
+        }
+    }
+
+    /// Resets the timer of the **picked** entry and counts a miss for every other one.
+    fn record(&mut self, picked: usize) {
+        for timer in &mut self.timers {
+            if timer.index == picked {
+                timer.misses = 0;
+            } else {
+                timer.misses = timer.misses.saturating_add(1);
+            }
+        }
+    }
+}
+
+impl<T> Display for PityTable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PityTable {{ {} choices, {} timers, method: {} }}",
+            self.table.possible_results.len(),
+            self.timers.len(),
+            self.table.roll_method
+        )
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns the index of the result of a random selection in a **`table`** whose protected
+    /// entries have their weight raised by the misses of their timer, then updates the timers.
+    ///
+    /// An entry protected by a [`PityRule::Guarantee`] that reached its limit is picked without
+    /// rolling, the first one in the list of timers if several did. No timer changes when
+    /// nothing gets picked.
+    pub fn get_result_index_with_pity<T>(&mut self, table: &mut PityTable<T>) -> Option<usize> {
+        self.try_get_result_index_with_pity(table).ok().flatten()
+    }
+
+    /// Returns the index of the result of a random selection in a **`table`** with bad luck
+    /// protection like [`SeededDiceRoller::get_result_index_with_pity`] does, but reports out of
+    /// range rolls when its [`crate::OutOfRangePolicy`] is [`crate::OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`crate::RollMethod::PreparedRoll`] gives a result
+    /// outside of the list while its policy is [`crate::OutOfRangePolicy::Error`]. No timer
+    /// changes then.
+    pub fn try_get_result_index_with_pity<T>(
+        &mut self,
+        table: &mut PityTable<T>,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        let guaranteed = table
+            .timers
+            .iter()
+            .find(|timer| {
+                timer.rule.guarantees(timer.misses)
+                    && timer.index < table.table.possible_results.len()
+            })
+            .map(|timer| timer.index);
+        let result = if let Some(index) = guaranteed {
+            trace!("   chosen: {index}, guaranteed by its pity timer");
+            Some(index)
+        } else {
+            let room = table.room();
+            self.pick_in_context(
+                &|i| table.boosted_weight(i, room).unwrap_or(0),
+                table.table.possible_results.len(),
+                &table.table.roll_method,
+                table.table.out_of_range,
+            )?
+        };
+        if let Some(index) = result {
+            table.record(index);
+        }
+        Ok(result)
+    }
+
+    /// Returns the result of a random selection in a **`table`** with bad luck protection like
+    /// [`SeededDiceRoller::get_result_index_with_pity`] does.
+    pub fn get_result_with_pity<T: Clone>(&mut self, table: &mut PityTable<T>) -> Option<T> {
+        self.get_result_index_with_pity(table)
+            .map(|index| table.table.possible_results[index].result.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutOfRangePolicy, PreparedRoll, RollMethod, WeightedResult};
+
+    /// Returns a table where the entry at index 1 is a rare drop.
+    fn loot(rule: PityRule) -> PityTable<&'static str> {
+        PityTable::new(RollToProcess::new(
+            vec![
+                WeightedResult::new("junk", 98),
+                WeightedResult::new("rare", 1),
+                WeightedResult::new("gem", 1),
+            ],
+            RollMethod::SimpleRoll,
+        ))
+        .with_timer(1, rule)
+    }
+
+    #[test]
+    fn misses_raise_the_effective_weight() {
+        let mut table = loot(PityRule::Linear { increment: 5 });
+        table.timers[0].misses = 3;
+        assert_eq!(table.effective_weight(1), Some(16));
+        assert_eq!(table.effective_weight(0), Some(98));
+
+        table = loot(PityRule::Step {
+            every: 4,
+            increment: 10,
+        });
+        table.timers[0].misses = 7;
+        assert_eq!(table.effective_weight(1), Some(11));
+        table.timers[0].misses = 8;
+        assert_eq!(table.effective_weight(1), Some(21));
+
+        table.reset();
+        assert_eq!(table.misses(1), Some(0));
+        assert_eq!(table.misses(0), None);
+    }
+
+    #[test]
+    fn effective_weights_never_add_up_to_more_than_a_u32() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut table = loot(PityRule::Linear {
+            increment: u32::MAX / 2,
+        })
+        .with_timer(
+            2,
+            PityRule::Linear {
+                increment: u32::MAX,
+            },
+        );
+        table.timers[0].misses = 3;
+        table.timers[1].misses = 1;
+        assert_eq!(table.effective_weight(1), Some(u32::MAX - 99));
+        assert_eq!(table.effective_weight(2), Some(1));
+        assert_eq!(table.effective_weight(3), None);
+        for _ in 0..20 {
+            assert!(rng.get_result_index_with_pity(&mut table).is_some());
+            let total: u64 = (0..3)
+                .filter_map(|i| table.effective_weight(i))
+                .map(u64::from)
+                .sum();
+            assert!(u32::try_from(total).is_ok());
+        }
+    }
+
+    #[test]
+    fn hits_reset_the_timer() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut table = loot(PityRule::Linear { increment: 20 });
+        let mut expected_misses = 0;
+        for _ in 0..200 {
+            let index = rng.get_result_index_with_pity(&mut table).unwrap();
+            expected_misses = if index == 1 { 0 } else { expected_misses + 1 };
+            assert_eq!(table.misses(1), Some(expected_misses));
+        }
+    }
+
+    #[test]
+    fn guarantees_cap_the_streaks_of_misses() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut table = loot(PityRule::Guarantee { after: 5 });
+        let mut streak = 0;
+        let mut longest = 0;
+        for _ in 0..1_000 {
+            if rng.get_result_with_pity(&mut table) == Some("rare") {
+                streak = 0;
+            } else {
+                streak += 1;
+                longest = longest.max(streak);
+            }
+        }
+        assert_eq!(longest, 5);
+    }
+
+    #[test]
+    fn pity_raises_the_long_run_frequency() {
+        let frequency = |rule| {
+            let mut rng = SeededDiceRoller::new("seed", "step");
+            let mut table = loot(rule);
+            (0..5_000)
+                .filter(|_| rng.get_result_index_with_pity(&mut table) == Some(1))
+                .count()
+        };
+        let without = frequency(PityRule::Linear { increment: 0 });
+        let linear = frequency(PityRule::Linear { increment: 2 });
+        assert!(without < 80);
+        assert!(linear > 5 * without);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn saved_timers_keep_picks_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut table = loot(PityRule::Step {
+            every: 2,
+            increment: 30,
+        });
+        for _ in 0..7 {
+            rng.get_result_index_with_pity(&mut table);
+        }
+        let saved = serde_json::to_string(&table).unwrap();
+        let mut loaded: PityTable<&str> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.timers, table.timers);
+
+        let mut other_rng = rng.clone();
+        let picks: Vec<Option<&str>> = (0..10)
+            .map(|_| rng.get_result_with_pity(&mut table))
+            .collect();
+        let loaded_picks: Vec<Option<&str>> = (0..10)
+            .map(|_| other_rng.get_result_with_pity(&mut loaded))
+            .collect();
+        assert_eq!(picks, loaded_picks);
+    }
+
+    #[test]
+    fn out_of_range_rolls_are_reported() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut table = loot(PityRule::Linear { increment: 5 });
+        table.table.roll_method = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 200));
+        table.table.out_of_range = OutOfRangePolicy::Error;
+        assert!(rng.try_get_result_index_with_pity(&mut table).is_err());
+        assert_eq!(rng.get_result_index_with_pity(&mut table), None);
+        assert_eq!(table.misses(1), Some(0));
+    }
+}
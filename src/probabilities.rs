@@ -0,0 +1,495 @@
+//! Exact chances of each entry of a list of possible results to get picked.
+//!
+//! The chances assume that every face of a die is as likely, ignoring the tiny bias of reducing
+//! a 32 bits draw to the faces of a die.
+
+use crate::shapes::ShapeFactors;
+use crate::strategy::picks_lone_entry;
+use crate::{Edges, OutOfRangePolicy, PreparedRoll, RollMethod, RollToProcess, SeededDiceRoller};
+
+impl<T> RollToProcess<T> {
+    /// Returns the chances of each entry to get picked by
+    /// [`SeededDiceRoller::get_result_index`], in the order of the entries.
+    ///
+    /// The chances sum to 1, except when the [`RollMethod::PreparedRoll`] of the table can give
+    /// results outside of the list while its policy is [`OutOfRangePolicy::Error`]: the rest is
+    /// the chance of getting an error. Weights that are all 0 give no chance to any entry,
+    /// unless the table is picked with a [`RollMethod::PreparedRoll`].
+    ///
+    /// Rolls of several dice giving more than a million different results have their chances
+    /// approximated from the distribution of the sum of their dice, like rolls going around a
+    /// list more than a million times with [`OutOfRangePolicy::Wrap`].
+    ///
+    /// ```
+    /// use seeded_dice_roller::*;
+    ///
+    /// let table = RollToProcess::new(
+    ///     vec![WeightedResult::new("a", 3), WeightedResult::new("b", 1)],
+    ///     RollMethod::SimpleRoll,
+    /// );
+    /// assert_eq!(table.probabilities(), vec![0.75, 0.25]);
+    /// ```
+    #[must_use]
+    pub fn probabilities(&self) -> Vec<f64> {
+        let entries = &self.possible_results;
+        entry_probabilities(
+            &|i| entries[i].weight,
+            entries.len(),
+            &self.roll_method,
+            self.out_of_range,
+            Edges::Listed,
+        )
+    }
+}
+
+/// Returns the chances of each of **length** entries whose weights are given by **weight** to
+/// get picked using a **method**, with out of range rolls handled as the **policy** requires and
+/// clamped onto the **edges**.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn entry_probabilities(
+    weight: &dyn Fn(usize) -> u32,
+    length: usize,
+    method: &RollMethod,
+    policy: OutOfRangePolicy,
+    edges: Edges,
+) -> Vec<f64> {
+    let mut chances = vec![0.0; length];
+    let total: u64 = (0..length).map(|i| u64::from(weight(i))).sum();
+    match (length, method) {
+        (0, _) => {}
+        (1, _) if picks_lone_entry(method, policy) => chances[0] = 1.0,
+        (_, RollMethod::PreparedRoll(roll)) => {
+            prepared_probabilities(weight, length, roll, policy, edges, &mut chances);
+        }
+        (_, _) if total == 0 => {}
+        (_, RollMethod::SimpleRoll) => {
+            for (i, chance) in chances.iter_mut().enumerate() {
+                *chance = f64::from(weight(i)) / total as f64;
+            }
+        }
+        (_, RollMethod::GaussianRoll(dice)) => {
+            gaussian_probabilities(weight, length, *dice, &mut chances);
+        }
+        (_, shaped) => {
+            let shaped: Vec<f64> = ShapeFactors::new(*shaped, length)
+                .enumerate()
+                .map(|(i, factor)| (u128::from(factor) * u128::from(weight(i))) as f64)
+                .collect();
+            let sum: f64 = shaped.iter().sum();
+            if sum > 0.0 {
+                for (chance, shaped) in chances.iter_mut().zip(shaped) {
+                    *chance = shaped / sum;
+                }
+            }
+        }
+    }
+    chances
+}
+
+/// The number of results above which the chances of a roll aren't listed one by one.
+pub(crate) const LISTED_RESULTS: i64 = 1 << 20;
+/// The number of dice above which the chances of a roll too wide to be listed are approximated
+/// using the normal distribution.
+const NORMAL_DICE: u16 = 20;
+
+/// The chances of the results of a [`PreparedRoll`], listed one by one when it gives few enough
+/// results to do so, and otherwise derived from the distribution of the sum of its dice.
+pub(crate) enum RollChances {
+    /// The chance of each result, from the **low**est one.
+    Listed {
+        /// The lowest result of the roll.
+        low: i64,
+        /// The chance of each result.
+        chances: Vec<f64>,
+    },
+    /// A roll giving too many results to list their chances.
+    Spread(PreparedRoll),
+}
+
+impl RollChances {
+    /// Returns the chances of the results of **roll**.
+    pub(crate) fn new(roll: &PreparedRoll) -> Self {
+        match roll.probabilities() {
+            Some(chances) => Self::Listed {
+                low: roll.min_result(),
+                chances,
+            },
+            None => Self::Spread(*roll),
+        }
+    }
+
+    /// Returns the chance of getting a result between **from** and **to**, both included.
+    ///
+    /// The chance is exact for listed rolls and rolls of a single die. The chances of rolls of
+    /// several dice too wide to be listed come from the sum of as many continuous uniform
+    /// values, using the normal distribution for more than [`NORMAL_DICE`] dice.
+    pub(crate) fn between(&self, from: i64, to: i64) -> f64 {
+        match self {
+            Self::Listed { low, chances } => {
+                if to < *low {
+                    return 0.0;
+                }
+                let index = |result: i64| {
+                    usize::try_from(result.saturating_sub(*low).max(0)).unwrap_or(usize::MAX)
+                };
+                let (from, to) = (index(from), index(to).min(chances.len().saturating_sub(1)));
+                chances
+                    .get(from..=to)
+                    .map_or(0.0, |chances| chances.iter().sum())
+            }
+            Self::Spread(roll) => {
+                if from > to {
+                    return 0.0;
+                }
+                spread_chance_up_to(roll, to) - spread_chance_up_to(roll, from.saturating_sub(1))
+            }
+        }
+    }
+}
+
+/// Returns the chance of a **roll** too wide to be listed giving **result** or less.
+#[allow(clippy::cast_precision_loss)]
+fn spread_chance_up_to(roll: &PreparedRoll, result: i64) -> f64 {
+    if result < roll.min_result() {
+        return 0.0;
+    }
+    if result >= roll.max_result() {
+        return 1.0;
+    }
+    let dice = f64::from(roll.dice);
+    // A die gives 1 plus the integer part of its number of faces times a uniform value in
+    // [0, 1), which falls half a face short of that product on average.
+    let reached = (result - roll.min_result()) as f64 + 0.5 + dice / 2.0;
+    uniform_sum_chance_below(roll.dice, reached / f64::from(roll.die_type))
+}
+
+/// Returns the chance of the sum of **count** uniform values in [0, 1) being below **sum**,
+/// following the Irwin-Hall distribution or its normal approximation for many values.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn uniform_sum_chance_below(count: u16, sum: f64) -> f64 {
+    let n = f64::from(count);
+    if sum <= 0.0 {
+        return 0.0;
+    }
+    if sum >= n {
+        return 1.0;
+    }
+    if count > NORMAL_DICE {
+        let deviations = (sum - n / 2.0) / (n / 12.0).sqrt();
+        return 0.5 * erfc(-deviations / std::f64::consts::SQRT_2);
+    }
+    // The distribution is symmetric, and its formula more precise below the middle.
+    let (below, mirrored) = if sum > n / 2.0 {
+        (n - sum, true)
+    } else {
+        (sum, false)
+    };
+    let (mut total, mut binomial, mut factorial) = (0.0, 1.0, 1.0);
+    for k in 0..=below.floor() as u16 {
+        let term = binomial * (below - f64::from(k)).powi(i32::from(count));
+        total += if k % 2 == 0 { term } else { -term };
+        binomial *= f64::from(count - k) / f64::from(k + 1);
+    }
+    for k in 2..=count {
+        factorial *= f64::from(k);
+    }
+    let chance = (total / factorial).clamp(0.0, 1.0);
+    if mirrored {
+        1.0 - chance
+    } else {
+        chance
+    }
+}
+
+/// Returns the complementary error function of **x**, with a relative error below 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |sum, coefficient| coefficient + t * sum);
+    let erfc = t * (-z * z + polynomial).exp();
+    if x >= 0.0 {
+        erfc
+    } else {
+        2.0 - erfc
+    }
+}
+
+/// Adds the chances of each entry to get picked by rolling **roll** to **chances**.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn prepared_probabilities(
+    weight: &dyn Fn(usize) -> u32,
+    length: usize,
+    roll: &PreparedRoll,
+    policy: OutOfRangePolicy,
+    edges: Edges,
+    chances: &mut [f64],
+) {
+    let weights = (0..length).map(weight);
+    let first = i64::from(roll.dice);
+    let covered: i64 = (0..length).map(|i| i64::from(weight(i))).sum();
+    let last = first + covered - 1;
+    let results = RollChances::new(roll);
+    let ranges = SeededDiceRoller::roll_ranges(weights, first, 1, edges);
+
+    if covered == 0 || policy == OutOfRangePolicy::Clamp {
+        add_range_chances(ranges, &results, 1.0, chances);
+        return;
+    }
+    // Rerolling until a covered result comes up spreads the other rolls proportionally.
+    let kept = match policy {
+        OutOfRangePolicy::Reroll => results.between(first, last),
+        _ => 1.0,
+    };
+    if kept <= 0.0 {
+        return;
+    }
+    let (low, high) = (roll.min_result(), roll.max_result());
+    let possible = i128::from(high) - i128::from(low) + 1;
+    // The lowest roll scaled onto the covered roll **fitted** or a higher one.
+    let lowest_scaled = |fitted: i64| {
+        let offset = i128::from(fitted - first) * possible;
+        low + ((offset + i128::from(covered) - 1) / i128::from(covered)) as i64
+    };
+    for (chance, (start, end)) in chances.iter_mut().zip(ranges) {
+        let (from, to) = (start.max(first), end.min(last + 1));
+        if from >= to {
+            continue;
+        }
+        *chance += match policy {
+            OutOfRangePolicy::Scale => results.between(lowest_scaled(from), lowest_scaled(to) - 1),
+            OutOfRangePolicy::Wrap => {
+                let periods =
+                    (low - first).div_euclid(covered)..=(high - first).div_euclid(covered);
+                if periods.end() - periods.start() >= LISTED_RESULTS {
+                    // Rolls going around the list that many times give every covered roll the
+                    // same chances.
+                    (to - from) as f64 / covered as f64
+                } else {
+                    periods
+                        .map(|period| {
+                            let shift = period * covered;
+                            results.between(from + shift, to - 1 + shift)
+                        })
+                        .sum()
+                }
+            }
+            _ => results.between(from, to - 1) / kept,
+        };
+    }
+}
+
+/// Adds the chances of each entry to get picked by the **dice** of a
+/// [`RollMethod::GaussianRoll`] to **chances**.
+fn gaussian_probabilities(
+    weight: &dyn Fn(usize) -> u32,
+    length: usize,
+    dice: u16,
+    chances: &mut [f64],
+) {
+    let weights = (0..length).map(weight);
+    let die_type = SeededDiceRoller::calculate_die_type(weights.clone());
+    // An even number of dice lowers the modifier by one half of the time.
+    let modifier = i32::from(dice / 2);
+    let modifiers: &[(i32, f64)] = if dice.is_multiple_of(2) {
+        &[(modifier, 0.5), (modifier - 1, 0.5)]
+    } else {
+        &[(modifier, 1.0)]
+    };
+    for &(modifier, share) in modifiers {
+        let results = RollChances::new(&PreparedRoll::new(dice, die_type, modifier));
+        let first = i64::from(dice);
+        let ranges = SeededDiceRoller::roll_ranges(weights.clone(), first, first, Edges::Listed);
+        add_range_chances(ranges, &results, share, chances);
+    }
+}
+
+/// Adds the chance of getting a roll within each of the **ranges** of rolls covered by the
+/// entries, multiplied by **share**, to the chances of the entries.
+fn add_range_chances(
+    ranges: impl Iterator<Item = (i64, i64)>,
+    results: &RollChances,
+    share: f64,
+    chances: &mut [f64],
+) {
+    for (chance, (start, end)) in chances.iter_mut().zip(ranges) {
+        *chance += results.between(start, end.saturating_sub(1)) * share;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::{CopyableRollToProcess, WeightedResult};
+
+    /// Returns a table of the given **weights** picked using **method** and **policy**.
+    fn table(weights: &[u32], method: RollMethod, policy: OutOfRangePolicy) -> RollToProcess<()> {
+        RollToProcess::new(
+            weights
+                .iter()
+                .map(|w| WeightedResult::new((), *w))
+                .collect(),
+            method,
+        )
+        .with_out_of_range(policy)
+    }
+
+    /// Asserts that **actual** chances are within a rounding error of **expected**.
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{actual:?} != {expected:?}");
+        }
+    }
+
+    /// Returns how often each entry of **`to_process`** gets picked in **picks** picks.
+    #[allow(clippy::cast_precision_loss)]
+    fn frequencies(to_process: &RollToProcess<()>, picks: u32) -> Vec<f64> {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut counts = vec![0; to_process.possible_results.len()];
+        for _ in 0..picks {
+            counts[rng.get_result_index(to_process).unwrap()] += 1;
+        }
+        counts
+            .iter()
+            .map(|c| f64::from(*c) / f64::from(picks))
+            .collect()
+    }
+
+    #[test]
+    fn prepared_roll_chances_follow_the_policy() {
+        let d6 = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 0));
+        let chances = |policy| table(&[2, 1], d6, policy).probabilities();
+        assert_close(&chances(OutOfRangePolicy::Clamp), &[2.0 / 6.0, 4.0 / 6.0]);
+        assert_close(&chances(OutOfRangePolicy::Reroll), &[2.0 / 3.0, 1.0 / 3.0]);
+        assert_close(&chances(OutOfRangePolicy::Wrap), &[4.0 / 6.0, 2.0 / 6.0]);
+        assert_close(&chances(OutOfRangePolicy::Scale), &[4.0 / 6.0, 2.0 / 6.0]);
+        assert_close(&chances(OutOfRangePolicy::Error), &[2.0 / 6.0, 1.0 / 6.0]);
+    }
+
+    #[test]
+    fn chances_match_the_picks() {
+        for method in [
+            RollMethod::SimpleRoll,
+            RollMethod::GaussianRoll(3),
+            RollMethod::GaussianRoll(4),
+            RollMethod::PreparedRoll(PreparedRoll::new(2, 6, -1)),
+            RollMethod::Triangular { peak: 1 },
+        ] {
+            let to_process = table(&[1, 3, 2, 0, 4], method, OutOfRangePolicy::Clamp);
+            let chances = to_process.probabilities();
+            assert!((chances.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            for (frequency, chance) in frequencies(&to_process, 40_000).iter().zip(&chances) {
+                assert!((frequency - chance).abs() < 0.01, "{method}: {chances:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_tables_have_simple_chances() {
+        let simple = RollMethod::SimpleRoll;
+        assert!(table(&[], simple, OutOfRangePolicy::Clamp)
+            .probabilities()
+            .is_empty());
+        assert_eq!(
+            table(&[0], simple, OutOfRangePolicy::Clamp).probabilities(),
+            vec![1.0]
+        );
+        let d6 = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 0));
+        assert_eq!(
+            table(&[1], d6, OutOfRangePolicy::Clamp).probabilities(),
+            vec![1.0]
+        );
+        assert_close(
+            &table(&[1], d6, OutOfRangePolicy::Reroll).probabilities(),
+            &[1.0],
+        );
+        assert_close(
+            &table(&[1], d6, OutOfRangePolicy::Error).probabilities(),
+            &[1.0 / 6.0],
+        );
+        assert_eq!(
+            table(&[0, 0], simple, OutOfRangePolicy::Clamp).probabilities(),
+            vec![0.0, 0.0]
+        );
+        let copyable = CopyableRollToProcess::new(
+            SeededDiceRoller::to_copyable_possible_results(vec!['a', 'b', 'c', 'd']),
+            RollMethod::SimpleRoll,
+        );
+        assert_eq!(copyable.probabilities(), vec![0.25; 4]);
+    }
+
+    #[test]
+    fn wide_rolls_get_their_chances_without_listing_them() {
+        let huge = RollMethod::PreparedRoll(PreparedRoll::new(1, u32::MAX, 0));
+        let chances = |policy| table(&[1, 2, 1], huge, policy).probabilities();
+        let faces = f64::from(u32::MAX);
+        assert_close(
+            &chances(OutOfRangePolicy::Clamp),
+            &[1.0 / faces, 2.0 / faces, 1.0 - 3.0 / faces],
+        );
+        assert_close(&chances(OutOfRangePolicy::Reroll), &[0.25, 0.5, 0.25]);
+        for policy in [OutOfRangePolicy::Wrap, OutOfRangePolicy::Scale] {
+            let chances = chances(policy);
+            assert!((chances[1] - 0.5).abs() < 1e-9, "{policy}: {chances:?}");
+            assert!((chances.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+
+        let gaussian = table(
+            &[u32::MAX / 4; 3],
+            RollMethod::GaussianRoll(3),
+            OutOfRangePolicy::Clamp,
+        );
+        let chances = gaussian.probabilities();
+        assert!(chances[1] > chances[0] && (chances[0] - chances[2]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn spread_chances_are_close_to_listed_ones() {
+        for roll in [
+            PreparedRoll::new(1, 1_000, 0),
+            PreparedRoll::new(3, 1_000, -5),
+            PreparedRoll::new(30, 100, 2),
+        ] {
+            let listed = RollChances::new(&roll);
+            let spread = RollChances::Spread(roll);
+            let (low, high) = (roll.min_result(), roll.max_result());
+            for (from, to) in [
+                (low, high),
+                (low, low + 40),
+                (low + 300, high - 200),
+                (high, high),
+            ] {
+                let (listed, spread) = (listed.between(from, to), spread.between(from, to));
+                assert!(
+                    (listed - spread).abs() < 2e-3,
+                    "{roll}: {listed} != {spread}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn listed_chances_take_bounds_far_outside_the_roll() {
+        let chances = RollChances::new(&PreparedRoll::new(1, 6, 0));
+        assert!((chances.between(3, i64::MAX) - 4.0 / 6.0).abs() < 1e-12);
+        assert!((chances.between(i64::MIN, 2) - 2.0 / 6.0).abs() < 1e-12);
+        assert!((chances.between(i64::MIN, i64::MAX) - 1.0).abs() < 1e-12);
+        assert_eq!(chances.between(i64::MIN, 0), 0.0);
+        assert_eq!(chances.between(7, i64::MAX), 0.0);
+    }
+}
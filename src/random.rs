@@ -0,0 +1,195 @@
+//! Types whose values can be picked or generated at random, usually by deriving their
+//! implementation with the `derive` feature.
+
+use crate::{RollMethod, RollToProcess, SeededDiceRoller};
+
+/// A type whose values can be picked at random in a weighted table.
+///
+/// With the `derive` feature, `#[derive(RandomPick)]` implements it for enums whose variants
+/// have no fields. Each variant has a weight of 1 unless given a `#[weight(n)]`, and the table is
+/// picked with [`RollMethod::SimpleRoll`] unless the enum is given a `#[roll_method(..)]`,
+/// either written in the notation of [`RollMethod::notation`] or as an expression:
+///
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// use seeded_dice_roller::*;
+///
+/// #[derive(RandomPick, Debug, PartialEq)]
+/// #[roll_method("gaussian(3)")]
+/// enum Weather {
+///     #[weight(5)]
+///     Sunny,
+///     Cloudy,
+///     #[weight(0)]
+///     Snowy,
+/// }
+///
+/// let table = Weather::table();
+/// assert_eq!(table.roll_method, RollMethod::GaussianRoll(3));
+/// assert_eq!(table.possible_results[0].weight, 5);
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// assert_ne!(Weather::pick(&mut rng), Some(Weather::Snowy));
+/// ```
+///
+/// Implementing it by hand only requires building the table:
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Coin {
+///     Heads,
+///     Tails,
+/// }
+///
+/// impl RandomPick for Coin {
+///     fn table() -> RollToProcess<Self> {
+///         RollToProcess::new(
+///             vec![WeightedResult::new(Coin::Heads, 1), WeightedResult::new(Coin::Tails, 1)],
+///             RollMethod::SimpleRoll,
+///         )
+///     }
+/// }
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// assert!(Coin::pick(&mut rng).is_some());
+/// assert_eq!(Coin::pick_with(&mut rng, RollMethod::Linear { start: 0, end: 1 }), Some(Coin::Tails));
+/// ```
+pub trait RandomPick: Sized {
+    /// Returns a table holding every value that can be picked, with its weight.
+    fn table() -> RollToProcess<Self>;
+
+    /// Returns a value picked in the [`RandomPick::table`] of the type.
+    fn pick(roller: &mut SeededDiceRoller) -> Option<Self> {
+        take_pick(roller, Self::table())
+    }
+
+    /// Returns a value picked in the [`RandomPick::table`] of the type using another **method**.
+    fn pick_with(roller: &mut SeededDiceRoller, method: RollMethod) -> Option<Self> {
+        let mut table = Self::table();
+        table.roll_method = method;
+        take_pick(roller, table)
+    }
+}
+
+/// A type whose values can be generated from a [`SeededDiceRoller`].
+///
+/// It is implemented for booleans, integers and floating point types, drawn with the matching
+/// `gen_` method of the roller. With the `derive` feature, `#[derive(SeededRandom)]` implements it
+/// for structs, each field being generated by a child of the roller named after the field (see
+/// [`SeededDiceRoller::child`]), so that adding, removing or reordering fields doesn't change the
+/// values of the others. Fields are generated with their own [`SeededRandom`] implementation
+/// unless given one of these attributes:
+/// - `#[roll("3d6")]` rolls dice written like the [`crate::PreparedRoll::formula`] of a roll,
+///   checked when the macro expands, converting the roll to the integer type of the field;
+/// - `#[range(1..=10)]` draws a value in a range with [`SeededDiceRoller::gen_range`];
+/// - `#[pick]` picks a value of a type implementing [`RandomPick`];
+/// - `#[pick(table = "names")]` takes a value out of the table returned by the function
+///   `names()` with [`SeededDiceRoller::take_result`], so values don't need to be [`Clone`]. The
+///   function is called for each generated value and its table must not be empty.
+///
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// use seeded_dice_roller::*;
+///
+/// fn names() -> RollToProcess<String> {
+///     RollToProcess::new(
+///         SeededDiceRoller::to_possible_results(vec!["Ada".to_string(), "Brom".to_string()]),
+///         RollMethod::SimpleRoll,
+///     )
+/// }
+///
+/// #[derive(SeededRandom)]
+/// struct Npc {
+///     #[pick(table = "names")]
+///     name: String,
+///     #[roll("3d6")]
+///     strength: u8,
+///     #[range(1..=10)]
+///     level: u32,
+///     hostile: bool,
+/// }
+///
+/// let mut rng = SeededDiceRoller::new("seed", "npcs");
+/// let npc = Npc::generate(&mut rng);
+/// assert!(["Ada", "Brom"].contains(&npc.name.as_str()));
+/// assert!((3..=18).contains(&npc.strength));
+/// assert!((1..=10).contains(&npc.level));
+/// ```
+pub trait SeededRandom: Sized {
+    /// Returns a new value generated using the **roller**.
+    fn generate(roller: &mut SeededDiceRoller) -> Self;
+}
+
+/// Implements [`SeededRandom`] for each type, drawn with the given method of the roller.
+macro_rules! impl_seeded_random {
+    ($($ty:ty => $method:ident),* $(,)?) => {
+        $(
+            impl SeededRandom for $ty {
+                fn generate(roller: &mut SeededDiceRoller) -> Self {
+                    roller.$method()
+                }
+            }
+        )*
+    };
+}
+
+impl_seeded_random!(
+    bool => gen_bool,
+    u8 => gen_u8,
+    u16 => gen_u16,
+    u32 => gen_u32,
+    u64 => gen_u64,
+    u128 => gen_u128,
+    usize => gen_usize,
+    i8 => gen_i8,
+    i16 => gen_i16,
+    i32 => gen_i32,
+    i64 => gen_i64,
+    i128 => gen_i128,
+    isize => gen_isize,
+    f32 => gen_f32,
+    f64 => gen_f64,
+);
+
+/// Returns the result picked in **table**, taken out of it.
+fn take_pick<T>(roller: &mut SeededDiceRoller, mut table: RollToProcess<T>) -> Option<T> {
+    roller.take_result(&mut table)
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::PreparedRoll;
+
+    #[derive(crate::RandomPick, Debug, PartialEq)]
+    #[seeded_dice_roller(crate = crate)]
+    #[roll_method("2d6")]
+    enum Encounter {
+        Bandits,
+        #[weight(9)]
+        Nothing,
+        Wolves,
+    }
+
+    #[derive(crate::SeededRandom)]
+    #[seeded_dice_roller(crate = crate)]
+    struct Npc {
+        #[roll("3d6")]
+        strength: u8,
+        #[pick]
+        encounter: Encounter,
+    }
+
+    #[test]
+    fn derives_can_name_the_crate() {
+        assert_eq!(
+            Encounter::table().roll_method,
+            RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 0))
+        );
+        let npc = Npc::generate(&mut SeededDiceRoller::new("seed", "npcs"));
+        assert!((3..=18).contains(&npc.strength));
+        assert_eq!(npc.encounter, Encounter::Nothing);
+    }
+}
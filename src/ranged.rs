@@ -0,0 +1,452 @@
+//! Tables whose entries declare the exact roll results they cover, as printed tabletop tables do.
+
+use crate::{PreparedRoll, RollMethod, RollToProcess, SeededDiceRoller, WeightedResult};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// A result covering every roll from **min** to **max**, both included.
+///
+/// Printed tables often write the highest result of a d100 as "00": transcribe it as 100.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangedEntry<T> {
+    /// The result picked when the roll is within the range.
+    pub result: T,
+    /// The lowest roll giving this result.
+    pub min: i64,
+    /// The highest roll giving this result.
+    pub max: i64,
+}
+
+impl<T> RangedEntry<T> {
+    /// Creates a new [`RangedEntry`] covering the rolls from **min** to **max**, both included.
+    pub fn new(result: T, min: i64, max: i64) -> Self {
+        Self { result, min, max }
+    }
+
+    /// Returns true if a **roll** gives this entry.
+    #[must_use]
+    pub fn contains(&self, roll: i64) -> bool {
+        self.min <= roll && roll <= self.max
+    }
+
+    /// Returns how many of the rolls from **low** to **high** give this entry.
+    fn covered(&self, low: i64, high: i64) -> u64 {
+        let (min, max) = (self.min.max(low), self.max.min(high));
+        if min > max {
+            0
+        } else {
+            max.abs_diff(min) + 1
+        }
+    }
+}
+
+impl<T> Display for RangedEntry<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.min == self.max {
+            write!(f, "{{ result, roll: {} }}", self.min)
+        } else {
+            write!(f, "{{ result, rolls: {}-{} }}", self.min, self.max)
+        }
+    }
+}
+
+/// Reasons why a list of [`RangedEntry`] can't make a [`RangedTable`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangedTableError {
+    /// The table has no entry.
+    Empty,
+    /// The roll has dice but a **`die_type`** of 0.
+    NoDieType,
+    /// The entry at **index** has a **min** higher than its **max**.
+    InvertedRange {
+        /// Index of the faulty entry.
+        index: usize,
+    },
+    /// The entries at indexes **first** and **second** both cover the **roll**.
+    Overlap {
+        /// Index of the first entry covering the roll.
+        first: usize,
+        /// Index of the second entry covering the roll.
+        second: usize,
+        /// The lowest roll covered by both entries.
+        roll: i64,
+    },
+    /// The entry at **index** covers more than [`u32::MAX`] of the rolls, so it can't be weighed.
+    TooWide {
+        /// Index of the faulty entry.
+        index: usize,
+    },
+    /// No entry covers the rolls from **min** to **max**, both included, while the roll can give
+    /// them.
+    Gap {
+        /// The lowest roll without an entry.
+        min: i64,
+        /// The highest roll without an entry.
+        max: i64,
+    },
+}
+
+impl Display for RangedTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangedTableError::Empty => write!(f, "the table has no entry"),
+            RangedTableError::NoDieType => write!(f, "the roll has dice but no die type"),
+            RangedTableError::InvertedRange { index } => {
+                write!(f, "entry {index} has a minimum higher than its maximum")
+            }
+            RangedTableError::Overlap {
+                first,
+                second,
+                roll,
+            } => write!(
+                f,
+                "entries {first} and {second} both cover a roll of {roll}"
+            ),
+            RangedTableError::TooWide { index } => {
+                write!(f, "entry {index} covers more than {} rolls", u32::MAX)
+            }
+            RangedTableError::Gap { min, max } if min == max => {
+                write!(f, "no entry covers a roll of {min}")
+            }
+            RangedTableError::Gap { min, max } => {
+                write!(f, "no entry covers the rolls from {min} to {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RangedTableError {}
+
+/// A table picking its result by rolling a [`PreparedRoll`] and looking for the entry whose range
+/// covers the roll, allowing to transcribe published tables verbatim:
+///
+/// ```
+/// # use seeded_dice_roller::*;
+/// // d100: 01-15 goblin, 16-40 orc, 41-00 troll
+/// let table = RangedTable::new(
+///     PreparedRoll::new(1, 100, 0),
+///     vec![
+///         RangedEntry::new("goblin", 1, 15),
+///         RangedEntry::new("orc", 16, 40),
+///         RangedEntry::new("troll", 41, 100),
+///     ],
+/// )
+/// .unwrap();
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// assert_eq!(*rng.get_ranged_result(&table), "troll");
+/// ```
+///
+/// Every result the roll can give must be covered by exactly one entry. Entries may cover results
+/// the roll can't give, and don't need to be sorted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "RangedTableData<T>",
+    bound(deserialize = "T: Deserialize<'de>")
+)]
+pub struct RangedTable<T> {
+    /// The roll made to pick a result.
+    roll: PreparedRoll,
+    /// The possible results and the rolls giving them.
+    entries: Vec<RangedEntry<T>>,
+}
+
+/// The unvalidated content of a [`RangedTable`], as found in serialized data.
+#[derive(Deserialize)]
+struct RangedTableData<T> {
+    roll: PreparedRoll,
+    entries: Vec<RangedEntry<T>>,
+}
+
+impl<T> TryFrom<RangedTableData<T>> for RangedTable<T> {
+    type Error = RangedTableError;
+
+    fn try_from(data: RangedTableData<T>) -> Result<Self, Self::Error> {
+        Self::new(data.roll, data.entries)
+    }
+}
+
+impl<T> RangedTable<T> {
+    /// Creates a new [`RangedTable`] rolling **roll** to pick one of the **entries**.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RangedTableError`] if there is no entry, if the roll has dice without a die
+    /// type, if an entry's range is inverted or covers more than [`u32::MAX`] of the rolls, if two
+    /// entries cover the same roll, or if a result the roll can give isn't covered by any entry.
+    pub fn new(roll: PreparedRoll, entries: Vec<RangedEntry<T>>) -> Result<Self, RangedTableError> {
+        if entries.is_empty() {
+            return Err(RangedTableError::Empty);
+        }
+        if roll.dice > 0 && roll.die_type == 0 {
+            return Err(RangedTableError::NoDieType);
+        }
+        if let Some(index) = entries.iter().position(|e| e.min > e.max) {
+            return Err(RangedTableError::InvertedRange { index });
+        }
+        let (low, high) = (roll.min_result(), roll.max_result());
+        if let Some(index) = entries
+            .iter()
+            .position(|e| u32::try_from(e.covered(low, high)).is_err())
+        {
+            return Err(RangedTableError::TooWide { index });
+        }
+
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        order.sort_by_key(|&index| (entries[index].min, index));
+
+        let mut next = roll.min_result();
+        for pair in order.windows(2) {
+            let (first, second) = (&entries[pair[0]], &entries[pair[1]]);
+            if second.min <= first.max {
+                return Err(RangedTableError::Overlap {
+                    first: pair[0].min(pair[1]),
+                    second: pair[0].max(pair[1]),
+                    roll: second.min,
+                });
+            }
+        }
+        for &index in &order {
+            let entry = &entries[index];
+            if entry.min > next && next <= roll.max_result() {
+                return Err(RangedTableError::Gap {
+                    min: next,
+                    max: (entry.min - 1).min(roll.max_result()),
+                });
+            }
+            next = next.max(entry.max.saturating_add(1));
+        }
+        if next <= roll.max_result() {
+            return Err(RangedTableError::Gap {
+                min: next,
+                max: roll.max_result(),
+            });
+        }
+
+        Ok(Self { roll, entries })
+    }
+
+    /// Returns the roll made to pick a result.
+    #[must_use]
+    pub fn roll(&self) -> &PreparedRoll {
+        &self.roll
+    }
+
+    /// Returns the possible results and the rolls giving them.
+    #[must_use]
+    pub fn entries(&self) -> &[RangedEntry<T>] {
+        &self.entries
+    }
+
+    /// Returns the index of the entry given by a **roll**, if any.
+    #[must_use]
+    pub fn index_for(&self, roll: i64) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.contains(roll))
+    }
+
+    /// Returns the entries, consuming the table.
+    #[must_use]
+    pub fn into_entries(self) -> Vec<RangedEntry<T>> {
+        self.entries
+    }
+}
+
+impl<T> From<RangedTable<T>> for RollToProcess<T> {
+    /// Converts a ranged table into a table using a [`RollMethod::PreparedRoll`] giving the same
+    /// results. Entries are sorted by range, weigh as many rolls as they cover, and the modifier
+    /// of the roll is removed so the first entry starts at the number of dice rolled.
+    fn from(table: RangedTable<T>) -> Self {
+        let (low, high) = (table.roll.min_result(), table.roll.max_result());
+        let mut entries = table.entries;
+        entries.sort_by_key(|entry| entry.min);
+        let possible_results = entries
+            .into_iter()
+            .map(|entry| {
+                let covered = u32::try_from(entry.covered(low, high))
+                    .expect("A ranged table's entries should cover at most u32::MAX rolls.");
+                WeightedResult::new(entry.result, covered)
+            })
+            .collect();
+        RollToProcess::new(
+            possible_results,
+            RollMethod::PreparedRoll(PreparedRoll::new(table.roll.dice, table.roll.die_type, 0)),
+        )
+    }
+}
+
+impl<T> Display for RangedTable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "RangedTable {{ {} entries, roll: {} }}",
+            self.entries.len(),
+            self.roll
+        )
+    }
+}
+
+impl SeededDiceRoller {
+    /// Rolls the [`PreparedRoll`] of a ranged **table** and returns the index of the entry
+    /// covering the roll.
+    pub fn get_ranged_result_index<T>(&mut self, table: &RangedTable<T>) -> usize {
+        let roll = self.roll_prepared(&table.roll);
+        let Some(index) = table.index_for(roll) else {
+            unreachable!("A ranged table should cover every result of its roll.");
+        };
+        trace!("   chosen: {index}");
+        index
+    }
+
+    /// Rolls the [`PreparedRoll`] of a ranged **table** and returns the result of the entry
+    /// covering the roll.
+    pub fn get_ranged_result<'a, T>(&mut self, table: &'a RangedTable<T>) -> &'a T {
+        let index = self.get_ranged_result_index(table);
+        &table.entries[index].result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d100() -> PreparedRoll {
+        PreparedRoll::new(1, 100, 0)
+    }
+
+    fn monsters() -> RangedTable<&'static str> {
+        RangedTable::new(
+            d100(),
+            vec![
+                RangedEntry::new("goblin", 1, 15),
+                RangedEntry::new("orc", 16, 40),
+                RangedEntry::new("troll", 41, 100),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ranged_results_follow_the_declared_ranges() {
+        let table = monsters();
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut other_rng = SeededDiceRoller::new("seed", "step");
+        for _ in 0..200 {
+            let roll = other_rng.roll_prepared(&d100());
+            let expected = match roll {
+                1..=15 => "goblin",
+                16..=40 => "orc",
+                _ => "troll",
+            };
+            assert_eq!(*rng.get_ranged_result(&table), expected);
+        }
+    }
+
+    #[test]
+    fn ranged_tables_accept_unsorted_and_wider_entries() {
+        let table = RangedTable::new(
+            PreparedRoll::new(2, 6, 0),
+            vec![
+                RangedEntry::new("high", 8, 100),
+                RangedEntry::new("low", -5, 7),
+            ],
+        )
+        .unwrap();
+        assert_eq!(table.index_for(2), Some(1));
+        assert_eq!(table.index_for(12), Some(0));
+        assert_eq!(table.index_for(101), None);
+    }
+
+    #[test]
+    fn ranged_tables_convert_to_prepared_roll_tables() {
+        let table = RangedTable::new(
+            PreparedRoll::new(2, 6, 1),
+            vec![
+                RangedEntry::new("high", 9, 100),
+                RangedEntry::new("never", -10, -5),
+                RangedEntry::new("low", -4, 8),
+            ],
+        )
+        .unwrap();
+        let to_process: RollToProcess<&str> = table.clone().into();
+        let weights: Vec<(&str, u32)> = to_process
+            .possible_results
+            .iter()
+            .map(|r| (r.result, r.weight))
+            .collect();
+        assert_eq!(weights, vec![("never", 0), ("low", 6), ("high", 5)]);
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut other_rng = SeededDiceRoller::new("seed", "step");
+        for _ in 0..100 {
+            let index = rng.get_result_index(&to_process).unwrap();
+            assert_eq!(
+                to_process.possible_results[index].result,
+                *other_rng.get_ranged_result(&table)
+            );
+        }
+    }
+
+    #[test]
+    fn ranged_tables_reject_invalid_entries() {
+        assert_eq!(
+            RangedTable::<&str>::new(d100(), vec![]),
+            Err(RangedTableError::Empty)
+        );
+        assert_eq!(
+            RangedTable::new(d100(), vec![RangedEntry::new("a", 100, 1)]),
+            Err(RangedTableError::InvertedRange { index: 0 })
+        );
+        assert_eq!(
+            RangedTable::new(
+                d100(),
+                vec![
+                    RangedEntry::new("a", 1, 50),
+                    RangedEntry::new("b", 60, 100),
+                    RangedEntry::new("c", 45, 59),
+                ]
+            ),
+            Err(RangedTableError::Overlap {
+                first: 0,
+                second: 2,
+                roll: 45
+            })
+        );
+        assert_eq!(
+            RangedTable::new(
+                d100(),
+                vec![RangedEntry::new("a", 1, 40), RangedEntry::new("b", 43, 100)]
+            ),
+            Err(RangedTableError::Gap { min: 41, max: 42 })
+        );
+        assert_eq!(
+            RangedTable::new(d100(), vec![RangedEntry::new("a", 2, 100)]),
+            Err(RangedTableError::Gap { min: 1, max: 1 })
+        );
+        assert_eq!(
+            RangedTable::new(d100(), vec![RangedEntry::new("a", 1, 99)]),
+            Err(RangedTableError::Gap { min: 100, max: 100 })
+        );
+        assert_eq!(
+            RangedTable::new(
+                PreparedRoll::new(2, 0, 0),
+                vec![RangedEntry::new("a", 0, 2)]
+            ),
+            Err(RangedTableError::NoDieType)
+        );
+        assert_eq!(
+            RangedTable::new(
+                PreparedRoll::new(2, u32::MAX, 0),
+                vec![
+                    RangedEntry::new("a", i64::MIN, 2),
+                    RangedEntry::new("b", 3, i64::MAX)
+                ]
+            ),
+            Err(RangedTableError::TooWide { index: 1 })
+        );
+        assert_eq!(
+            RangedTableError::Gap { min: 41, max: 42 }.to_string(),
+            "no entry covers the rolls from 41 to 42"
+        );
+    }
+}
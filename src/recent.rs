@@ -0,0 +1,418 @@
+//! Successive picks avoiding the results picked last, so that the same line of dialogue or the
+//! same music doesn't play twice in a row.
+
+use crate::{OutOfRangeError, RollMethod, RollToProcess, SeededDiceRoller, WeightedResult};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+/// How much the results found in the history of a [`RecentPicks`] are avoided.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RepeatRule {
+    /// Results found in the history can't be picked.
+    #[default]
+    Exclude,
+    /// Results found in the history keep **kept** percent of their weight for each time they
+    /// appear in it.
+    ///
+    /// Tables picked with a [`RollMethod::PreparedRoll`] tie their rolls to their weights, which
+    /// are therefore rounded down once decayed: a weight of 1 decays straight to 0, just like
+    /// with [`RepeatRule::Exclude`]. Give their entries larger weights, and dice to match, for a
+    /// gradual decay.
+    Decay {
+        /// The percentage of its weight a result keeps for each time it appears in the history.
+        kept: u8,
+    },
+}
+
+impl RepeatRule {
+    /// Returns the **weight** of a result found **times** times in the history, multiplied by
+    /// **scale** so that small weights keep their proportions once decayed.
+    fn weight(self, weight: u32, times: usize, scale: u64) -> u32 {
+        match self {
+            RepeatRule::Exclude => {
+                if times > 0 {
+                    0
+                } else {
+                    weight
+                }
+            }
+            RepeatRule::Decay { kept } => {
+                let kept = u64::from(kept.min(100));
+                let start = u64::from(weight) * scale;
+                let decayed = (0..times)
+                    .try_fold(start, |weight, _| {
+                        Some(weight * kept / 100).filter(|w| *w > 0)
+                    })
+                    .unwrap_or(0);
+                u32::try_from(decayed).unwrap_or(u32::MAX)
+            }
+        }
+    }
+}
+
+impl Display for RepeatRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepeatRule::Exclude => write!(f, "Exclude"),
+            RepeatRule::Decay { kept } => write!(f, "Decay(keeps {kept}%)"),
+        }
+    }
+}
+
+/// The indexes of the last results picked in a table, avoided by the next picks following a
+/// [`RepeatRule`].
+///
+/// When avoiding the whole history would leave nothing to pick, for instance in a table shorter
+/// than the window, the oldest picks of the history are forgiven until a result can be picked.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let lines = RollToProcess::new(
+///     SeededDiceRoller::to_possible_results(vec!["Hello!", "Hi.", "Greetings.", "Hey."]),
+///     RollMethod::SimpleRoll,
+/// );
+/// let mut recent = RecentPicks::new(2, RepeatRule::Exclude);
+///
+/// let picks: Vec<usize> = (0..20)
+///     .map(|_| rng.get_result_index_without_repeat(&lines, &mut recent).unwrap())
+///     .collect();
+/// assert!(picks.windows(3).all(|w| w[0] != w[1] && w[1] != w[2] && w[0] != w[2]));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RecentPicks {
+    /// How many of the last picks are avoided.
+    window: usize,
+    /// How much the results of the last picks are avoided.
+    rule: RepeatRule,
+    /// The indexes of the last picks, the most recent one last.
+    #[serde(default)]
+    history: VecDeque<usize>,
+}
+
+impl RecentPicks {
+    /// Creates a new [`RecentPicks`] avoiding the results of the last **window** picks following
+    /// a **rule**.
+    #[must_use]
+    pub fn new(window: usize, rule: RepeatRule) -> Self {
+        Self {
+            window,
+            rule,
+            history: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Returns how many of the last picks are avoided.
+    #[must_use]
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Returns how much the results of the last picks are avoided.
+    #[must_use]
+    pub fn rule(&self) -> RepeatRule {
+        self.rule
+    }
+
+    /// Returns the indexes of the last picks, the most recent one last.
+    pub fn history(&self) -> impl Iterator<Item = usize> + '_ {
+        self.history.iter().copied()
+    }
+
+    /// Forgets every pick, so that no result is avoided anymore.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Adds the **picked** index to the history, forgetting the oldest pick if the window is
+    /// full.
+    pub fn record(&mut self, picked: usize) {
+        if self.window == 0 {
+            return;
+        }
+        if self.history.len() >= self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(picked);
+    }
+
+    /// Returns the weight of the entry at **index** when avoiding the **last** picks, multiplied
+    /// by **scale**.
+    fn weight(&self, index: usize, weight: u32, last: usize, scale: u64) -> u32 {
+        let times = self
+            .history
+            .iter()
+            .rev()
+            .take(last)
+            .filter(|&&picked| picked == index)
+            .count();
+        self.rule.weight(weight, times, scale)
+    }
+
+    /// Returns how much the weights of **entries** are multiplied by before decaying them: up to
+    /// 100 while they add up to [`u32::MAX`] at most, but not at all with a
+    /// [`RollMethod::PreparedRoll`], whose rolls are tied to the weights.
+    fn scale<T>(&self, entries: &[WeightedResult<T>], method: &RollMethod) -> u64 {
+        if matches!(method, RollMethod::PreparedRoll(_)) || self.rule == RepeatRule::Exclude {
+            return 1;
+        }
+        let total: u64 = entries.iter().map(|entry| u64::from(entry.weight)).sum();
+        (u64::from(u32::MAX) / total.max(1)).clamp(1, 100)
+    }
+}
+
+impl Display for RecentPicks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "RecentPicks {{ {} of {} picks, rule: {} }}",
+            self.history.len(),
+            self.window,
+            self.rule
+        )
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns the index of the result of a random selection in a **`to_process`** list which
+    /// avoids the results found in the history of **recent**, then records the pick in it.
+    pub fn get_result_index_without_repeat<T>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        recent: &mut RecentPicks,
+    ) -> Option<usize> {
+        self.try_get_result_index_without_repeat(to_process, recent)
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the index of the result of a random selection in a **`to_process`** list avoiding
+    /// the results found in the history of **recent** like
+    /// [`SeededDiceRoller::get_result_index_without_repeat`] does, but reports out of range rolls
+    /// when its [`crate::OutOfRangePolicy`] is [`crate::OutOfRangePolicy::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`RollMethod::PreparedRoll`] gives a result outside of
+    /// the list while its policy is [`crate::OutOfRangePolicy::Error`]. Nothing is recorded then.
+    pub fn try_get_result_index_without_repeat<T>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        recent: &mut RecentPicks,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        let entries = &to_process.possible_results;
+        let scale = recent.scale(entries, &to_process.roll_method);
+        let Some(last) = (0..=recent.history.len()).rev().find(|&last| {
+            (0..entries.len()).any(|i| recent.weight(i, entries[i].weight, last, scale) > 0)
+        }) else {
+            return Ok(None);
+        };
+        if last < recent.history.len() {
+            trace!(" pre-roll: only avoiding the last {last} picks");
+        }
+        let result = self.pick_in_context(
+            &|i| recent.weight(i, entries[i].weight, last, scale),
+            entries.len(),
+            &to_process.roll_method,
+            to_process.out_of_range,
+        )?;
+        if let Some(index) = result {
+            recent.record(index);
+        }
+        Ok(result)
+    }
+
+    /// Returns the result of a random selection in a **`to_process`** list avoiding the results
+    /// found in the history of **recent** like
+    /// [`SeededDiceRoller::get_result_index_without_repeat`] does.
+    pub fn get_result_without_repeat<T: Clone>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        recent: &mut RecentPicks,
+    ) -> Option<T> {
+        self.get_result_index_without_repeat(to_process, recent)
+            .map(|index| to_process.possible_results[index].result.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutOfRangePolicy, PreparedRoll, WeightedResult};
+
+    /// Returns a table of **length** results of weight 1.
+    fn table(length: usize, method: RollMethod) -> RollToProcess<usize> {
+        RollToProcess::new(
+            SeededDiceRoller::to_possible_results((0..length).collect()),
+            method,
+        )
+    }
+
+    #[test]
+    fn excluded_results_never_repeat_within_the_window() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        for method in [
+            RollMethod::SimpleRoll,
+            RollMethod::GaussianRoll(2),
+            RollMethod::PreparedRoll(PreparedRoll::new(1, 8, 0)),
+        ] {
+            let table = table(6, method);
+            let mut recent = RecentPicks::new(3, RepeatRule::Exclude);
+            let picks: Vec<usize> = (0..200)
+                .map(|_| {
+                    rng.get_result_index_without_repeat(&table, &mut recent)
+                        .unwrap()
+                })
+                .collect();
+            for window in picks.windows(4) {
+                assert!(!window[..3].contains(&window[3]), "{method}: {window:?}");
+            }
+            assert_eq!(recent.history().count(), 3);
+        }
+    }
+
+    #[test]
+    fn short_tables_forgive_the_oldest_picks() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let pair = table(2, RollMethod::SimpleRoll);
+        let mut recent = RecentPicks::new(5, RepeatRule::Exclude);
+        let picks: Vec<usize> = (0..6)
+            .map(|_| {
+                rng.get_result_index_without_repeat(&pair, &mut recent)
+                    .unwrap()
+            })
+            .collect();
+        assert!(picks.windows(2).all(|pair| pair[0] != pair[1]));
+
+        let mut single = RecentPicks::new(1, RepeatRule::Exclude);
+        let one = table(1, RollMethod::SimpleRoll);
+        assert_eq!(
+            rng.get_result_index_without_repeat(&one, &mut single),
+            Some(0)
+        );
+        assert_eq!(
+            rng.get_result_index_without_repeat(&one, &mut single),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn decayed_results_repeat_less_often() {
+        let repeats = |rule| {
+            let mut rng = SeededDiceRoller::new("seed", "step");
+            let table = table(4, RollMethod::SimpleRoll);
+            let mut recent = RecentPicks::new(1, rule);
+            let picks: Vec<usize> = (0..4_000)
+                .map(|_| {
+                    rng.get_result_index_without_repeat(&table, &mut recent)
+                        .unwrap()
+                })
+                .collect();
+            picks.windows(2).filter(|pair| pair[0] == pair[1]).count()
+        };
+        // Keeping half of its weight gives the last result 0.5 / 3.5 of the chances.
+        let decayed = repeats(RepeatRule::Decay { kept: 50 });
+        assert!(decayed > 500 && decayed < 650, "{decayed}");
+        assert!(repeats(RepeatRule::Decay { kept: 100 }) > 900);
+        assert_eq!(repeats(RepeatRule::Exclude), 0);
+    }
+
+    #[test]
+    fn decay_keeps_a_percentage_per_appearance() {
+        let rule = RepeatRule::Decay { kept: 50 };
+        assert_eq!(rule.weight(3, 0, 100), 300);
+        assert_eq!(rule.weight(3, 2, 100), 75);
+        assert_eq!(rule.weight(3, 2, 1), 0);
+        assert_eq!(RepeatRule::Decay { kept: 0 }.weight(3, 1, 100), 0);
+    }
+
+    #[test]
+    fn scaled_weights_never_add_up_to_more_than_a_u32() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let heavy = RollToProcess::new(
+            vec![
+                WeightedResult::new("a", u32::MAX / 2),
+                WeightedResult::new("b", u32::MAX / 2),
+            ],
+            RollMethod::SimpleRoll,
+        );
+        let mut recent = RecentPicks::new(1, RepeatRule::Decay { kept: 50 });
+        assert_eq!(recent.scale(&heavy.possible_results, &heavy.roll_method), 1);
+        for _ in 0..20 {
+            assert!(rng.get_result_without_repeat(&heavy, &mut recent).is_some());
+        }
+
+        let light = table(4, RollMethod::SimpleRoll);
+        let prepared = table(4, RollMethod::PreparedRoll(PreparedRoll::new(1, 4, 0)));
+        assert_eq!(
+            recent.scale(&light.possible_results, &light.roll_method),
+            100
+        );
+        assert_eq!(
+            recent.scale(&prepared.possible_results, &prepared.roll_method),
+            1
+        );
+    }
+
+    #[test]
+    fn picks_without_repeat_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let table = RollToProcess::new(
+            vec![
+                WeightedResult::new("a", 5),
+                WeightedResult::new("b", 3),
+                WeightedResult::new("c", 1),
+            ],
+            RollMethod::SimpleRoll,
+        );
+        let mut recent = RecentPicks::new(1, RepeatRule::Decay { kept: 20 });
+        let picks: Vec<&str> = (0..10)
+            .map(|_| rng.get_result_without_repeat(&table, &mut recent).unwrap())
+            .collect();
+        assert_eq!(
+            picks,
+            vec!["a", "a", "a", "b", "a", "b", "c", "a", "c", "a"]
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn saved_history_keeps_picks_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let table = table(5, RollMethod::SimpleRoll);
+        let mut recent = RecentPicks::new(2, RepeatRule::Exclude);
+        for _ in 0..3 {
+            rng.get_result_index_without_repeat(&table, &mut recent);
+        }
+        let saved = serde_json::to_string(&recent).unwrap();
+        let mut loaded: RecentPicks = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded, recent);
+
+        let mut other_rng = rng.clone();
+        for _ in 0..10 {
+            assert_eq!(
+                rng.get_result_index_without_repeat(&table, &mut recent),
+                other_rng.get_result_index_without_repeat(&table, &mut loaded)
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_rolls_are_reported() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let to_process = table(3, RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10)))
+            .with_out_of_range(OutOfRangePolicy::Error);
+        let mut recent = RecentPicks::new(2, RepeatRule::Exclude);
+        assert!(rng
+            .try_get_result_index_without_repeat(&to_process, &mut recent)
+            .is_err());
+        assert_eq!(
+            rng.get_result_index_without_repeat(&to_process, &mut recent),
+            None
+        );
+        assert_eq!(recent.history().count(), 0);
+    }
+}
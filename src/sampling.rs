@@ -0,0 +1,349 @@
+//! Sampling algorithms used by [`SeededDiceRoller`] to turn the raw output of its generator into
+//! booleans, numbers, ranges and shuffles.
+//!
+//! These algorithms are part of the crate and their behaviour is frozen: a given **seed** and
+//! **step** give the same results for as long as this crate's major version stays the same,
+//! whatever happens in the wider `rand` ecosystem. They reproduce what `rand` 0.8 did, so
+//! values generated with earlier versions of this crate stay the same.
+//!
+//! # Standard mode
+//! - booleans are **true** when the high bit of a `u32` draw is set,
+//! - integers of 32 bits or less are truncated from a `u32` draw, 64 bits ones come from a `u64`
+//!   draw and 128 bits ones from two `u64` draws (the first one being the low half), while
+//!   pointer-sized integers use the draw matching the target's pointer width,
+//! - floats in `[0, 1)` multiply the 24 (or 53) high bits of a `u32` (or `u64`) draw by 2^-24
+//!   (or 2^-53),
+//! - integer ranges use a widening multiplication with rejection, drawing `u32` values for types
+//!   of 32 bits or less and values of the type's width otherwise,
+//! - float ranges build a value in `[0, 1)` from the 23 (or 52) high bits of a draw, scale it
+//!   and reject the values that would round up to an excluded upper bound,
+//! - shuffles are Fisher-Yates shuffles going from the end of the slice to its start, the
+//!   swapped index being drawn from a `u32` range whenever the slice's length fits in it,
+//! - chances compare a `u64` draw against the probability multiplied by 2^64, while ratios draw
+//!   a `u32` below the denominator and compare it against the numerator. Both always consume
+//!   their draw, even when the outcome is certain.
+//!
+//! # Portable mode
+//! When the roller was created with [`SeededDiceRoller::new_portable`], every sample is built
+//! from fixed-width draws so that the same seed gives the same numbers on every target:
+//! - pointer-sized integers are drawn as 32 bits values,
+//! - integers ranges up to 64 bits are drawn from a `u64` using Lemire's widening
+//!   multiplication with rejection, pointer-sized integers being sampled as 64 bits values,
+//! - 128 bits integers ranges are drawn from two `u64` using a rejection zone and a modulo,
+//! - float ranges use the 53 (or 24) high bits of a `u64` (or `u32`) draw as their mantissa,
+//!   divided by 2^53 (or 2^24) for half-open ranges and by 2^53 - 1 (or 2^24 - 1) for inclusive
+//!   ones.
+
+use crate::SeededDiceRoller;
+use rand_core::RngCore;
+use std::ops::{Range, RangeInclusive};
+
+/// A type that can be sampled uniformly between two bounds by a [`SeededDiceRoller`].
+pub trait SampleUniform: Copy + PartialOrd {
+    /// Returns a value in the `[low, high)` range.
+    fn sample_single(low: Self, high: Self, roller: &mut SeededDiceRoller) -> Self;
+
+    /// Returns a value in the `[low, high]` range.
+    fn sample_single_inclusive(low: Self, high: Self, roller: &mut SeededDiceRoller) -> Self;
+}
+
+/// A range that can be given to [`SeededDiceRoller::gen_range`].
+pub trait SampleRange<T> {
+    /// Returns a value picked uniformly within the range.
+    fn sample_single(self, roller: &mut SeededDiceRoller) -> T;
+
+    /// Returns **true** if the range contains no value.
+    fn is_empty(&self) -> bool;
+}
+
+impl<T: SampleUniform> SampleRange<T> for Range<T> {
+    fn sample_single(self, roller: &mut SeededDiceRoller) -> T {
+        T::sample_single(self.start, self.end, roller)
+    }
+
+    fn is_empty(&self) -> bool {
+        !matches!(
+            self.start.partial_cmp(&self.end),
+            Some(std::cmp::Ordering::Less)
+        )
+    }
+}
+
+impl<T: SampleUniform> SampleRange<T> for RangeInclusive<T> {
+    fn sample_single(self, roller: &mut SeededDiceRoller) -> T {
+        T::sample_single_inclusive(*self.start(), *self.end(), roller)
+    }
+
+    fn is_empty(&self) -> bool {
+        !matches!(
+            self.start().partial_cmp(self.end()),
+            Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal)
+        )
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns the next 32 bits of the generator.
+    pub(crate) fn draw_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    /// Returns the next 64 bits of the generator.
+    pub(crate) fn draw_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    /// Returns the next 128 bits of the generator, built from two 64 bits draws.
+    pub(crate) fn draw_u128(&mut self) -> u128 {
+        let low = u128::from(self.draw_u64());
+        let high = u128::from(self.draw_u64());
+        (high << 64) | low
+    }
+
+    /// Returns a pointer-sized value, drawn on 32 bits in portable mode and using the target's
+    /// pointer width otherwise.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn draw_usize(&mut self) -> usize {
+        if self.portable || cfg!(target_pointer_width = "32") {
+            self.draw_u32() as usize
+        } else {
+            self.draw_u64() as usize
+        }
+    }
+
+    /// Returns **true** if the high bit of a 32 bits draw is set.
+    pub(crate) fn draw_bool(&mut self) -> bool {
+        self.draw_u32() >> 31 == 1
+    }
+
+    /// Returns a `f64` in `[0, 1)` built from the 53 high bits of a `u64` draw.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn draw_f64(&mut self) -> f64 {
+        (self.draw_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Returns a `f32` in `[0, 1)` built from the 24 high bits of a `u32` draw.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn draw_f32(&mut self) -> f32 {
+        (self.draw_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Returns an index in `[0, bound)`, drawn from a `u32` range whenever possible.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn draw_index(&mut self, bound: usize) -> usize {
+        if u32::try_from(bound).is_ok() {
+            u32::sample_single(0, bound as u32, self) as usize
+        } else {
+            usize::sample_single(0, bound, self)
+        }
+    }
+
+    /// Shuffles the given **slice** in place.
+    ///
+    /// The shuffle is a Fisher-Yates shuffle going from the end of the slice to its start.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.draw_index(i + 1);
+            slice.swap(i, j);
+        }
+    }
+}
+
+/// Returns the high and low halves of the product of two `u32`.
+#[allow(clippy::cast_possible_truncation)]
+fn wmul_u32(a: u32, b: u32) -> (u32, u32) {
+    let product = u64::from(a) * u64::from(b);
+    ((product >> 32) as u32, product as u32)
+}
+
+/// Returns the high and low halves of the product of two `u64`.
+#[allow(clippy::cast_possible_truncation)]
+fn wmul_u64(a: u64, b: u64) -> (u64, u64) {
+    let product = u128::from(a) * u128::from(b);
+    ((product >> 64) as u64, product as u64)
+}
+
+/// Returns the high and low halves of the product of two `usize`.
+#[allow(clippy::cast_possible_truncation)]
+fn wmul_usize(a: usize, b: usize) -> (usize, usize) {
+    let product = a as u128 * b as u128;
+    ((product >> usize::BITS) as usize, product as usize)
+}
+
+/// Returns the high and low halves of the product of two `u128`.
+fn wmul_u128(a: u128, b: u128) -> (u128, u128) {
+    const LOWER_MASK: u128 = !0 >> 64;
+    let mut low = (a & LOWER_MASK).wrapping_mul(b & LOWER_MASK);
+    let mut t = low >> 64;
+    low &= LOWER_MASK;
+    t += (a >> 64).wrapping_mul(b & LOWER_MASK);
+    low += (t & LOWER_MASK) << 64;
+    let mut high = t >> 64;
+    t = low >> 64;
+    low &= LOWER_MASK;
+    t += (b >> 64).wrapping_mul(a & LOWER_MASK);
+    low += (t & LOWER_MASK) << 64;
+    high += t >> 64;
+    high += (a >> 64).wrapping_mul(b >> 64);
+    (high, low)
+}
+
+/// Returns a value in `[0, range)` drawn from 64 bits values, or any `u64` if **range** is 0.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn portable_u64_below(roller: &mut SeededDiceRoller, range: u64) -> u64 {
+    if range == 0 {
+        return roller.draw_u64();
+    }
+    let threshold = range.wrapping_neg() % range;
+    loop {
+        let (high, low) = wmul_u64(roller.draw_u64(), range);
+        if low >= threshold {
+            return high;
+        }
+    }
+}
+
+/// Returns a value in `[0, range)` drawn from 128 bits values, or any `u128` if **range** is 0.
+fn portable_u128_below(roller: &mut SeededDiceRoller, range: u128) -> u128 {
+    if range == 0 {
+        return roller.draw_u128();
+    }
+    let zone = u128::MAX - (u128::MAX - range + 1) % range;
+    loop {
+        let value = roller.draw_u128();
+        if value <= zone {
+            return value % range;
+        }
+    }
+}
+
+macro_rules! uniform_int_impl {
+    (
+        $ty:ty, $unsigned:ident, $large:ident, $draw:ident, $wmul:ident,
+        $portable_large:ty, $portable_below:ident
+    ) => {
+        impl SampleUniform for $ty {
+            fn sample_single(low: Self, high: Self, roller: &mut SeededDiceRoller) -> Self {
+                assert!(low < high, "sample_single: low >= high");
+                Self::sample_single_inclusive(low, high - 1, roller)
+            }
+
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_possible_wrap,
+                clippy::cast_sign_loss,
+                clippy::cast_lossless
+            )]
+            fn sample_single_inclusive(
+                low: Self,
+                high: Self,
+                roller: &mut SeededDiceRoller,
+            ) -> Self {
+                assert!(low <= high, "sample_single_inclusive: low > high");
+                if roller.portable {
+                    // A range covering the whole type wraps to 0, which asks for any value
+                    let range =
+                        (high.wrapping_sub(low) as $unsigned as $portable_large).wrapping_add(1);
+                    let range = if range > $unsigned::MAX as $portable_large {
+                        0
+                    } else {
+                        range
+                    };
+                    return low.wrapping_add($portable_below(roller, range) as $ty);
+                }
+
+                let range = high.wrapping_sub(low).wrapping_add(1) as $unsigned as $large;
+                if range == 0 {
+                    return roller.$draw() as $ty;
+                }
+                let zone = if $unsigned::MAX as u128 <= u16::MAX as u128 {
+                    let ints_to_reject = ($large::MAX - range + 1) % range;
+                    $large::MAX - ints_to_reject
+                } else {
+                    (range << range.leading_zeros()).wrapping_sub(1)
+                };
+                loop {
+                    let (high, low_bits) = $wmul(roller.$draw(), range);
+                    if low_bits <= zone {
+                        return low.wrapping_add(high as $ty);
+                    }
+                }
+            }
+        }
+    };
+}
+
+uniform_int_impl! { u8, u8, u32, draw_u32, wmul_u32, u64, portable_u64_below }
+uniform_int_impl! { u16, u16, u32, draw_u32, wmul_u32, u64, portable_u64_below }
+uniform_int_impl! { u32, u32, u32, draw_u32, wmul_u32, u64, portable_u64_below }
+uniform_int_impl! { u64, u64, u64, draw_u64, wmul_u64, u64, portable_u64_below }
+uniform_int_impl! { usize, usize, usize, draw_usize, wmul_usize, u64, portable_u64_below }
+uniform_int_impl! { u128, u128, u128, draw_u128, wmul_u128, u128, portable_u128_below }
+uniform_int_impl! { i8, u8, u32, draw_u32, wmul_u32, u64, portable_u64_below }
+uniform_int_impl! { i16, u16, u32, draw_u32, wmul_u32, u64, portable_u64_below }
+uniform_int_impl! { i32, u32, u32, draw_u32, wmul_u32, u64, portable_u64_below }
+uniform_int_impl! { i64, u64, u64, draw_u64, wmul_u64, u64, portable_u64_below }
+uniform_int_impl! { isize, usize, usize, draw_usize, wmul_usize, u64, portable_u64_below }
+uniform_int_impl! { i128, u128, u128, draw_u128, wmul_u128, u128, portable_u128_below }
+
+macro_rules! uniform_float_impl {
+    (
+        $ty:ident, $uty:ident, $draw:ident, $fraction_bits:expr, $exponent_bias:expr,
+        $value1_2:ident
+    ) => {
+        /// Returns a value in `[1, 2)` using the high bits of **value** as its mantissa.
+        fn $value1_2(value: $uty) -> $ty {
+            let fraction = value >> ($uty::BITS - $fraction_bits);
+            $ty::from_bits(fraction | ($exponent_bias << $fraction_bits))
+        }
+
+        impl SampleUniform for $ty {
+            #[allow(clippy::cast_precision_loss)]
+            fn sample_single(low: Self, high: Self, roller: &mut SeededDiceRoller) -> Self {
+                assert!(low < high, "sample_single: low >= high");
+                let scale = high - low;
+                assert!(scale.is_finite(), "sample_single: range overflow");
+                loop {
+                    let value0_1 = if roller.portable {
+                        (roller.$draw() >> ($uty::BITS - $fraction_bits - 1)) as $ty
+                            / (<$uty>::from(1u8) << ($fraction_bits + 1)) as $ty
+                    } else {
+                        $value1_2(roller.$draw()) - 1.0
+                    };
+                    let value = value0_1 * scale + low;
+                    if value < high {
+                        return value;
+                    }
+                }
+            }
+
+            #[allow(clippy::cast_precision_loss)]
+            fn sample_single_inclusive(
+                low: Self,
+                high: Self,
+                roller: &mut SeededDiceRoller,
+            ) -> Self {
+                assert!(low <= high, "sample_single_inclusive: low > high");
+                if roller.portable {
+                    let scale = high - low;
+                    assert!(scale.is_finite(), "sample_single_inclusive: range overflow");
+                    let value0_1 = (roller.$draw() >> ($uty::BITS - $fraction_bits - 1)) as $ty
+                        / ((<$uty>::from(1u8) << ($fraction_bits + 1)) - 1) as $ty;
+                    return (value0_1 * scale + low).min(high);
+                }
+
+                // Shrinks the scale until the highest value that can be drawn maps to high
+                let max_rand = $value1_2($uty::MAX) - 1.0;
+                let mut scale = (high - low) / max_rand;
+                assert!(scale.is_finite(), "sample_single_inclusive: range overflow");
+                while scale * max_rand + low > high {
+                    scale = $ty::from_bits(scale.to_bits() - 1);
+                }
+                ($value1_2(roller.$draw()) - 1.0) * scale + low
+            }
+        }
+    };
+}
+
+uniform_float_impl! { f32, u32, draw_u32, 23, 127, f32_value1_2 }
+uniform_float_impl! { f64, u64, draw_u64, 52, 1023, f64_value1_2 }
@@ -0,0 +1,317 @@
+//! Rarity curves applied to ordered lists of possible results by the shaped [`RollMethod`]
+//! variants.
+//!
+//! Each curve gives every position in the list a multiplier, which is applied to the weight of
+//! the result found at that position before picking one of them. Multipliers are integers
+//! computed with integer arithmetic only, so that picks stay exact and are the same on every
+//! target. The gaussian curve's multipliers are `2^32 * exp(-(index - center)² / (2 * spread²))`
+//! rounded to the nearest integer, its exponential being computed in fixed point.
+
+use crate::{RollMethod, SampleUniform, SeededDiceRoller};
+use log::trace;
+
+/// The multiplier given to the first entry of exponential and gaussian curves.
+const CURVE_ONE: u64 = 1 << 32;
+
+/// The number of fractional bits used to compute gaussian multipliers.
+const FIXED_BITS: u32 = 62;
+
+/// `exp(-1)` with [`FIXED_BITS`] fractional bits.
+const EXP_MINUS_ONE: i128 = 1_696_544_475_317_221_319;
+
+/// The number of terms of the series giving the exponential of the fractional part of the
+/// gaussian exponent, enough for the last one to be below the fixed point precision.
+const EXP_TERMS: i128 = 24;
+
+/// Iterates over the multipliers a shaped [`RollMethod`] gives to each position of a list.
+pub(crate) struct ShapeFactors {
+    /// The method giving the shape of the curve.
+    method: RollMethod,
+    /// The number of entries in the list.
+    length: usize,
+    /// The position of the next multiplier.
+    index: usize,
+    /// The multiplier of the previous position, for curves computed step by step.
+    previous: u64,
+}
+
+impl ShapeFactors {
+    /// Returns the multipliers **method** gives to the positions of a list of **length**
+    /// entries. Methods that don't shape lists give the same multiplier to every entry.
+    pub(crate) fn new(method: RollMethod, length: usize) -> Self {
+        Self {
+            method,
+            length,
+            index: 0,
+            previous: CURVE_ONE,
+        }
+    }
+
+    /// Returns the multiplier of the entry at **index**.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn factor(&mut self, index: usize) -> u64 {
+        let last = self.length.saturating_sub(1) as u64;
+        let position = index as u64;
+        match self.method {
+            RollMethod::Triangular { peak } => {
+                let peak = u64::from(peak).min(last);
+                if position <= peak {
+                    (position + 1) * (last + 1 - peak)
+                } else {
+                    (last + 1 - position) * (peak + 1)
+                }
+            }
+            RollMethod::Exponential { decay } => {
+                if index > 0 {
+                    let kept = 100 - u64::from(decay.min(100));
+                    self.previous = self.previous * kept / 100;
+                }
+                self.previous
+            }
+            RollMethod::Gaussian { center, spread } => {
+                let distance = position.abs_diff(u64::from(center).min(last));
+                if spread == 0 {
+                    u64::from(distance == 0)
+                } else {
+                    gaussian_factor(distance, spread)
+                }
+            }
+            RollMethod::Linear { start, end } => {
+                if last == 0 {
+                    1
+                } else {
+                    u64::from(start) * (last - position) + u64::from(end) * position
+                }
+            }
+            _ => 1,
+        }
+    }
+}
+
+/// Returns `2^32 * exp(-distance² / (2 * spread²))` rounded to the nearest integer, using
+/// fixed point arithmetic.
+fn gaussian_factor(distance: u64, spread: u16) -> u64 {
+    let (distance, spread) = (i128::from(distance), i128::from(spread));
+    // Seven spreads away, the exponent reaches 24.5 and the multiplier rounds to 0.
+    if distance >= 7 * spread {
+        return 0;
+    }
+    let exponent = ((distance * distance) << (FIXED_BITS - 1)) / (spread * spread);
+    let (whole, fraction) = (exponent >> FIXED_BITS, exponent & ((1 << FIXED_BITS) - 1));
+
+    // exp(-fraction) from its series, then exp(-1) once per whole unit of the exponent.
+    let mut value = 1 << FIXED_BITS;
+    let mut term = value;
+    for k in 1..=EXP_TERMS {
+        term = -((term * fraction) >> FIXED_BITS) / k;
+        value += term;
+    }
+    for _ in 0..whole {
+        value = (value * EXP_MINUS_ONE) >> FIXED_BITS;
+    }
+    let shift = FIXED_BITS - CURVE_ONE.trailing_zeros();
+    u64::try_from((value + (1 << (shift - 1))) >> shift).unwrap_or(0)
+}
+
+impl Iterator for ShapeFactors {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index >= self.length {
+            return None;
+        }
+        let factor = self.factor(self.index);
+        self.index += 1;
+        Some(factor)
+    }
+}
+
+impl SeededDiceRoller {
+    /// Picks a result from a list of **length** entries whose weights are given by **weight**,
+    /// after multiplying the weight of each entry by the multiplier its position gets from the
+    /// curve of the given [`RollMethod`].
+    pub(crate) fn process_shaped_roll(
+        &mut self,
+        weight: &dyn Fn(usize) -> u32,
+        length: usize,
+        method: RollMethod,
+    ) -> Option<usize> {
+        let shaped_weights = || {
+            ShapeFactors::new(method, length)
+                .enumerate()
+                .map(|(i, factor)| u128::from(factor) * u128::from(weight(i)))
+        };
+
+        let total: u128 = shaped_weights().sum();
+        if total == 0 {
+            trace!("   chosen: None, every shaped weight is 0");
+            return None;
+        }
+        let roll = u128::sample_single(0, total, self);
+        let mut last_end = 0;
+        let result = shaped_weights().position(|weight| {
+            last_end += weight;
+            roll < last_end
+        });
+        trace!("   chosen: {result:?}");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RollToProcess, WeightedResult};
+
+    /// Returns a list of **length** entries of weight 1 to pick from using **method**.
+    fn list(length: usize, method: RollMethod) -> RollToProcess<usize> {
+        RollToProcess {
+            possible_results: (0..length)
+                .map(|result| WeightedResult { result, weight: 1 })
+                .collect(),
+            roll_method: method,
+            ..Default::default()
+        }
+    }
+
+    /// Returns how many times each index got picked in **picks** picks.
+    fn counts(method: RollMethod, length: usize, picks: usize) -> Vec<usize> {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let to_process = list(length, method);
+        let mut counts = vec![0; length];
+        for _ in 0..picks {
+            counts[rng.get_result_index(&to_process).unwrap()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn shape_factors_follow_their_curve() {
+        let factors = |method, length| ShapeFactors::new(method, length).collect::<Vec<u64>>();
+
+        assert_eq!(
+            factors(RollMethod::Triangular { peak: 1 }, 4),
+            vec![3, 6, 4, 2]
+        );
+        assert_eq!(
+            factors(RollMethod::Triangular { peak: 9 }, 3),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            factors(RollMethod::Exponential { decay: 50 }, 3),
+            vec![CURVE_ONE, CURVE_ONE / 2, CURVE_ONE / 4]
+        );
+        assert_eq!(
+            factors(RollMethod::Exponential { decay: 200 }, 3),
+            vec![CURVE_ONE, 0, 0]
+        );
+        assert_eq!(
+            factors(
+                RollMethod::Gaussian {
+                    center: 1,
+                    spread: 0
+                },
+                3
+            ),
+            vec![0, 1, 0]
+        );
+        assert_eq!(
+            factors(
+                RollMethod::Gaussian {
+                    center: 2,
+                    spread: 1
+                },
+                3
+            ),
+            vec![581_260_615, 2_605_029_347, CURVE_ONE]
+        );
+        assert_eq!(
+            factors(RollMethod::Linear { start: 0, end: 3 }, 4),
+            vec![0, 3, 6, 9]
+        );
+        assert_eq!(factors(RollMethod::Linear { start: 0, end: 0 }, 1), vec![1]);
+    }
+
+    #[test]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn gaussian_factors_are_rounded_from_the_curve() {
+        for spread in [1, 2, 7, 100, 4_321, u16::MAX] {
+            for distance in (0..8 * u64::from(spread)).step_by(usize::from(spread / 50 + 1)) {
+                let ratio = distance as f64 / f64::from(spread);
+                let expected = (CURVE_ONE as f64 * (-0.5 * ratio * ratio).exp()).round() as u64;
+                assert_eq!(
+                    gaussian_factor(distance, spread),
+                    expected,
+                    "{distance} entries away with a spread of {spread}"
+                );
+            }
+        }
+        assert_eq!(gaussian_factor(u64::MAX, u16::MAX), 0);
+    }
+
+    #[test]
+    fn shaped_rolls_are_deterministic() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let methods = [
+            RollMethod::Triangular { peak: 3 },
+            RollMethod::Exponential { decay: 30 },
+            RollMethod::Gaussian {
+                center: 6,
+                spread: 2,
+            },
+            RollMethod::Linear { start: 1, end: 10 },
+        ];
+        let picks: Vec<usize> = methods
+            .iter()
+            .flat_map(|method| {
+                let to_process = list(10, *method);
+                (0..3)
+                    .map(|_| rng.get_result_index(&to_process).unwrap())
+                    .collect::<Vec<usize>>()
+            })
+            .collect();
+        assert_eq!(picks, vec![8, 5, 5, 0, 0, 0, 7, 5, 2, 6, 8, 9]);
+    }
+
+    #[test]
+    fn shaped_rolls_favour_the_expected_entries() {
+        let triangular = counts(RollMethod::Triangular { peak: 2 }, 7, 7_000);
+        assert!(triangular.iter().all(|count| *count <= triangular[2]));
+
+        let exponential = counts(RollMethod::Exponential { decay: 40 }, 6, 6_000);
+        assert!(exponential.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let gaussian = counts(
+            RollMethod::Gaussian {
+                center: 5,
+                spread: 1,
+            },
+            11,
+            10_000,
+        );
+        let around_center: usize = gaussian[4..=6].iter().sum();
+        assert!(around_center > 8_600 && around_center < 9_050);
+
+        let linear = counts(RollMethod::Linear { start: 0, end: 1 }, 5, 5_000);
+        assert_eq!(linear[0], 0);
+        assert!(linear.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn shaped_rolls_respect_weights() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut to_process = list(3, RollMethod::Exponential { decay: 10 });
+        to_process.possible_results[0].weight = 0;
+        to_process.possible_results[1].weight = 0;
+        for _ in 0..20 {
+            assert_eq!(rng.get_result_index(&to_process), Some(2));
+        }
+
+        to_process.possible_results[2].weight = 0;
+        assert_eq!(rng.get_result_index(&to_process), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Extension point allowing to pick results in a [`RollToProcess`] with a custom selection logic.
+
+use crate::{
+    Edges, OutOfRangeError, OutOfRangePolicy, RollMethod, RollToProcess, SeededDiceRoller,
+    WeightedResult,
+};
+
+/// A way to pick the index of one entry in a list of weighted possible results of type **T**.
+///
+/// Strategies see the whole entries, so they can pick depending on the results themselves as
+/// well as on their weights. The trait is object safe, so strategies can be stored as
+/// `Box<dyn PickStrategy<T>>` and chosen at runtime.
+///
+/// Every [`RollMethod`] is a [`PickStrategy`], which is how
+/// [`SeededDiceRoller::get_result_index`] works. Implement it on your own types to plug a bespoke
+/// selection logic into [`SeededDiceRoller::get_result_index_with`] while still using
+/// [`RollToProcess`] tables.
+///
+/// A [`RollMethod`] used as a strategy doesn't know the [`OutOfRangePolicy`] of the table
+/// it picks in: the rolls of a [`RollMethod::PreparedRoll`] outside of the list are always
+/// clamped, whereas [`SeededDiceRoller::get_result_index`] follows the policy of the table.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// /// Always picks the heaviest entry, the lowest result in case of a tie.
+/// struct Heaviest;
+///
+/// impl<T: Ord> PickStrategy<T> for Heaviest {
+///     fn pick_index(
+///         &mut self,
+///         _roller: &mut SeededDiceRoller,
+///         entries: &[WeightedResult<T>],
+///     ) -> Option<usize> {
+///         (0..entries.len()).max_by(|&a, &b| {
+///             let (a, b) = (&entries[a], &entries[b]);
+///             a.weight.cmp(&b.weight).then(b.result.cmp(&a.result))
+///         })
+///     }
+/// }
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let table = RollToProcess {
+///     possible_results: vec![
+///         WeightedResult { result: "c", weight: 5 },
+///         WeightedResult { result: "a", weight: 1 },
+///         WeightedResult { result: "b", weight: 5 },
+///     ],
+///     ..Default::default()
+/// };
+/// assert_eq!(rng.get_result_index_with(&table, &mut Heaviest), Some(2));
+/// ```
+pub trait PickStrategy<T> {
+    /// Returns the index of the entry picked among the **entries**, using the **roller** for any
+    /// random draw, or **None** if no entry can be picked.
+    fn pick_index(
+        &mut self,
+        roller: &mut SeededDiceRoller,
+        entries: &[WeightedResult<T>],
+    ) -> Option<usize>;
+}
+
+/// Picks like [`SeededDiceRoller::get_result_index`] does in a table using
+/// [`OutOfRangePolicy::Clamp`].
+impl<T> PickStrategy<T> for RollMethod {
+    fn pick_index(
+        &mut self,
+        roller: &mut SeededDiceRoller,
+        entries: &[WeightedResult<T>],
+    ) -> Option<usize> {
+        roller
+            .pick_with_method(entries, self, OutOfRangePolicy::Clamp)
+            .ok()
+            .flatten()
+    }
+}
+
+impl SeededDiceRoller {
+    /// Picks the index of one of the **entries** using a **method**, handling the rolls of a
+    /// [`RollMethod::PreparedRoll`] outside of the list as the **policy** requires. Clamped rolls
+    /// go to the first or last entry, whatever their weight.
+    pub(crate) fn pick_with_method<T>(
+        &mut self,
+        entries: &[WeightedResult<T>],
+        method: &RollMethod,
+        policy: OutOfRangePolicy,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        self.pick_weighted_with_method(
+            &|i| entries[i].weight,
+            entries.len(),
+            method,
+            policy,
+            Edges::Listed,
+        )
+    }
+
+    /// Picks the index of one of **length** entries whose weights are given by **weight** using a
+    /// **method**, handling the rolls of a [`RollMethod::PreparedRoll`] outside of the list as
+    /// the **policy** requires and clamping them onto the **edges**.
+    pub(crate) fn pick_weighted_with_method(
+        &mut self,
+        weight: &dyn Fn(usize) -> u32,
+        length: usize,
+        method: &RollMethod,
+        policy: OutOfRangePolicy,
+        edges: Edges,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        Ok(match length {
+            0 => None,
+            1 if picks_lone_entry(method, policy) => Some(0),
+            _ => match method {
+                RollMethod::PreparedRoll(ref roll) => {
+                    self.process_prepared_roll(weight, length, roll, policy, edges)?
+                }
+                RollMethod::GaussianRoll(dice) => self.process_gaussian_roll(weight, length, *dice),
+                RollMethod::SimpleRoll => self.process_simple_roll((0..length).map(weight)),
+                RollMethod::Triangular { .. }
+                | RollMethod::Exponential { .. }
+                | RollMethod::Gaussian { .. }
+                | RollMethod::Linear { .. } => self.process_shaped_roll(weight, length, *method),
+            },
+        })
+    }
+
+    /// Returns the index of the result picked by **strategy** in a **`to_process`** list,
+    /// ignoring the [`RollMethod`] stored alongside it.
+    pub fn get_result_index_with<T>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        strategy: &mut dyn PickStrategy<T>,
+    ) -> Option<usize> {
+        strategy.pick_index(self, &to_process.possible_results)
+    }
+
+    /// Returns the result picked by **strategy** in a **`to_process`** list, ignoring the
+    /// [`RollMethod`] stored alongside it.
+    pub fn get_result_with<T: Copy>(
+        &mut self,
+        to_process: &RollToProcess<T>,
+        strategy: &mut dyn PickStrategy<T>,
+    ) -> Option<T> {
+        self.get_result_index_with(to_process, strategy)
+            .map(|index| to_process.possible_results[index].result)
+    }
+}
+
+/// Tells if a list of a single entry always gives it when picked with a **method** and a
+/// **policy**, without drawing anything. A [`RollMethod::PreparedRoll`] still has to be rolled
+/// unless its rolls are clamped, as they can miss the entry.
+pub(crate) fn picks_lone_entry(method: &RollMethod, policy: OutOfRangePolicy) -> bool {
+    !matches!(method, RollMethod::PreparedRoll(_)) || policy == OutOfRangePolicy::Clamp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CopyableRollToProcess, CopyableWeightedResult, PreparedRoll};
+
+    /// Ignores weights and picks any entry with the same chances, counting its picks.
+    struct Unweighted {
+        picks: usize,
+    }
+
+    impl<T> PickStrategy<T> for Unweighted {
+        fn pick_index(
+            &mut self,
+            roller: &mut SeededDiceRoller,
+            entries: &[WeightedResult<T>],
+        ) -> Option<usize> {
+            if entries.is_empty() {
+                return None;
+            }
+            self.picks += 1;
+            Some(roller.gen_range(0..entries.len()))
+        }
+    }
+
+    /// Picks among the entries whose result starts with a **prefix**, following their weights.
+    struct Prefixed {
+        prefix: &'static str,
+    }
+
+    impl PickStrategy<&str> for Prefixed {
+        fn pick_index(
+            &mut self,
+            roller: &mut SeededDiceRoller,
+            entries: &[WeightedResult<&str>],
+        ) -> Option<usize> {
+            let matching = |i: usize| {
+                if entries[i].result.starts_with(self.prefix) {
+                    entries[i].weight
+                } else {
+                    0
+                }
+            };
+            roller
+                .pick_weighted_with_method(
+                    &matching,
+                    entries.len(),
+                    &RollMethod::SimpleRoll,
+                    OutOfRangePolicy::Clamp,
+                    Edges::Listed,
+                )
+                .ok()
+                .flatten()
+        }
+    }
+
+    #[test]
+    fn roll_methods_pick_like_get_result_index() {
+        let methods = [
+            RollMethod::PreparedRoll(PreparedRoll {
+                dice: 2,
+                die_type: 6,
+                modifier: 0,
+            }),
+            RollMethod::GaussianRoll(3),
+            RollMethod::SimpleRoll,
+            RollMethod::Linear { start: 1, end: 4 },
+        ];
+        for method in methods {
+            let to_process = RollToProcess {
+                possible_results: SeededDiceRoller::to_possible_results((0..11).collect()),
+                roll_method: method,
+                ..Default::default()
+            };
+            let mut rng = SeededDiceRoller::new("seed", "step");
+            let mut other_rng = SeededDiceRoller::new("seed", "step");
+            for _ in 0..20 {
+                assert_eq!(
+                    rng.get_result_index(&to_process),
+                    other_rng.get_result_index_with(&to_process, &mut method.clone())
+                );
+            }
+        }
+
+        // Used as a strategy, a method always clamps the rolls outside of the list.
+        let mut method = RollMethod::PreparedRoll(PreparedRoll::new(1, 20, 0));
+        let to_process = RollToProcess::new(
+            SeededDiceRoller::to_possible_results((0..4).collect()),
+            method,
+        );
+        let wrapped = to_process.clone().with_out_of_range(OutOfRangePolicy::Wrap);
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut other_rng = rng.clone();
+        let mut wrapped_rng = rng.clone();
+        let mut differs = false;
+        for _ in 0..20 {
+            let picked = rng.get_result_index_with(&wrapped, &mut method);
+            assert_eq!(picked, other_rng.get_result_index(&to_process));
+            differs |= picked != wrapped_rng.get_result_index(&wrapped);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn custom_strategies_get_the_roller_and_the_entries() {
+        let mut rng = SeededDiceRoller::new_portable("seed", "step");
+        let mut strategy = Unweighted { picks: 0 };
+        let to_process = CopyableRollToProcess {
+            possible_results: vec![
+                CopyableWeightedResult {
+                    result: "a",
+                    weight: 0,
+                },
+                CopyableWeightedResult {
+                    result: "b",
+                    weight: 0,
+                },
+            ],
+            roll_method: RollMethod::SimpleRoll,
+            ..Default::default()
+        };
+
+        let picks: Vec<&str> = (0..6)
+            .filter_map(|_| rng.get_result_with(&to_process, &mut strategy))
+            .collect();
+        assert_eq!(picks, vec!["a", "b", "b", "b", "a", "b"]);
+        assert_eq!(strategy.picks, 6);
+
+        let empty = RollToProcess::<&str>::default();
+        assert_eq!(rng.get_result_index_with(&empty, &mut strategy), None);
+        assert_eq!(strategy.picks, 6);
+
+        let monsters = RollToProcess::new(
+            vec![
+                WeightedResult::new("goblin", 5),
+                WeightedResult::new("dragon", 1),
+                WeightedResult::new("gnoll", 3),
+                WeightedResult::new("ghost", 0),
+            ],
+            RollMethod::SimpleRoll,
+        );
+        let mut prefixed = Prefixed { prefix: "g" };
+        for _ in 0..20 {
+            let picked = rng.get_result_with(&monsters, &mut prefixed).unwrap();
+            assert!(["goblin", "gnoll"].contains(&picked), "{picked}");
+        }
+        prefixed.prefix = "z";
+        assert_eq!(rng.get_result_with(&monsters, &mut prefixed), None);
+    }
+
+    #[test]
+    fn strategies_can_be_chosen_at_runtime() {
+        let mut strategies: Vec<Box<dyn PickStrategy<usize>>> = vec![
+            Box::new(RollMethod::GaussianRoll(3)),
+            Box::new(Unweighted { picks: 0 }),
+        ];
+        let to_process = RollToProcess::new(
+            SeededDiceRoller::to_possible_results((0..5).collect()),
+            RollMethod::SimpleRoll,
+        );
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut other_rng = rng.clone();
+        for _ in 0..20 {
+            assert_eq!(
+                rng.get_result_with(&to_process, strategies[0].as_mut()),
+                other_rng.get_result_with(&to_process, &mut RollMethod::GaussianRoll(3))
+            );
+        }
+        assert!(rng
+            .get_result_with(&to_process, strategies[1].as_mut())
+            .is_some());
+    }
+}
@@ -0,0 +1,435 @@
+//! Controlled randomness for repeated checks, limiting the streaks of hits or misses while
+//! keeping the long-run chance of success of the checks.
+
+use crate::SeededDiceRoller;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The chance under which the constant of a [`StreakRule::Prd`] is approximated instead of
+/// searched for.
+const PRD_APPROXIMATED_CHANCE: f64 = 1e-4;
+/// The chance of missing every check so far under which the remaining checks are neglected when
+/// searching for the constant of a [`StreakRule::Prd`].
+const PRD_NEGLECTED_MISSES: f64 = 1e-20;
+
+/// How a [`SmoothedChance`] limits streaks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StreakRule {
+    /// The pseudo-random distribution of Warcraft III: the n-th check since the last hit
+    /// succeeds with a probability of `C * n`, the constant `C` being chosen so that one check
+    /// in `1 / chance` succeeds in the long run.
+    #[default]
+    Prd,
+    /// Checks fail after **`max_hits`** hits in a row and succeed after **`max_misses`** misses in
+    /// a row, the other checks succeeding with a probability chosen so that the long-run chance
+    /// stays the same.
+    Cap {
+        /// How many hits in a row are allowed, without limit if None.
+        max_hits: Option<u32>,
+        /// How many misses in a row are allowed, without limit if None.
+        max_misses: Option<u32>,
+    },
+}
+
+impl Display for StreakRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cap = |max: Option<u32>| max.map_or_else(|| "none".to_string(), |m| m.to_string());
+        match self {
+            StreakRule::Prd => write!(f, "Prd"),
+            StreakRule::Cap {
+                max_hits,
+                max_misses,
+            } => write!(
+                f,
+                "Cap(hits: {}, misses: {})",
+                cap(*max_hits),
+                cap(*max_misses)
+            ),
+        }
+    }
+}
+
+/// A check succeeding in the long run with the given **chance**, whose streaks of hits or misses
+/// are limited following a [`StreakRule`].
+///
+/// The current streak is part of the check, so serializing it keeps the next checks giving the
+/// same results once it is loaded back.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let mut hit = SmoothedChance::new(0.75, StreakRule::Prd);
+///
+/// let checks: Vec<bool> = (0..100).map(|_| rng.check_smoothed(&mut hit)).collect();
+/// assert!(checks.windows(2).all(|pair| pair[0] || pair[1]));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmoothedChance {
+    /// The long-run chance of success of the checks.
+    chance: f64,
+    /// How streaks are limited.
+    rule: StreakRule,
+    /// The constant `C` of a [`StreakRule::Prd`], or the probability of the checks that aren't
+    /// forced by a [`StreakRule::Cap`].
+    constant: f64,
+    /// How many checks in a row succeeded.
+    #[serde(default)]
+    hits: u32,
+    /// How many checks in a row failed.
+    #[serde(default)]
+    misses: u32,
+}
+
+impl SmoothedChance {
+    /// Creates a new [`SmoothedChance`] succeeding with a long-run **chance**, which must be
+    /// between 0 and 1, and limiting streaks following a **rule**.
+    ///
+    /// # Panics
+    /// Panics if **chance** is not between 0 and 1.
+    #[must_use]
+    pub fn new(chance: f64, rule: StreakRule) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&chance),
+            "SmoothedChance: chance must be between 0 and 1"
+        );
+        let constant = match rule {
+            StreakRule::Prd => Self::prd_constant(chance),
+            StreakRule::Cap {
+                max_hits,
+                max_misses,
+            } => Self::capped_probability(chance, max_hits, max_misses),
+        };
+        Self {
+            chance,
+            rule,
+            constant,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Creates a new [`SmoothedChance`] succeeding **p** percent of the time in the long run.
+    /// Values above 100 always succeed.
+    #[must_use]
+    pub fn percent(p: u8, rule: StreakRule) -> Self {
+        Self::new(f64::from(p.min(100)) / 100.0, rule)
+    }
+
+    /// Returns the long-run chance of success of the checks.
+    #[must_use]
+    pub fn chance(&self) -> f64 {
+        self.chance
+    }
+
+    /// Returns how streaks are limited.
+    #[must_use]
+    pub fn rule(&self) -> StreakRule {
+        self.rule
+    }
+
+    /// Returns the constant `C` of a [`StreakRule::Prd`], or the probability of the checks that
+    /// aren't forced by a [`StreakRule::Cap`].
+    #[must_use]
+    pub fn constant(&self) -> f64 {
+        self.constant
+    }
+
+    /// Returns the current streak: how many checks in a row succeeded, or failed if negative.
+    #[must_use]
+    pub fn streak(&self) -> i64 {
+        i64::from(self.hits) - i64::from(self.misses)
+    }
+
+    /// Forgets the current streak, as if no check ever happened.
+    pub fn reset(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Returns the probability of success of the next check.
+    #[must_use]
+    pub fn next_probability(&self) -> f64 {
+        match self.rule {
+            StreakRule::Prd => (self.constant * (f64::from(self.misses) + 1.0)).min(1.0),
+            StreakRule::Cap {
+                max_hits,
+                max_misses,
+            } => {
+                if max_misses.is_some_and(|max| self.misses >= max) {
+                    1.0
+                } else if max_hits.is_some_and(|max| self.hits >= max) {
+                    0.0
+                } else {
+                    self.constant
+                }
+            }
+        }
+    }
+
+    /// Returns the constant `C` giving a long-run **chance** of success to checks succeeding with
+    /// a probability of `C * n` on the n-th check since the last hit.
+    ///
+    /// The constant is found by bisection, down to the precision of a `f64`. Chances below
+    /// 1 in 10 000 would make that search slow, so their constant comes from the asymptotic
+    /// expansion `C = π / (2 * (1 / chance + 1 / 3)²)` instead, whose relative error is below
+    /// `chance²`.
+    ///
+    /// # Panics
+    /// Panics if **chance** is not between 0 and 1.
+    #[must_use]
+    pub fn prd_constant(chance: f64) -> f64 {
+        assert!(
+            (0.0..=1.0).contains(&chance),
+            "prd_constant: chance must be between 0 and 1"
+        );
+        if chance <= 0.0 || chance >= 1.0 {
+            return chance;
+        }
+        if chance < PRD_APPROXIMATED_CHANCE {
+            let checks = 1.0 / chance + 1.0 / 3.0;
+            return std::f64::consts::PI / (2.0 * checks * checks);
+        }
+        bisect(chance, |constant| {
+            // The expected number of checks until a hit is the sum of the chances of missing
+            // every check before the n-th, which soon become negligible.
+            let mut expected = 0.0;
+            let mut missed_so_far = 1.0;
+            let mut n = 1.0;
+            while missed_so_far > PRD_NEGLECTED_MISSES {
+                expected += missed_so_far;
+                missed_so_far *= 1.0 - f64::min(1.0, constant * n);
+                n += 1.0;
+            }
+            1.0 / expected
+        })
+    }
+
+    /// Returns the probability of success of the checks that aren't forced by **`max_hits`** hits
+    /// or **`max_misses`** misses in a row, so that they succeed with a long-run **chance**.
+    ///
+    /// Streak caps can't give a long-run chance below `1 / (max_misses + 1)` or above
+    /// `max_hits / (max_hits + 1)`: the closest possible probability is returned in that case.
+    ///
+    /// # Panics
+    /// Panics if **chance** is not between 0 and 1.
+    #[must_use]
+    pub fn capped_probability(chance: f64, max_hits: Option<u32>, max_misses: Option<u32>) -> f64 {
+        assert!(
+            (0.0..=1.0).contains(&chance),
+            "capped_probability: chance must be between 0 and 1"
+        );
+        // Hits and misses come in alternating runs, the length of a run being geometric but
+        // stopped by its cap.
+        let expected_run = |p: f64, max: Option<u32>| match max {
+            _ if p >= 1.0 => max.map_or(f64::INFINITY, f64::from),
+            Some(max) => (1.0 - p.powi(i32::try_from(max).unwrap_or(i32::MAX))) / (1.0 - p),
+            None => 1.0 / (1.0 - p),
+        };
+        bisect(chance, |p| {
+            let hits = expected_run(p, max_hits);
+            let misses = expected_run(1.0 - p, max_misses);
+            if hits.is_infinite() {
+                1.0
+            } else {
+                hits / (hits + misses)
+            }
+        })
+    }
+
+    /// Updates the current streak after a check giving **hit**.
+    fn record(&mut self, hit: bool) {
+        if hit {
+            self.hits = self.hits.saturating_add(1);
+            self.misses = 0;
+        } else {
+            self.misses = self.misses.saturating_add(1);
+            self.hits = 0;
+        }
+    }
+}
+
+impl Display for SmoothedChance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SmoothedChance {{ chance: {}, rule: {}, streak: {} }}",
+            self.chance,
+            self.rule,
+            self.streak()
+        )
+    }
+}
+
+/// Returns the value between 0 and 1 for which the increasing function **rate** gives
+/// **target**, narrowing the search until it reaches the precision of a `f64`.
+fn bisect(target: f64, rate: impl Fn(f64) -> f64) -> f64 {
+    let (mut low, mut high) = (0.0_f64, 1.0_f64);
+    loop {
+        let middle = low + (high - low) / 2.0;
+        if middle <= low || middle >= high {
+            return middle;
+        }
+        if rate(middle) < target {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns **true** if a **check** succeeds, then updates its streak.
+    ///
+    /// Checks forced by a [`StreakRule::Cap`] or reaching a probability of 1 under a
+    /// [`StreakRule::Prd`] don't draw from the generator.
+    pub fn check_smoothed(&mut self, check: &mut SmoothedChance) -> bool {
+        let probability = check.next_probability();
+        let gen = if probability >= 1.0 {
+            true
+        } else if probability <= 0.0 {
+            false
+        } else {
+            self.chance(probability)
+        };
+        trace!("  smoothed: {check} = {gen}");
+        check.record(gen);
+        gen
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    /// Returns how often **check** succeeds over **count** checks, and its longest streaks of
+    /// hits and misses.
+    fn run(mut check: SmoothedChance, count: u32) -> (f64, u32, u32) {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let (mut hits, mut longest_hits, mut longest_misses) = (0, 0, 0);
+        for _ in 0..count {
+            if rng.check_smoothed(&mut check) {
+                hits += 1;
+            }
+            longest_hits = longest_hits.max(check.hits);
+            longest_misses = longest_misses.max(check.misses);
+        }
+        (
+            f64::from(hits) / f64::from(count),
+            longest_hits,
+            longest_misses,
+        )
+    }
+
+    #[test]
+    fn prd_constants_match_the_known_values() {
+        let constant = SmoothedChance::prd_constant;
+        assert!((constant(0.1) - 0.014_745_844).abs() < 1e-9);
+        assert!((constant(0.25) - 0.084_744_091).abs() < 1e-9);
+        assert!((constant(0.5) - 0.302_103_025).abs() < 1e-9);
+        assert!((constant(0.75) - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(constant(0.0), 0.0);
+        assert_eq!(constant(1.0), 1.0);
+    }
+
+    #[test]
+    fn small_prd_chances_get_their_constant_quickly() {
+        let constant = SmoothedChance::prd_constant;
+        let searched = constant(PRD_APPROXIMATED_CHANCE);
+        let approximated = constant(PRD_APPROXIMATED_CHANCE * (1.0 - f64::EPSILON));
+        assert!((approximated - searched).abs() < 1e-8 * searched);
+        for chance in [1e-5, 1e-9, 1e-150] {
+            let c = constant(chance);
+            assert!(c > 0.0 && (c - std::f64::consts::PI * chance * chance / 2.0).abs() < 1e-4 * c);
+        }
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut check = SmoothedChance::new(1e-6, StreakRule::Prd);
+        assert!(!(0..100).any(|_| rng.check_smoothed(&mut check)));
+    }
+
+    #[test]
+    fn prd_keeps_the_long_run_chance() {
+        for chance in [0.1, 0.25, 0.5, 0.75] {
+            let (frequency, _, _) = run(SmoothedChance::new(chance, StreakRule::Prd), 100_000);
+            assert!((frequency - chance).abs() < 0.006, "{chance}: {frequency}");
+        }
+        let (_, _, longest_misses) = run(SmoothedChance::percent(75, StreakRule::Prd), 10_000);
+        assert_eq!(longest_misses, 1);
+    }
+
+    #[test]
+    fn caps_limit_streaks_and_keep_the_long_run_chance() {
+        let rule = StreakRule::Cap {
+            max_hits: Some(4),
+            max_misses: Some(2),
+        };
+        let check = SmoothedChance::new(0.6, rule);
+        assert!(check.constant() < 0.6);
+        let (frequency, longest_hits, longest_misses) = run(check, 100_000);
+        assert!((frequency - 0.6).abs() < 0.006, "{frequency}");
+        assert_eq!((longest_hits, longest_misses), (4, 2));
+
+        let misses_only = StreakRule::Cap {
+            max_hits: None,
+            max_misses: Some(3),
+        };
+        let (frequency, _, longest_misses) = run(SmoothedChance::new(0.3, misses_only), 100_000);
+        assert!((frequency - 0.3).abs() < 0.006, "{frequency}");
+        assert_eq!(longest_misses, 3);
+    }
+
+    #[test]
+    fn caps_out_of_reach_give_the_closest_probability() {
+        assert_eq!(SmoothedChance::capped_probability(0.1, None, Some(1)), 0.0);
+        assert!(SmoothedChance::capped_probability(0.9, Some(2), None) > 1.0 - 1e-12);
+        assert!((SmoothedChance::capped_probability(0.5, None, None) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn streaks_follow_the_checks() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut check = SmoothedChance::percent(25, StreakRule::Prd);
+        let checks: Vec<bool> = (0..12).map(|_| rng.check_smoothed(&mut check)).collect();
+        assert_eq!(
+            checks,
+            vec![false, false, false, false, true, false, false, false, false, true, false, true]
+        );
+        assert_eq!(check.streak(), 1);
+        check.reset();
+        assert_eq!(check.next_probability(), check.constant());
+    }
+
+    #[test]
+    fn longest_streaks_of_misses_still_succeed() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut check = SmoothedChance::new(0.1, StreakRule::Prd);
+        check.misses = u32::MAX;
+        assert_eq!(check.next_probability(), 1.0);
+        assert!(rng.check_smoothed(&mut check));
+        assert_eq!(check.streak(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn saved_checks_keep_their_streak() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut check = SmoothedChance::new(0.2, StreakRule::Prd);
+        for _ in 0..5 {
+            rng.check_smoothed(&mut check);
+        }
+        let saved = serde_json::to_string(&check).unwrap();
+        let mut loaded: SmoothedChance = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded, check);
+
+        let mut other_rng = rng.clone();
+        for _ in 0..20 {
+            assert_eq!(
+                rng.check_smoothed(&mut check),
+                other_rng.check_smoothed(&mut loaded)
+            );
+        }
+    }
+}
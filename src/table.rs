@@ -0,0 +1,288 @@
+//! Shorter ways to write a [`RollToProcess`]: a fluent [`Table`] builder, collecting
+//! `(result, weight)` pairs and the [`crate::table!`] and [`crate::try_table!`] macros.
+
+use crate::{OutOfRangePolicy, RollMethod, RollToProcess, WeightedResult};
+
+/// Builds a [`RollToProcess`] one entry at a time, picked with [`RollMethod::SimpleRoll`] unless
+/// given another method.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let table = Table::new()
+///     .entry("a", 5)
+///     .entry("b", 1)
+///     .method(RollMethod::GaussianRoll(3))
+///     .build();
+/// assert_eq!(table.possible_results[0].weight, 5);
+/// assert_eq!(table.roll_method, RollMethod::GaussianRoll(3));
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// assert!(rng.get_result(&table).is_some());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Table<T> {
+    /// The table being built.
+    table: RollToProcess<T>,
+}
+
+impl<T> Table<T> {
+    /// Creates a new [`Table`] without entries.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            table: RollToProcess::new(Vec::new(), RollMethod::SimpleRoll),
+        }
+    }
+
+    /// Adds a **result** with its **weight** after the other entries.
+    #[must_use]
+    pub fn entry(mut self, result: T, weight: u32) -> Self {
+        self.table
+            .possible_results
+            .push(WeightedResult::new(result, weight));
+        self
+    }
+
+    /// Adds every one of the **entries**, given as `(result, weight)` pairs, after the other
+    /// entries.
+    #[must_use]
+    pub fn entries(mut self, entries: impl IntoIterator<Item = (T, u32)>) -> Self {
+        self.extend(entries);
+        self
+    }
+
+    /// Sets the **method** with which to pick a result.
+    #[must_use]
+    pub fn method(mut self, method: RollMethod) -> Self {
+        self.table.roll_method = method;
+        self
+    }
+
+    /// Sets what to do when a [`RollMethod::PreparedRoll`] gives a result outside of the list.
+    #[must_use]
+    pub fn out_of_range(mut self, out_of_range: OutOfRangePolicy) -> Self {
+        self.table.out_of_range = out_of_range;
+        self
+    }
+
+    /// Returns the built [`RollToProcess`].
+    #[must_use]
+    pub fn build(self) -> RollToProcess<T> {
+        self.table
+    }
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Table<T>> for RollToProcess<T> {
+    fn from(table: Table<T>) -> Self {
+        table.build()
+    }
+}
+
+impl<T> Extend<(T, u32)> for Table<T> {
+    fn extend<I: IntoIterator<Item = (T, u32)>>(&mut self, entries: I) {
+        self.table.extend(entries);
+    }
+}
+
+impl<T> FromIterator<(T, u32)> for Table<T> {
+    fn from_iter<I: IntoIterator<Item = (T, u32)>>(entries: I) -> Self {
+        Self::new().entries(entries)
+    }
+}
+
+impl<T> Extend<(T, u32)> for RollToProcess<T> {
+    fn extend<I: IntoIterator<Item = (T, u32)>>(&mut self, entries: I) {
+        self.possible_results.extend(
+            entries
+                .into_iter()
+                .map(|(result, weight)| WeightedResult::new(result, weight)),
+        );
+    }
+}
+
+/// Collects `(result, weight)` pairs into a table picked with [`RollMethod::SimpleRoll`].
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let table: RollToProcess<&str> = [("a", 5), ("b", 1)].into_iter().collect();
+/// assert_eq!(table.possible_results[1].weight, 1);
+/// ```
+impl<T> FromIterator<(T, u32)> for RollToProcess<T> {
+    fn from_iter<I: IntoIterator<Item = (T, u32)>>(entries: I) -> Self {
+        Table::from_iter(entries).build()
+    }
+}
+
+/// Writes a [`RollToProcess`] as `result => weight` entries, optionally followed by a `;` and
+/// the method picking them, written like [`RollMethod::notation`] gives it: `2d6`, `3d6+2`,
+/// `gaussian(3)`, `triangular(peak=2)`... Tables are picked with [`RollMethod::SimpleRoll`]
+/// without it.
+///
+/// With the `derive` feature, the method is checked when the macro expands and an invalid one
+/// fails to compile. Without it, the method is only parsed when the table is built:
+/// [`crate::try_table!`] returns the error instead of panicking.
+///
+/// # Panics
+///
+/// Without the `derive` feature, panics if the method isn't a valid notation.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let table = table! { "a" => 5, "b" => 1; 2d6 };
+/// assert_eq!(table.roll_method, RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 0)));
+/// assert_eq!(table.possible_results[0].weight, 5);
+///
+/// let coin = table! { "heads" => 1, "tails" => 1 };
+/// assert_eq!(coin.roll_method, RollMethod::SimpleRoll);
+/// ```
+#[macro_export]
+macro_rules! table {
+    ($($result:expr => $weight:expr),* $(,)?; $($method:tt)+) => {
+        $crate::RollToProcess::new(
+            ::std::vec![$($crate::WeightedResult::new($result, $weight)),*],
+            $crate::__table_method!($crate; $($method)+),
+        )
+    };
+    ($($result:expr => $weight:expr),* $(,)?) => {
+        $crate::RollToProcess::new(
+            ::std::vec![$($crate::WeightedResult::new($result, $weight)),*],
+            $crate::RollMethod::SimpleRoll,
+        )
+    };
+}
+
+/// Parses the method of a [`crate::table!`] when the table is built, without the `derive` feature
+/// to check it when the macro expands.
+#[cfg(not(feature = "derive"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __table_method {
+    ($krate:path; $($method:tt)+) => {
+        <$crate::RollMethod as ::std::str::FromStr>::from_str(stringify!($($method)+))
+            .expect(concat!("invalid roll method ", stringify!($($method)+)))
+    };
+}
+
+/// Writes a [`RollToProcess`] like [`crate::table!`] does, returning the
+/// [`crate::ParseRollError`] met if the method isn't a valid notation instead of panicking.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let table = try_table! { "a" => 5, "b" => 1; gaussian(3) }.unwrap();
+/// assert_eq!(table.roll_method, RollMethod::GaussianRoll(3));
+///
+/// let sideways: Result<RollToProcess<&str>, _> = try_table! { "a" => 1; sideways };
+/// assert!(sideways.is_err());
+/// ```
+#[macro_export]
+macro_rules! try_table {
+    ($($result:expr => $weight:expr),* $(,)?; $($method:tt)+) => {
+        <$crate::RollMethod as ::std::str::FromStr>::from_str(stringify!($($method)+)).map(
+            |method| {
+                $crate::RollToProcess::new(
+                    ::std::vec![$($crate::WeightedResult::new($result, $weight)),*],
+                    method,
+                )
+            },
+        )
+    };
+    ($($result:expr => $weight:expr),* $(,)?) => {
+        ::std::result::Result::<_, $crate::ParseRollError>::Ok($crate::table! {
+            $($result => $weight),*
+        })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PreparedRoll, SeededDiceRoller};
+
+    #[test]
+    fn every_way_gives_the_same_table() {
+        let built = Table::new()
+            .entry("a", 5)
+            .entries([("b", 1), ("c", 0)])
+            .method(RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 1)))
+            .out_of_range(OutOfRangePolicy::Wrap)
+            .build();
+        let mut collected: RollToProcess<&str> =
+            [("a", 5), ("b", 1), ("c", 0)].into_iter().collect();
+        collected.roll_method = RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 1));
+        let written = crate::table! { "a" => 5, "b" => 1, "c" => 0; 2d6+1 }
+            .with_out_of_range(OutOfRangePolicy::Wrap);
+
+        for table in [&collected, &written] {
+            let entries = |t: &RollToProcess<&'static str>| {
+                t.possible_results
+                    .iter()
+                    .map(|e| (e.result, e.weight))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(entries(table), entries(&built));
+            assert_eq!(table.roll_method, built.roll_method);
+        }
+        assert_eq!(written.out_of_range, built.out_of_range);
+        assert_eq!(collected.out_of_range, OutOfRangePolicy::default());
+
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut same_rng = rng.clone();
+        for _ in 0..20 {
+            assert_eq!(rng.get_result(&built), same_rng.get_result(&written));
+        }
+    }
+
+    #[test]
+    fn macro_methods_follow_the_notation() {
+        let method = |table: RollToProcess<u8>| table.roll_method;
+        assert_eq!(
+            method(crate::table! { 1 => 1, 2 => 3; gaussian(3) }),
+            RollMethod::GaussianRoll(3)
+        );
+        assert_eq!(
+            method(crate::table! { 1 => 1, 2 => 3, ; triangular(peak = 1) }),
+            RollMethod::Triangular { peak: 1 }
+        );
+        assert_eq!(method(crate::table! { 1 => 1, }), RollMethod::SimpleRoll);
+        let empty: RollToProcess<u8> = crate::table! {};
+        assert!(empty.possible_results.is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "derive"))]
+    #[should_panic(expected = "invalid roll method")]
+    fn invalid_macro_methods_panic() {
+        let _: RollToProcess<u8> = crate::table! { 1 => 1; sideways };
+    }
+
+    #[test]
+    fn invalid_macro_methods_can_be_reported() {
+        let sideways: Result<RollToProcess<u8>, _> = crate::try_table! { 1 => 1; sideways };
+        assert_eq!(
+            sideways.map(|t| t.roll_method),
+            Err("sideways".parse::<RollMethod>().unwrap_err())
+        );
+        assert_eq!(
+            crate::try_table! { 1 => 1, 2 => 3; 2d6+1 }.map(|t: RollToProcess<u8>| t.roll_method),
+            Ok(RollMethod::PreparedRoll(PreparedRoll::new(2, 6, 1)))
+        );
+        assert_eq!(
+            crate::try_table! { 1 => 1, 2 => 3; d% - 2 }.map(|t: RollToProcess<u8>| t.roll_method),
+            Ok(RollMethod::PreparedRoll(PreparedRoll::new(1, 100, -2)))
+        );
+        assert_eq!(
+            crate::try_table! { 1 => 1 }.map(|t: RollToProcess<u8>| t.roll_method),
+            Ok(RollMethod::SimpleRoll)
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Urn models: tables whose weights change after each draw, reinforcing or depleting the results
+//! drawn.
+
+use crate::{OutOfRangeError, RollToProcess, SeededDiceRoller};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// How the weights of an [`UrnTable`] change after drawing one of its entries.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UrnRule {
+    /// The weights never change.
+    #[default]
+    Keep,
+    /// Adds **amount** to the weight of the drawn entry, like the balls added to a Pólya urn:
+    /// the more an entry is drawn, the more likely it gets.
+    Reinforce {
+        /// The weight added to the drawn entry.
+        amount: u32,
+    },
+    /// Removes **amount** from the weight of the drawn entry, down to 0. Removing 1 from
+    /// weights counting copies draws them without replacement.
+    Deplete {
+        /// The weight removed from the drawn entry.
+        amount: u32,
+    },
+    /// Adds **amount** to the weight of every entry but the drawn one, so that results drawn
+    /// less often catch up.
+    Spread {
+        /// The weight added to every other entry.
+        amount: u32,
+    },
+}
+
+impl Display for UrnRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrnRule::Keep => write!(f, "Keep"),
+            UrnRule::Reinforce { amount } => write!(f, "Reinforce(+{amount})"),
+            UrnRule::Deplete { amount } => write!(f, "Deplete(-{amount})"),
+            UrnRule::Spread { amount } => write!(f, "Spread(+{amount} to the others)"),
+        }
+    }
+}
+
+/// A [`RollToProcess`] whose weights change after each draw following an [`UrnRule`], or the
+/// rule given to the drawn entry in particular.
+///
+/// Weights only grow as long as they add up to [`u32::MAX`] at most, the largest die a table can
+/// be rolled with.
+///
+/// The current weights are part of the table, so serializing it saves its evolution and draws
+/// keep giving the same results once it is loaded back.
+///
+/// ```
+/// use seeded_dice_roller::*;
+///
+/// let mut rng = SeededDiceRoller::new("seed", "step");
+/// let mut marbles = UrnTable::new(
+///     RollToProcess::new(
+///         vec![WeightedResult::new("red", 2), WeightedResult::new("blue", 1)],
+///         RollMethod::SimpleRoll,
+///     ),
+///     UrnRule::Deplete { amount: 1 },
+/// );
+///
+/// let mut drawn: Vec<&str> = (0..3).map(|_| rng.draw_from_urn(&mut marbles).unwrap()).collect();
+/// drawn.sort_unstable();
+/// assert_eq!(drawn, vec!["blue", "red", "red"]);
+/// assert_eq!(rng.draw_from_urn(&mut marbles), None);
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UrnTable<T> {
+    /// The table whose entries get drawn, holding the current weights.
+    pub table: RollToProcess<T>,
+    /// How the weights change after drawing an entry without a rule of its own.
+    pub rule: UrnRule,
+    /// The entries following a rule of their own, and that rule.
+    #[serde(default)]
+    pub entry_rules: Vec<(usize, UrnRule)>,
+    /// The weights of the entries before the first draw. Entries missing from it, like the ones
+    /// added to the table afterwards or the ones of an urn saved without it, are given their
+    /// weight from before the next change.
+    #[serde(default)]
+    initial_weights: Vec<u32>,
+}
+
+impl<T> UrnTable<T> {
+    /// Creates a new [`UrnTable`] whose weights change following a **rule** after each draw.
+    #[must_use]
+    pub fn new(table: RollToProcess<T>, rule: UrnRule) -> Self {
+        let initial_weights = table.possible_results.iter().map(|e| e.weight).collect();
+        Self {
+            table,
+            rule,
+            entry_rules: Vec::new(),
+            initial_weights,
+        }
+    }
+
+    /// Makes the weights change following **rule** after drawing the entry at **index**,
+    /// replacing its previous rule.
+    #[must_use]
+    pub fn with_entry_rule(mut self, index: usize, rule: UrnRule) -> Self {
+        self.entry_rules.retain(|(i, _)| *i != index);
+        self.entry_rules.push((index, rule));
+        self
+    }
+
+    /// Returns the current weight of the entry at **index**.
+    #[must_use]
+    pub fn weight(&self, index: usize) -> u32 {
+        self.table.possible_results[index].weight
+    }
+
+    /// Returns the current weights of the entries.
+    pub fn weights(&self) -> impl Iterator<Item = u32> + '_ {
+        self.table.possible_results.iter().map(|e| e.weight)
+    }
+
+    /// Returns the rule followed after drawing the entry at **index**.
+    #[must_use]
+    pub fn rule_for(&self, index: usize) -> UrnRule {
+        self.entry_rules
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map_or(self.rule, |(_, rule)| *rule)
+    }
+
+    /// Adds **amount** to the weight of the entry at **index**, adding less if needed for the
+    /// weights of the urn to add up to [`u32::MAX`] at most.
+    pub fn add(&mut self, index: usize, amount: u32) {
+        self.remember_initial_weights();
+        let room = self.room();
+        self.table.possible_results[index].weight += amount.min(room);
+    }
+
+    /// Removes **amount** from the weight of the entry at **index**, down to 0.
+    pub fn remove(&mut self, index: usize, amount: u32) {
+        self.remember_initial_weights();
+        let weight = &mut self.table.possible_results[index].weight;
+        *weight = weight.saturating_sub(amount);
+    }
+
+    /// Gives every entry back the weight it had before the first draw.
+    pub fn reset(&mut self) {
+        self.remember_initial_weights();
+        for (entry, weight) in self
+            .table
+            .possible_results
+            .iter_mut()
+            .zip(&self.initial_weights)
+        {
+            entry.weight = *weight;
+        }
+    }
+
+    /// Returns the weight that can still be added before the weights add up to [`u32::MAX`].
+    fn room(&self) -> u32 {
+        let total: u64 = self.weights().map(u64::from).sum();
+        u32::try_from(u64::from(u32::MAX).saturating_sub(total)).unwrap_or(0)
+    }
+
+    /// Keeps the current weights of the entries missing from the initial weights as theirs.
+    fn remember_initial_weights(&mut self) {
+        let known = self.initial_weights.len();
+        if let Some(missing) = self.table.possible_results.get(known..) {
+            self.initial_weights
+                .extend(missing.iter().map(|e| e.weight));
+        }
+    }
+
+    /// Changes the weights after drawing the entry at **drawn**.
+    fn apply(&mut self, drawn: usize) {
+        match self.rule_for(drawn) {
+            UrnRule::Keep => {}
+            UrnRule::Reinforce { amount } => self.add(drawn, amount),
+            UrnRule::Deplete { amount } => self.remove(drawn, amount),
+            UrnRule::Spread { amount } => {
+                self.remember_initial_weights();
+                let mut room = self.room();
+                for (index, entry) in self.table.possible_results.iter_mut().enumerate() {
+                    if index != drawn {
+                        let added = amount.min(room);
+                        entry.weight += added;
+                        room -= added;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> Display for UrnTable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "UrnTable {{ {} choices, rule: {}, method: {} }}",
+            self.table.possible_results.len(),
+            self.rule,
+            self.table.roll_method
+        )
+    }
+}
+
+impl SeededDiceRoller {
+    /// Returns the index of the result of a random selection in an **urn** using its current
+    /// weights, then changes them following the rule of the drawn entry. Returns None once every
+    /// weight is 0, or when the roll is out of range of an urn with an
+    /// [`crate::OutOfRangePolicy::Error`] policy.
+    pub fn draw_index_from_urn<T>(&mut self, urn: &mut UrnTable<T>) -> Option<usize> {
+        self.try_draw_index_from_urn(urn).ok().flatten()
+    }
+
+    /// Returns the index of the result of a random selection in an **urn** like
+    /// [`SeededDiceRoller::draw_index_from_urn`] does, but reports out of range rolls when its
+    /// [`crate::OutOfRangePolicy`] is [`crate::OutOfRangePolicy::Error`]. Returns `Ok(None)` once
+    /// every weight is 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutOfRangeError`] if a [`crate::RollMethod::PreparedRoll`] gives a result
+    /// outside of the list while its policy is [`crate::OutOfRangePolicy::Error`]. The weights
+    /// don't change then.
+    pub fn try_draw_index_from_urn<T>(
+        &mut self,
+        urn: &mut UrnTable<T>,
+    ) -> Result<Option<usize>, OutOfRangeError> {
+        let entries = &urn.table.possible_results;
+        let result = self.pick_in_context(
+            &|i| entries[i].weight,
+            entries.len(),
+            &urn.table.roll_method,
+            urn.table.out_of_range,
+        )?;
+        if let Some(index) = result {
+            urn.apply(index);
+            trace!("      urn: {:?}", urn.weights().collect::<Vec<u32>>());
+        }
+        Ok(result)
+    }
+
+    /// Returns the result of a random selection in an **urn**, then changes its weights like
+    /// [`SeededDiceRoller::draw_index_from_urn`] does.
+    pub fn draw_from_urn<T: Clone>(&mut self, urn: &mut UrnTable<T>) -> Option<T> {
+        self.draw_index_from_urn(urn)
+            .map(|index| urn.table.possible_results[index].result.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutOfRangePolicy, PreparedRoll, RollMethod, WeightedResult};
+
+    /// Returns an urn holding one ball of each colour.
+    fn urn(rule: UrnRule) -> UrnTable<&'static str> {
+        UrnTable::new(
+            RollToProcess::new(
+                vec![
+                    WeightedResult::new("red", 1),
+                    WeightedResult::new("green", 1),
+                    WeightedResult::new("blue", 1),
+                ],
+                RollMethod::SimpleRoll,
+            ),
+            rule,
+        )
+    }
+
+    #[test]
+    fn rules_change_the_weights_after_each_draw() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut reinforced = urn(UrnRule::Reinforce { amount: 2 });
+        let drawn = rng.draw_index_from_urn(&mut reinforced).unwrap();
+        assert_eq!(reinforced.weight(drawn), 3);
+        assert_eq!(reinforced.weights().sum::<u32>(), 5);
+
+        let mut spread = urn(UrnRule::Spread { amount: 1 });
+        let drawn = rng.draw_index_from_urn(&mut spread).unwrap();
+        let weights: Vec<u32> = spread.weights().collect();
+        assert_eq!(weights[drawn], 1);
+        assert_eq!(weights.iter().sum::<u32>(), 5);
+
+        let mut kept = urn(UrnRule::Keep);
+        rng.draw_index_from_urn(&mut kept);
+        assert_eq!(kept.weights().collect::<Vec<u32>>(), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn depleted_urns_run_out() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut depleted = urn(UrnRule::Deplete { amount: 1 });
+        let mut drawn: Vec<&str> = (0..3)
+            .map(|_| rng.draw_from_urn(&mut depleted).unwrap())
+            .collect();
+        drawn.sort_unstable();
+        assert_eq!(drawn, vec!["blue", "green", "red"]);
+        assert_eq!(rng.draw_from_urn(&mut depleted), None);
+
+        depleted.reset();
+        assert_eq!(depleted.weights().collect::<Vec<u32>>(), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn entry_rules_override_the_rule_of_the_urn() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut urn = urn(UrnRule::Reinforce { amount: 1 })
+            .with_entry_rule(2, UrnRule::Deplete { amount: 5 })
+            .with_entry_rule(2, UrnRule::Keep);
+        assert_eq!(urn.rule_for(0), UrnRule::Reinforce { amount: 1 });
+        assert_eq!(urn.rule_for(2), UrnRule::Keep);
+        let mut draws = [0; 3];
+        for _ in 0..30 {
+            draws[rng.draw_index_from_urn(&mut urn).unwrap()] += 1;
+        }
+        assert!(draws[2] > 0);
+        assert_eq!(
+            urn.weights().collect::<Vec<u32>>(),
+            vec![1 + draws[0], 1 + draws[1], 1]
+        );
+    }
+
+    #[test]
+    fn polya_urns_get_richer() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut urn = urn(UrnRule::Reinforce { amount: 1 });
+        let drawn: Vec<&str> = (0..12)
+            .map(|_| rng.draw_from_urn(&mut urn).unwrap())
+            .collect();
+        assert_eq!(
+            drawn,
+            vec![
+                "blue", "green", "red", "green", "blue", "green", "green", "green", "green",
+                "green", "blue", "green"
+            ]
+        );
+        assert_eq!(urn.weights().sum::<u32>(), 15);
+    }
+
+    #[test]
+    fn weights_never_add_up_to_more_than_a_u32() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut reinforced = urn(UrnRule::Reinforce {
+            amount: u32::MAX / 2,
+        });
+        let mut spread = urn(UrnRule::Spread { amount: u32::MAX });
+        for _ in 0..10 {
+            assert!(rng.draw_index_from_urn(&mut reinforced).is_some());
+            assert!(rng.draw_index_from_urn(&mut spread).is_some());
+        }
+        for urn in [reinforced, spread] {
+            assert_eq!(
+                urn.weights().map(u64::from).sum::<u64>(),
+                u64::from(u32::MAX)
+            );
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn saved_urns_keep_their_weights() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut urn = UrnTable::new(
+            RollToProcess::new(
+                vec![WeightedResult::new(1, 5), WeightedResult::new(2, 5)],
+                RollMethod::SimpleRoll,
+            ),
+            UrnRule::Reinforce { amount: 3 },
+        );
+        for _ in 0..4 {
+            rng.draw_index_from_urn(&mut urn);
+        }
+        let saved = serde_json::to_string(&urn).unwrap();
+        let mut loaded: UrnTable<i32> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(
+            loaded.weights().collect::<Vec<u32>>(),
+            urn.weights().collect::<Vec<u32>>()
+        );
+
+        let mut other_rng = rng.clone();
+        for _ in 0..10 {
+            assert_eq!(
+                rng.draw_from_urn(&mut urn),
+                other_rng.draw_from_urn(&mut loaded)
+            );
+        }
+        loaded.reset();
+        assert_eq!(loaded.weights().collect::<Vec<u32>>(), vec![5, 5]);
+    }
+
+    #[test]
+    fn entries_missing_from_the_initial_weights_are_reset_too() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut loaded: UrnTable<i32> = serde_json::from_str(
+            r#"{
+                "table": {
+                    "possible_results": [{ "result": 1, "weight": 4 }],
+                    "roll_method": "SimpleRoll"
+                },
+                "rule": { "Reinforce": { "amount": 3 } }
+            }"#,
+        )
+        .unwrap();
+        loaded
+            .table
+            .possible_results
+            .push(WeightedResult::new(2, 6));
+        for _ in 0..5 {
+            rng.draw_index_from_urn(&mut loaded);
+        }
+        assert_eq!(loaded.weights().sum::<u32>(), 25);
+        loaded.reset();
+        assert_eq!(loaded.weights().collect::<Vec<u32>>(), vec![4, 6]);
+    }
+
+    #[test]
+    fn out_of_range_rolls_are_reported() {
+        let mut rng = SeededDiceRoller::new("seed", "step");
+        let mut urn = urn(UrnRule::Deplete { amount: 1 });
+        urn.table.roll_method = RollMethod::PreparedRoll(PreparedRoll::new(1, 6, 10));
+        urn.table.out_of_range = OutOfRangePolicy::Error;
+        assert!(rng.try_draw_index_from_urn(&mut urn).is_err());
+        assert_eq!(rng.draw_index_from_urn(&mut urn), None);
+        assert_eq!(urn.weights().collect::<Vec<u32>>(), vec![1, 1, 1]);
+    }
+}